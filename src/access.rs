@@ -0,0 +1,191 @@
+// Key-based access shared by every keyed collection: hash and sorted maps by
+// key, and lists and tuples by index. The `-in` variants follow a path of keys through
+// nested collections, creating sorted maps for missing levels on the way down.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+use super::hashmap::MapKey;
+use super::sorted::SortKey;
+use super::{args, as_index, call_exp, meta, RispEnv, RispErr, RispExp};
+
+fn index(key: &RispExp, len: usize) -> Result<Option<usize>, RispErr> {
+    match key {
+        RispExp::Number(_) | RispExp::Int(_) => Ok(as_index(key).filter(|idx| *idx < len)),
+        _ => Err(RispErr::Reason(format!("expected an index, got '{}'", key))),
+    }
+}
+
+pub fn get_key(coll: &RispExp, key: &RispExp) -> Result<Option<RispExp>, RispErr> {
+    match coll {
+        RispExp::SortedMap(map) => Ok(map.get(&SortKey::new(key.clone())?).cloned()),
+        RispExp::Map(map) => Ok(map.get(&MapKey::new(key)?).cloned()),
+        RispExp::List(list) => Ok(index(key, list.len())?.map(|idx| list[idx].clone())),
+        RispExp::Tuple(values) => Ok(index(key, values.len())?.map(|idx| values[idx].clone())),
+        RispExp::Vector(xs) => {
+            let xs = xs.borrow();
+            Ok(index(key, xs.len())?.map(|idx| xs[idx].clone()))
+        },
+        _ => Err(RispErr::Reason(format!("expected a keyed collection, got '{}'", coll))),
+    }
+}
+
+pub fn assoc_key(coll: &RispExp, key: &RispExp, value: RispExp) -> Result<RispExp, RispErr> {
+    Ok(meta::carry(coll, assoc_copy(coll, key, value)?))
+}
+
+fn assoc_copy(coll: &RispExp, key: &RispExp, value: RispExp) -> Result<RispExp, RispErr> {
+    match coll {
+        RispExp::SortedMap(map) => {
+            let mut map = map.as_ref().clone();
+            map.insert(SortKey::new(key.clone())?, value);
+
+            Ok(RispExp::SortedMap(Rc::new(map)))
+        },
+        RispExp::Map(map) => {
+            let mut map = map.as_ref().clone();
+            map.insert(MapKey::new(key)?, value);
+
+            Ok(RispExp::Map(Rc::new(map)))
+        },
+        // lists can also grow by one, at the index just past their end
+        RispExp::List(list) => {
+            let mut list = list.clone();
+            match index(key, list.len() + 1)? {
+                Some(idx) if idx == list.len() => list.push(value),
+                Some(idx) => list[idx] = value,
+                None => return Err(RispErr::Reason(format!("index '{}' is out of range", key))),
+            }
+
+            Ok(RispExp::List(list))
+        },
+        RispExp::Tuple(values) => {
+            let mut values = values.as_ref().clone();
+            let idx = index(key, values.len())?
+                .ok_or(RispErr::Reason(format!("index '{}' is out of range", key)))?;
+            values[idx] = value;
+
+            Ok(RispExp::Tuple(Rc::new(values)))
+        },
+        // a changed copy, `vec-set!` changes the vector itself
+        RispExp::Vector(xs) => {
+            let mut xs = xs.borrow().clone();
+            let idx = index(key, xs.len())?
+                .ok_or(RispErr::Reason(format!("index '{}' is out of range", key)))?;
+            xs[idx] = value;
+
+            Ok(RispExp::Vector(Rc::new(RefCell::new(xs))))
+        },
+        _ => Err(RispErr::Reason(format!("expected a keyed collection, got '{}'", coll))),
+    }
+}
+
+fn path_arg(op: &str, args: &[RispExp], idx: usize) -> Result<Vec<RispExp>, RispErr> {
+    match args::get(op, args, idx, "a path list")? {
+        RispExp::List(path) => Ok(path.to_vec()),
+        RispExp::Tuple(path) => Ok(path.as_ref().clone()),
+        exp => Err(args::wrong(op, idx, "a path list", exp)),
+    }
+}
+
+fn get_in(coll: &RispExp, path: &[RispExp]) -> Result<Option<RispExp>, RispErr> {
+    let mut current = coll.clone();
+    for key in path {
+        current = match get_key(&current, key)? {
+            Some(next) => next,
+            None => return Ok(None),
+        };
+    }
+
+    Ok(Some(current))
+}
+
+fn assoc_in(coll: &RispExp, path: &[RispExp], value: RispExp) -> Result<RispExp, RispErr> {
+    match path.split_first() {
+        None => Ok(value),
+        Some((key, rest)) => {
+            let child = match get_key(coll, key)? {
+                Some(child) => child,
+                None => RispExp::SortedMap(Rc::new(BTreeMap::new())),
+            };
+
+            assoc_key(coll, key, assoc_in(&child, rest, value)?)
+        },
+    }
+}
+
+// a missing value is passed to the update function as `false`
+fn update_in(coll: &RispExp, path: &[RispExp], f_args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let f = f_args.first().ok_or(RispErr::Reason("expected an update function".to_string()))?;
+    let current = get_in(coll, path)?.unwrap_or(RispExp::Bool(false));
+
+    let mut args = vec![current];
+    args.extend(f_args[1..].iter().cloned());
+    let value = call_exp(f, args, env)?;
+
+    assoc_in(coll, path, value)
+}
+
+// `(update coll key f args...)`
+fn update(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let key = args::get("update", args, 1, "a key")?;
+    update_in(args::get("update", args, 0, "a collection")?, std::slice::from_ref(key), &args[2..], env)
+}
+
+// `(update-in coll path f args...)`
+fn update_in_args(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    const OP: &str = "update-in";
+    update_in(args::get(OP, args, 0, "a collection")?, &path_arg(OP, args, 1)?, &args[2..], env)
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert(
+        "get".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                let key = args::get("get", args, 1, "a key")?;
+                let default = args.get(2).cloned().unwrap_or(RispExp::Bool(false));
+
+                Ok(get_key(args::get("get", args, 0, "a collection")?, key)?.unwrap_or(default))
+            }
+        )
+    );
+
+    data.insert(
+        "get-in".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                let default = args.get(2).cloned().unwrap_or(RispExp::Bool(false));
+
+                Ok(get_in(args::get("get-in", args, 0, "a collection")?, &path_arg("get-in", args, 1)?)?.unwrap_or(default))
+            }
+        )
+    );
+
+    data.insert(
+        "assoc".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                match args {
+                    [coll, key, value] => assoc_key(coll, key, value.clone()),
+                    _ => Err(RispErr::Reason("expected a collection, a key and a value".to_string())),
+                }
+            }
+        )
+    );
+
+    data.insert(
+        "assoc-in".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                let value = args::get("assoc-in", args, 2, "a value")?;
+
+                assoc_in(args::get("assoc-in", args, 0, "a collection")?, &path_arg("assoc-in", args, 1)?, value.clone())
+            }
+        )
+    );
+
+    data.insert("update".to_string(), RispExp::EnvFunc(update));
+    data.insert("update-in".to_string(), RispExp::EnvFunc(update_in_args));
+}
@@ -0,0 +1,209 @@
+// Checking the arguments builtins are called with, so a wrong one is reported
+// the same way everywhere: by the builtin it was passed to, its position,
+// counting from 1, and what it was instead.
+//
+//     `>` expected a number as argument 2, got a string "a"
+//
+// It's public for programs embedding risp, so the builtins they define report
+// errors the same way:
+//
+//     fn hypot(xs: &[RispExp]) -> Result<RispExp, RispErr> {
+//         args::exactly("hypot", xs, 2)?;
+//         let ns = args::nums("hypot", xs)?;
+//         Ok(RispExp::Number(ns[0].hypot(ns[1])))
+//     }
+//
+// `(assert-arity 'name args n [max])` does the same for risp fns taking a
+// list of arguments, between `n` and `max` of them with a `max`.
+//
+// The errors are `RispErr::Args`, which keeps what went wrong apart from the
+// message so it can be translated and explained without reading the English.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use super::{as_float, as_index, RispErr, RispExp};
+
+// what kind of value it is, for errors
+pub fn type_name(exp: &RispExp) -> &'static str {
+    match exp {
+        RispExp::Bool(_) => "a bool",
+        RispExp::Symbol(_) => "a symbol",
+        RispExp::Keyword(_) => "a keyword",
+        RispExp::Number(_) => "a float",
+        RispExp::Int(_) => "an int",
+        #[cfg(feature = "bignum")]
+        RispExp::Exact(_) => "an exact number",
+        RispExp::Str(_) => "a string",
+        RispExp::Char(_) => "a character",
+        RispExp::List(list) if list.is_empty() => "nil",
+        RispExp::List(_) => "a list",
+        RispExp::Vector(_) => "a vector",
+        RispExp::Func(_) | RispExp::EnvFunc(_) | RispExp::Lambda(_) | RispExp::Instrumented(_) | RispExp::Generic(_) => "a fn",
+        RispExp::Generator(_) => "a generator",
+        RispExp::Resource(_) | RispExp::Weak(_) => "a resource",
+        RispExp::Tuple(_) => "a tuple",
+        RispExp::SortedMap(_) | RispExp::Map(_) => "a map",
+        RispExp::SortedSet(_) => "a set",
+        RispExp::Queue(_) => "a queue",
+        RispExp::Stack(_) => "a stack",
+        RispExp::Zipper(_) => "a zipper",
+        RispExp::Object(_) => "an object",
+        RispExp::Tagged(_) => "a tagged value",
+        RispExp::Env(_) => "an environment",
+    }
+}
+
+#[derive(Debug)]
+pub enum ArgErr {
+    // something other than `expected` as argument `idx`, counting from 0, or
+    // nothing at all
+    Wrong { op: String, idx: usize, expected: String, got: Option<RispExp> },
+    // a number of arguments outside of what `op` takes
+    Count { op: String, bound: Bound, expected: usize, got: usize },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bound {
+    Exactly,
+    AtLeast,
+    AtMost,
+}
+
+// how an argument that was given is described, `a string "a"`
+fn describe(got: &RispExp) -> String {
+    match got {
+        RispExp::List(list) if list.is_empty() => "nil".to_string(),
+        _ => format!("{} {}", type_name(got), got),
+    }
+}
+
+fn count(n: usize) -> String {
+    match n {
+        1 => "1 argument".to_string(),
+        _ => format!("{} arguments", n),
+    }
+}
+
+impl fmt::Display for ArgErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArgErr::Wrong { op, idx, expected, got: Some(got) } =>
+                write!(f, "`{}` expected {} as argument {}, got {}", op, expected, idx + 1, describe(got)),
+            ArgErr::Wrong { op, idx, expected, got: None } =>
+                write!(f, "`{}` expected {} as argument {}", op, expected, idx + 1),
+            ArgErr::Count { op, bound, expected, got } => {
+                let bound = match bound {
+                    Bound::Exactly => "",
+                    Bound::AtLeast => "at least ",
+                    Bound::AtMost => "at most ",
+                };
+                write!(f, "`{}` expected {}{}, got {}", op, bound, count(*expected), got)
+            },
+        }
+    }
+}
+
+pub fn wrong(op: &str, idx: usize, expected: &str, got: &RispExp) -> RispErr {
+    RispErr::Args(ArgErr::Wrong { op: op.to_string(), idx, expected: expected.to_string(), got: Some(got.clone()) })
+}
+
+pub fn missing(op: &str, idx: usize, expected: &str) -> RispErr {
+    RispErr::Args(ArgErr::Wrong { op: op.to_string(), idx, expected: expected.to_string(), got: None })
+}
+
+fn count_err(op: &str, bound: Bound, expected: usize, got: usize) -> RispErr {
+    RispErr::Args(ArgErr::Count { op: op.to_string(), bound, expected, got })
+}
+
+pub fn exactly(op: &str, args: &[RispExp], n: usize) -> Result<(), RispErr> {
+    match args.len() == n {
+        true => Ok(()),
+        false => Err(count_err(op, Bound::Exactly, n, args.len())),
+    }
+}
+
+pub fn at_least(op: &str, args: &[RispExp], n: usize) -> Result<(), RispErr> {
+    match args.len() >= n {
+        true => Ok(()),
+        false => Err(count_err(op, Bound::AtLeast, n, args.len())),
+    }
+}
+
+pub fn at_most(op: &str, args: &[RispExp], n: usize) -> Result<(), RispErr> {
+    match args.len() <= n {
+        true => Ok(()),
+        false => Err(count_err(op, Bound::AtMost, n, args.len())),
+    }
+}
+
+// the argument at `idx`, counting from 0
+pub fn get<'a>(op: &str, args: &'a [RispExp], idx: usize, expected: &str) -> Result<&'a RispExp, RispErr> {
+    args.get(idx).ok_or_else(|| missing(op, idx, expected))
+}
+
+// a number of any kind
+pub fn number<'a>(op: &str, args: &'a [RispExp], idx: usize) -> Result<&'a RispExp, RispErr> {
+    let exp = get(op, args, idx, "a number")?;
+    match as_float(exp) {
+        Some(_) => Ok(exp),
+        None => Err(wrong(op, idx, "a number", exp)),
+    }
+}
+
+pub fn float(op: &str, args: &[RispExp], idx: usize) -> Result<f64, RispErr> {
+    let exp = get(op, args, idx, "a number")?;
+    as_float(exp).ok_or_else(|| wrong(op, idx, "a number", exp))
+}
+
+// every argument as a float
+pub fn nums(op: &str, args: &[RispExp]) -> Result<Vec<f64>, RispErr> {
+    (0..args.len()).map(|idx| float(op, args, idx)).collect()
+}
+
+// a whole number that isn't negative
+pub fn index(op: &str, args: &[RispExp], idx: usize) -> Result<usize, RispErr> {
+    let exp = get(op, args, idx, "an index")?;
+    as_index(exp).ok_or_else(|| wrong(op, idx, "an index", exp))
+}
+
+pub fn string<'a>(op: &str, args: &'a [RispExp], idx: usize) -> Result<&'a str, RispErr> {
+    match get(op, args, idx, "a string")? {
+        RispExp::Str(s) => Ok(s),
+        exp => Err(wrong(op, idx, "a string", exp)),
+    }
+}
+
+// a list, nil being the empty one
+pub fn list<'a>(op: &str, args: &'a [RispExp], idx: usize) -> Result<&'a [RispExp], RispErr> {
+    match get(op, args, idx, "a list")? {
+        RispExp::List(xs) => Ok(xs),
+        exp => Err(wrong(op, idx, "a list", exp)),
+    }
+}
+
+// `(assert-arity 'name args n [max])`, giving back `args`
+fn assert_arity(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    const OP: &str = "assert-arity";
+    let name = match get(OP, args, 0, "a quoted name")? {
+        RispExp::Symbol(name) => name,
+        exp => return Err(wrong(OP, 0, "a quoted name", exp)),
+    };
+    let xs = list(OP, args, 1)?;
+    let min = index(OP, args, 2)?;
+
+    match args.get(3) {
+        None => exactly(name, xs, min)?,
+        Some(_) => {
+            let max = index(OP, args, 3)?;
+            at_least(name, xs, min)?;
+            at_most(name, xs, max)?;
+        },
+    }
+
+    Ok(args[1].clone())
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert("assert-arity".to_string(), RispExp::Func(assert_arity));
+}
@@ -0,0 +1,95 @@
+// `(beep)` rings the terminal bell and `(play-tone freq ms)` plays a sine
+// wave, behind the `audio` feature. Tones are written as a WAV file and
+// handed to the platform's player, `afplay` on macOS and `aplay` or `paplay`
+// elsewhere, and Windows uses the console beep. Both block until the sound is
+// done, so a melody is a sequence of calls.
+
+#[cfg(not(windows))]
+use std::{env, fs, process::{Command, Stdio}};
+
+use super::{as_float, output, RispErr, RispExp};
+
+#[cfg(not(windows))]
+const SAMPLE_RATE: u32 = 44100;
+
+pub fn beep(_args: &[RispExp]) -> Result<RispExp, RispErr> {
+    output::write("\x07")?;
+    Ok(RispExp::Bool(true))
+}
+
+#[cfg(not(windows))]
+// 16 bit mono PCM, faded in and out over a few milliseconds to avoid clicks
+fn wav(freq: f64, ms: f64) -> Vec<u8> {
+    let samples = (SAMPLE_RATE as f64 * ms / 1000.0) as u32;
+    let fade = (SAMPLE_RATE / 200).min(samples / 2).max(1) as f64;
+
+    let mut out = vec![];
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + samples * 2).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    out.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    out.extend_from_slice(&2u16.to_le_bytes());
+    out.extend_from_slice(&16u16.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&(samples * 2).to_le_bytes());
+
+    for n in 0..samples {
+        let t = n as f64 / SAMPLE_RATE as f64;
+        let envelope = (n as f64 / fade).min((samples - n) as f64 / fade).min(1.0);
+        let sample = (t * freq * std::f64::consts::TAU).sin() * envelope * 0.5 * i16::MAX as f64;
+        out.extend_from_slice(&(sample as i16).to_le_bytes());
+    }
+
+    out
+}
+
+#[cfg(not(windows))]
+fn play(freq: f64, ms: f64) -> Result<(), RispErr> {
+    let path = env::temp_dir().join(format!("risp-tone-{}.wav", std::process::id()));
+    fs::write(&path, wav(freq, ms)).map_err(|e| RispErr::Reason(format!("could not write tone: {}", e)))?;
+
+    let players: [(&str, &[&str]); 3] = [("afplay", &[]), ("aplay", &["-q"]), ("paplay", &[])];
+    let played = players.iter().any(|(player, args)| {
+        Command::new(player)
+            .args(*args)
+            .arg(&path)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false)
+    });
+    let _ = fs::remove_file(&path);
+
+    match played {
+        true => Ok(()),
+        false => Err(RispErr::Reason("could not play tone, expected one of afplay, aplay or paplay".to_string())),
+    }
+}
+
+#[cfg(windows)]
+fn play(freq: f64, ms: f64) -> Result<(), RispErr> {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn Beep(freq: u32, duration: u32) -> i32;
+    }
+
+    match unsafe { Beep(freq as u32, ms as u32) } {
+        0 => Err(RispErr::Reason("could not play tone".to_string())),
+        _ => Ok(()),
+    }
+}
+
+pub fn play_tone(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    match args.iter().map(as_float).collect::<Option<Vec<f64>>>().as_deref() {
+        Some([freq, ms]) if (20.0..=20000.0).contains(freq) && *ms >= 0.0 => {
+            play(*freq, *ms)?;
+            Ok(RispExp::Bool(true))
+        },
+        _ => Err(RispErr::Reason("expected a frequency from 20 to 20000 hz and a duration in ms".to_string())),
+    }
+}
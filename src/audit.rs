@@ -0,0 +1,93 @@
+// An audit trail of the builtins that touch the world outside the
+// interpreter: files, the network, the terminal and sound. While a hook is
+// installed each call to one of them is passed to it, with the arguments as
+// given, before it runs. `--audit-log path` writes them to a file, and
+// embedders get them from `Interpreter::set_audit_hook`.
+//
+// Builtins are recognised by the function itself rather than their name, so
+// calls through another binding like `(def read slurp)` are audited too.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::time::SystemTime;
+
+use super::{edn, effect, producing, RispErr, RispExp};
+
+const AUDITED: [&str; 20] = [
+    "open", "close", "read-line", "write", "slurp", "with-lines", "glob", "process-files", "require", "spit",
+    "emit-file", "remote-eval",
+    "write-ppm", "turtle-save",
+    "beep", "play-tone", "clear-screen", "move-cursor", "raw-mode", "read-key",
+];
+
+pub struct AuditEntry {
+    pub builtin: String,
+    // each argument as risp source, or as it prints when it can't be written
+    pub args: Vec<String>,
+    pub at: SystemTime,
+}
+
+pub type AuditHook = Box<dyn FnMut(&AuditEntry)>;
+
+thread_local! {
+    static HOOK: RefCell<Option<AuditHook>> = RefCell::new(None);
+    static BUILTINS: RefCell<HashMap<usize, &'static str>> = RefCell::new(HashMap::new());
+}
+
+type Builtin = fn(&[RispExp]) -> Result<RispExp, RispErr>;
+
+// remembers which functions in the freshly built environment are audited
+pub fn register(data: &HashMap<String, RispExp>) {
+    BUILTINS.with(|builtins| {
+        let mut builtins = builtins.borrow_mut();
+        for name in AUDITED {
+            if let Some(RispExp::Func(f)) = data.get(name) {
+                builtins.insert(*f as usize, name);
+            }
+        }
+    });
+}
+
+// installs `hook`, returning the one it replaces
+pub fn set_hook(hook: Option<AuditHook>) -> Option<AuditHook> {
+    HOOK.with(|current| current.replace(hook))
+}
+
+pub fn record(builtin: &str, args: &[RispExp]) {
+    HOOK.with(|hook| {
+        if let Some(hook) = hook.borrow_mut().as_mut() {
+            let args = args.iter().map(|arg| edn::write_exp(arg).unwrap_or_else(|_| arg.to_string())).collect();
+            hook(&AuditEntry { builtin: builtin.to_string(), args, at: SystemTime::now() });
+        }
+    });
+}
+
+// calls a builtin function, recording the call first when it's audited
+pub fn call(f: Builtin, args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let audited = || BUILTINS.with(|builtins| builtins.borrow().get(&(f as usize)).copied());
+    if HOOK.with(|hook| hook.borrow().is_some()) {
+        if let Some(name) = audited() {
+            record(name, args);
+        }
+    }
+    if producing() {
+        if let Some(name) = audited() {
+            effect(&format!("call '{}'", name))?;
+        }
+    }
+
+    f(args)
+}
+
+// `--audit-log path`, one `unix-ms builtin args...` line per call
+pub fn log_to(path: &str) -> Result<(), String> {
+    let mut file = File::create(path).map_err(|e| format!("could not write '{}': {}", path, e))?;
+    set_hook(Some(Box::new(move |entry: &AuditEntry| {
+        let ms = entry.at.duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+        let _ = writeln!(file, "{} {} {}", ms, entry.builtin, entry.args.join(" "));
+    })));
+
+    Ok(())
+}
@@ -0,0 +1,284 @@
+// Exact numbers for the `bignum` feature: integers of any size and the
+// rationals between them. A `Rational` is always in lowest terms with a
+// positive denominator, so equal values have equal parts, and a whole number
+// is one with a denominator of 1.
+//
+// Magnitudes are little-endian limbs of 32 bits with no zero limbs at the
+// top, so zero is no limbs at all.
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+
+type Limbs = Vec<u32>;
+
+fn trimmed(mut xs: Limbs) -> Limbs {
+    while xs.last() == Some(&0) {
+        xs.pop();
+    }
+    xs
+}
+
+fn from_u64(n: u64) -> Limbs {
+    trimmed(vec![n as u32, (n >> 32) as u32])
+}
+
+fn cmp_mag(a: &[u32], b: &[u32]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_mag(a: &[u32], b: &[u32]) -> Limbs {
+    let mut out = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0u64;
+    for idx in 0..a.len().max(b.len()) {
+        let sum = *a.get(idx).unwrap_or(&0) as u64 + *b.get(idx).unwrap_or(&0) as u64 + carry;
+        out.push(sum as u32);
+        carry = sum >> 32;
+    }
+    out.push(carry as u32);
+
+    trimmed(out)
+}
+
+// `a - b` where `a` is at least `b`
+fn sub_mag(a: &[u32], b: &[u32]) -> Limbs {
+    let mut out = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (idx, x) in a.iter().enumerate() {
+        let mut diff = *x as i64 - *b.get(idx).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if diff < 0 {
+            diff += 1 << 32;
+            borrow = 1;
+        }
+        out.push(diff as u32);
+    }
+
+    trimmed(out)
+}
+
+fn mul_mag(a: &[u32], b: &[u32]) -> Limbs {
+    let mut out = vec![0u32; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, y) in b.iter().enumerate() {
+            let product = *x as u64 * *y as u64 + out[i + j] as u64 + carry;
+            out[i + j] = product as u32;
+            carry = product >> 32;
+        }
+        out[i + b.len()] = carry as u32;
+    }
+
+    trimmed(out)
+}
+
+// the quotient and remainder of dividing by a single limb
+fn divrem_small(a: &[u32], b: u32) -> (Limbs, u32) {
+    let mut out = vec![0u32; a.len()];
+    let mut rem = 0u64;
+    for idx in (0..a.len()).rev() {
+        let cur = (rem << 32) | a[idx] as u64;
+        out[idx] = (cur / b as u64) as u32;
+        rem = cur % b as u64;
+    }
+
+    (trimmed(out), rem as u32)
+}
+
+// long division a bit at a time, `b` isn't zero
+fn divrem_mag(a: &[u32], b: &[u32]) -> (Limbs, Limbs) {
+    if let [b] = b {
+        let (q, r) = divrem_small(a, *b);
+        return (q, from_u64(r as u64))
+    }
+    if cmp_mag(a, b) == Ordering::Less {
+        return (vec![], a.to_vec())
+    }
+
+    let mut q = vec![0u32; a.len()];
+    let mut r: Limbs = vec![];
+    for bit in (0..a.len() * 32).rev() {
+        // r = r * 2 + the next bit of a
+        r = add_mag(&r, &r);
+        if (a[bit / 32] >> (bit % 32)) & 1 == 1 {
+            r = add_mag(&r, &[1]);
+        }
+        if cmp_mag(&r, b) != Ordering::Less {
+            r = sub_mag(&r, b);
+            q[bit / 32] |= 1 << (bit % 32);
+        }
+    }
+
+    (trimmed(q), r)
+}
+
+fn gcd_mag(a: &[u32], b: &[u32]) -> Limbs {
+    let (mut a, mut b) = (a.to_vec(), b.to_vec());
+    while !b.is_empty() {
+        let (_, r) = divrem_mag(&a, &b);
+        a = b;
+        b = r;
+    }
+    a
+}
+
+fn mag_to_f64(xs: &[u32]) -> f64 {
+    xs.iter().rev().fold(0.0, |acc, x| acc * 4294967296.0 + *x as f64)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rational {
+    negative: bool,
+    num: Limbs,
+    den: Limbs,
+}
+
+impl Rational {
+    fn new(negative: bool, num: Limbs, den: Limbs) -> Rational {
+        let g = gcd_mag(&num, &den);
+        let (num, den) = match g[..] {
+            [1] => (num, den),
+            _ => (divrem_mag(&num, &g).0, divrem_mag(&den, &g).0),
+        };
+        // zero has no sign
+        Rational { negative: negative && !num.is_empty(), num, den }
+    }
+
+    pub fn from_i64(n: i64) -> Rational {
+        Rational { negative: n < 0, num: from_u64(n.unsigned_abs()), den: vec![1] }
+    }
+
+    // a whole number written in decimal, with an optional sign
+    pub fn parse_integer(s: &str) -> Option<Rational> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None
+        }
+
+        let mut num: Limbs = vec![];
+        for d in digits.bytes() {
+            num = add_mag(&mul_mag(&num, &[10]), &from_u64((d - b'0') as u64));
+        }
+        Some(Rational::new(negative, num, vec![1]))
+    }
+
+    // `n/d`, like `1/3`
+    pub fn parse(s: &str) -> Option<Rational> {
+        match s.split_once('/') {
+            Some((num, den)) if !den.starts_with(['-', '+']) => Rational::parse_integer(num)?.div(&Rational::parse_integer(den)?),
+            Some(_) => None,
+            None => Rational::parse_integer(s),
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.den == [1]
+    }
+
+    // the same number as an i64, when it's a whole one that fits
+    pub fn to_i64(&self) -> Option<i64> {
+        if !self.is_integer() || self.num.len() > 2 {
+            return None
+        }
+        let n = self.num.iter().rev().fold(0u64, |acc, x| (acc << 32) | *x as u64);
+        match self.negative {
+            true if n == 1 << 63 => Some(i64::MIN),
+            true => i64::try_from(n).ok().map(|n| -n),
+            false => i64::try_from(n).ok(),
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let n = mag_to_f64(&self.num) / mag_to_f64(&self.den);
+        if self.negative { -n } else { n }
+    }
+
+    fn signed_add(&self, other: &Rational, negate_other: bool) -> Rational {
+        let a = mul_mag(&self.num, &other.den);
+        let b = mul_mag(&other.num, &self.den);
+        let den = mul_mag(&self.den, &other.den);
+        let other_negative = other.negative != negate_other;
+
+        if self.negative == other_negative {
+            return Rational::new(self.negative, add_mag(&a, &b), den)
+        }
+        match cmp_mag(&a, &b) {
+            Ordering::Less => Rational::new(other_negative, sub_mag(&b, &a), den),
+            _ => Rational::new(self.negative, sub_mag(&a, &b), den),
+        }
+    }
+
+    pub fn add(&self, other: &Rational) -> Rational {
+        self.signed_add(other, false)
+    }
+
+    pub fn sub(&self, other: &Rational) -> Rational {
+        self.signed_add(other, true)
+    }
+
+    pub fn mul(&self, other: &Rational) -> Rational {
+        Rational::new(self.negative != other.negative, mul_mag(&self.num, &other.num), mul_mag(&self.den, &other.den))
+    }
+
+    // `None` when dividing by zero
+    pub fn div(&self, other: &Rational) -> Option<Rational> {
+        if other.num.is_empty() {
+            return None
+        }
+        Some(Rational::new(self.negative != other.negative, mul_mag(&self.num, &other.den), mul_mag(&self.den, &other.num)))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Rational) -> Ordering {
+        let by_magnitude = cmp_mag(&mul_mag(&self.num, &other.den), &mul_mag(&other.num, &self.den));
+        match (self.negative, other.negative) {
+            (false, false) => by_magnitude,
+            (true, true) => by_magnitude.reverse(),
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+        }
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Rational) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn write_mag(f: &mut fmt::Formatter, xs: &[u32]) -> fmt::Result {
+    // nine decimal digits at a time, the most significant last
+    let mut chunks = vec![];
+    let mut rest = xs.to_vec();
+    while !rest.is_empty() {
+        let (q, r) = divrem_small(&rest, 1_000_000_000);
+        chunks.push(r);
+        rest = q;
+    }
+
+    match chunks.split_last() {
+        None => write!(f, "0"),
+        Some((top, lower)) => {
+            write!(f, "{}", top)?;
+            lower.iter().rev().try_for_each(|chunk| write!(f, "{:09}", chunk))
+        },
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write_mag(f, &self.num)?;
+        if !self.is_integer() {
+            write!(f, "/")?;
+            write_mag(f, &self.den)?;
+        }
+        Ok(())
+    }
+}
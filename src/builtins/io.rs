@@ -0,0 +1,225 @@
+// Files: opening, reading and writing them, whole or a line at a time, with
+// `slurp` and `spit` for the common cases. Paths go through the sandbox, so an
+// interpreter with a root set can't reach outside it. The group is behind the
+// `files` feature, and installed lazily like the other builtins that touch the
+// world outside the interpreter.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::rc::Rc;
+
+use crate::{args, as_index, glob, option_symbol, parse_options, replay, resource, sandbox, template, RispErr, RispExp, PENDING_FINALIZERS};
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert("glob".to_string(), RispExp::Func(glob::glob_builtin));
+    data.insert(
+        "open".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                let path = args::string("open", args, 0)?.to_string();
+                let mode = match args.get(1) {
+                    Some(_) => args::string("open", args, 1)?,
+                    None => "r",
+                };
+
+                let mut options = OpenOptions::new();
+                match mode {
+                    "r" => options.read(true),
+                    "w" => options.write(true).create(true).truncate(true),
+                    "a" => options.append(true).create(true),
+                    _ => return Err(RispErr::Reason(format!("unknown file mode '{}'", mode))),
+                };
+
+                let file = options.open(sandbox::resolve(&path)?)
+                    .map_err(|e| RispErr::Reason(format!("could not open '{}': {}", path, e)))?;
+
+                Ok(resource::wrap("file", path, BufReader::new(file)))
+            }
+        )
+    );
+
+    data.insert(
+        "close".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                resource::any(args.first())?.borrow_mut().close()?;
+
+                Ok(RispExp::Bool(true))
+            }
+        )
+    );
+
+    data.insert(
+        "read-line".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                let file = resource::expect(args.first(), "file")?;
+                let mut file = file.borrow_mut();
+                let reader = file.get_mut::<BufReader<File>>()?;
+
+                replay::recorded("read-line", || {
+                    let mut line = String::new();
+                    match reader.read_line(&mut line) {
+                        Ok(0) => Ok(RispExp::Bool(false)),
+                        Ok(_) => Ok(RispExp::Str(line.trim_end_matches(['\n', '\r']).to_string())),
+                        Err(e) => Err(RispErr::Reason(format!("could not read file: {}", e))),
+                    }
+                })
+            }
+        )
+    );
+
+    data.insert(
+        "write".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                let file = resource::expect(args.first(), "file")?;
+                let text = args::string("write", args, 1)?;
+                let mut file = file.borrow_mut();
+                let writer = file.get_mut::<BufReader<File>>()?;
+
+                writer.get_mut().write_all(text.as_bytes())
+                    .map_err(|e| RispErr::Reason(format!("could not write file: {}", e)))?;
+
+                Ok(RispExp::Bool(true))
+            }
+        )
+    );
+
+    data.insert(
+        "set-finalizer!".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                let resource = resource::any(args.first())?;
+                let finalizer = match args.get(1) {
+                    Some(f @ RispExp::Func(_)) | Some(f @ RispExp::EnvFunc(_)) | Some(f @ RispExp::Lambda(_)) => Ok(f.clone()),
+                    _ => Err(RispErr::Reason("expected a finalizer function".to_string())),
+                }?;
+
+                resource.borrow_mut().add_finalizer(Box::new(move |_| {
+                    // handles can be collected while thread locals are torn down at exit
+                    let _ = PENDING_FINALIZERS.try_with(|pending| pending.borrow_mut().push(finalizer));
+                }));
+
+                Ok(RispExp::Bool(true))
+            }
+        )
+    );
+
+    data.insert(
+        "weak".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                let resource = resource::any(args.first())?;
+
+                Ok(RispExp::Weak(Rc::downgrade(&resource)))
+            }
+        )
+    );
+
+    data.insert(
+        "weak-get".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                match args::get("weak-get", args, 0, "a weak reference")? {
+                    RispExp::Weak(weak) => Ok(
+                        weak.upgrade().map(RispExp::Resource).unwrap_or(RispExp::Bool(false))
+                    ),
+                    exp => Err(args::wrong("weak-get", 0, "a weak reference", exp)),
+                }
+            }
+        )
+    );
+
+    data.insert(
+        "slurp".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                let path = args::string("slurp", args, 0)?.to_string();
+                let options = parse_options(&args[1..], &[":encoding", ":newline"])?;
+                let encoding = option_symbol(&options, ":encoding", ":utf-8")?;
+                let newline = option_symbol(&options, ":newline", ":lf")?;
+
+                replay::recorded("slurp", || {
+                    let bytes = fs::read(sandbox::resolve(&path)?)
+                        .map_err(|e| RispErr::Reason(format!("could not read '{}': {}", path, e)))?;
+
+                    let text = match encoding.as_ref() {
+                        ":bytes" => return Ok(RispExp::List(
+                            bytes.into_iter().map(|b| RispExp::Int(b as i64)).collect()
+                        )),
+                        ":utf-8" => String::from_utf8(bytes)
+                            .map_err(|_| RispErr::Reason(format!("'{}' is not valid utf-8", path)))?,
+                        ":latin-1" => bytes.into_iter().map(|b| b as char).collect(),
+                        _ => return Err(RispErr::Reason(format!("unknown encoding '{}'", encoding))),
+                    };
+
+                    match newline.as_ref() {
+                        ":lf" => Ok(RispExp::Str(text.replace("\r\n", "\n"))),
+                        ":keep" => Ok(RispExp::Str(text)),
+                        _ => Err(RispErr::Reason(format!("unknown newline mode '{}'", newline))),
+                    }
+                })
+            }
+        )
+    );
+
+    data.insert(
+        "spit".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                let path = args::string("spit", args, 0)?.to_string();
+                let content = args::get("spit", args, 1, "content")?;
+                let options = parse_options(&args[2..], &[":encoding", ":newline"])?;
+                let encoding = option_symbol(&options, ":encoding", ":utf-8")?;
+                let newline = option_symbol(&options, ":newline", ":lf")?;
+
+                let bytes = match (encoding.as_ref(), content) {
+                    (":bytes", RispExp::List(list)) => list
+                        .iter()
+                        .map(|b| {
+                            as_index(b)
+                                .and_then(|b| u8::try_from(b).ok())
+                                .ok_or(RispErr::Reason("expected bytes to be numbers from 0 to 255".to_string()))
+                        })
+                        .collect::<Result<Vec<u8>, RispErr>>()?,
+                    (":bytes", _) => return Err(RispErr::Reason("expected a list of bytes".to_string())),
+                    (_, RispExp::Str(text)) => {
+                        let text = text.replace("\r\n", "\n");
+                        let text = match newline.as_ref() {
+                            ":lf" => text,
+                            ":crlf" => text.replace('\n', "\r\n"),
+                            ":native" if cfg!(windows) => text.replace('\n', "\r\n"),
+                            ":native" => text,
+                            _ => return Err(RispErr::Reason(format!("unknown newline mode '{}'", newline))),
+                        };
+
+                        match encoding.as_ref() {
+                            ":utf-8" => text.into_bytes(),
+                            ":latin-1" => text
+                                .chars()
+                                .map(|c| u8::try_from(c).map_err(
+                                    |_| RispErr::Reason(format!("'{}' can not be encoded as latin-1", c))
+                                ))
+                                .collect::<Result<Vec<u8>, RispErr>>()?,
+                            _ => return Err(RispErr::Reason(format!("unknown encoding '{}'", encoding))),
+                        }
+                    },
+                    _ => return Err(args::wrong("spit", 1, "a string", content)),
+                };
+
+                fs::write(sandbox::resolve(&path)?, bytes)
+                    .map_err(|e| RispErr::Reason(format!("could not write '{}': {}", path, e)))?;
+
+                Ok(RispExp::Bool(true))
+            }
+        )
+    );
+
+    data.insert(
+        "emit-file".to_string(),
+        RispExp::Func(template::emit_file)
+    );
+}
@@ -0,0 +1,62 @@
+// Collections: lists, vectors, sorted maps and sets, queues and stacks,
+// zippers, walking nested ones, `get` and `assoc` on any of them, and
+// metadata. Maps themselves are core, the reader needs `hash-map` for `{...}`.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::{access, args, diff, lists, meta, queue, sorted, vector, walk, zipper, Cons, RispErr, RispExp};
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    lists::add_builtins(data);
+    vector::add_builtins(data);
+    access::add_builtins(data);
+    sorted::add_builtins(data);
+    queue::add_builtins(data);
+    zipper::add_builtins(data);
+    walk::add_builtins(data);
+    meta::add_builtins(data);
+
+    data.insert(
+        "values".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                Ok(RispExp::Tuple(Rc::new(args.to_vec())))
+            }
+        )
+    );
+
+    data.insert(
+        "sexp-diff".to_string(),
+        RispExp::Func(diff::sexp_diff)
+    );
+
+    data.insert(
+        "nil?".to_string(),
+        RispExp::Func(|args: &[RispExp]| -> Result<RispExp, RispErr> {
+            Ok(RispExp::Bool(matches!(args.first(), Some(RispExp::List(list)) if list.is_empty())))
+        })
+    );
+
+    data.insert(
+        "empty?".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                let empty = match args::get("empty?", args, 0, "a collection")? {
+                    RispExp::List(list) => list.is_empty(),
+                    RispExp::Str(s) => s.is_empty(),
+                    RispExp::Vector(xs) => xs.borrow().is_empty(),
+                    RispExp::Tuple(values) => values.is_empty(),
+                    RispExp::SortedMap(map) => map.is_empty(),
+                    RispExp::Map(map) => map.is_empty(),
+                    RispExp::SortedSet(set) => set.is_empty(),
+                    RispExp::Queue(queue) => queue.is_empty(),
+                    RispExp::Stack(stack) => matches!(stack.as_ref(), Cons::Nil),
+                    exp => return Err(args::wrong("empty?", 0, "a collection", exp)),
+                };
+
+                Ok(RispExp::Bool(empty))
+            }
+        )
+    );
+}
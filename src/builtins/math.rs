@@ -0,0 +1,110 @@
+// Arithmetic and comparisons: `+`, `-`, `*`, `/` and the rest of `numeric`,
+// `=`, `<` and friends, which take numbers or strings, and `divmod`.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::{args, as_float, numeric, RispErr, RispExp};
+
+// the ints, when every argument is one, so arithmetic on them stays exact
+fn all_ints(args: &[RispExp]) -> Option<Vec<i64>> {
+    args.iter().map(|arg| match arg {
+        RispExp::Int(n) => Some(*n),
+        _ => None,
+    }).collect()
+}
+
+fn overflow() -> RispErr {
+    RispErr::Reason("integer overflow".to_string())
+}
+
+// the ordering of each pair of neighbours, numbers compared with numbers and
+// strings with strings
+fn compare_chain(op: &str, args: &[RispExp]) -> Result<Vec<Option<std::cmp::Ordering>>, RispErr> {
+    let first = args::get(op, args, 0, "a number or string")?;
+    let strings = match first {
+        RispExp::Str(_) => true,
+        _ if as_float(first).is_some() => false,
+        _ => return Err(args::wrong(op, 0, "a number or string", first)),
+    };
+    for idx in 1..args.len() {
+        if strings {
+            args::string(op, args, idx)?;
+        } else {
+            args::number(op, args, idx)?;
+        }
+    }
+
+    Ok(args.windows(2).map(|pair| match pair {
+        [RispExp::Str(a), RispExp::Str(b)] => Some(a.cmp(b)),
+        _ => numeric::compare(&pair[0], &pair[1]),
+    }).collect())
+}
+
+macro_rules!ensure_tonicity {
+    ($op:expr, $check_fn:expr) => {{
+        |args: &[RispExp]| ->Result<RispExp, RispErr> {
+            Ok(RispExp::Bool(compare_chain($op, args)?.into_iter().all($check_fn)))
+        } 
+    }};
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    numeric::add_builtins(data);
+
+    data.insert(
+        "=".to_string(),
+        RispExp::Func(ensure_tonicity!("=", |ord| ord == Some(std::cmp::Ordering::Equal)))
+    );
+
+    data.insert(
+        ">".to_string(),
+        RispExp::Func(ensure_tonicity!(">", |ord| ord == Some(std::cmp::Ordering::Greater)))
+    );
+
+    data.insert(
+        ">=".to_string(),
+        RispExp::Func(ensure_tonicity!(">=", |ord| matches!(ord, Some(std::cmp::Ordering::Greater | std::cmp::Ordering::Equal))))
+    );
+
+    data.insert(
+        "<".to_string(),
+        RispExp::Func(ensure_tonicity!("<", |ord| ord == Some(std::cmp::Ordering::Less)))
+    );
+
+    data.insert(
+        "<=".to_string(),
+        RispExp::Func(ensure_tonicity!("<=", |ord| matches!(ord, Some(std::cmp::Ordering::Less | std::cmp::Ordering::Equal))))
+    );
+
+    data.insert(
+        "divmod".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                // the quotient is rounded down, so the remainder has the sign of the divisor
+                if let Some([a, b]) = all_ints(args).as_deref() {
+                    if *b == 0 {
+                        return Err(RispErr::Reason("division by zero".to_string()))
+                    }
+                    let mut quotient = a.checked_div(*b).ok_or_else(overflow)?;
+                    if a % b != 0 && (*a < 0) != (*b < 0) {
+                        quotient -= 1;
+                    }
+                    let remainder = a - quotient * b;
+
+                    return Ok(RispExp::Tuple(Rc::new(vec![RispExp::Int(quotient), RispExp::Int(remainder)])))
+                }
+                args::exactly("divmod", args, 2)?;
+                let floats = args::nums("divmod", args)?;
+                if floats[1] == 0.0 {
+                    return Err(RispErr::Reason("division by zero".to_string()))
+                }
+
+                let quotient = (floats[0] / floats[1]).floor();
+                let remainder = floats[0] - quotient * floats[1];
+
+                Ok(RispExp::Tuple(Rc::new(vec![RispExp::Number(quotient), RispExp::Number(remainder)])))
+            }
+        )
+    );
+}
@@ -0,0 +1,73 @@
+// The builtins of `default_env`, in groups that can each be left out. Every
+// group is behind a cargo feature of the same name, `io` behind `files`, and
+// an environment is built with the groups of the features it's given, so an
+// embedded interpreter can go without files or the network in a build that
+// also has the scripting one with everything:
+//
+//     let calc = Interpreter::with_features(&["math", "list"])?;
+//
+// The core that's always there is the reader's: type predicates, maps, which
+// `{...}` reads as, tagged values, namespaces, objects and `read`.
+// An environment built without a group doesn't see its builtins, even the
+// lazily installed ones the rest of the thread shares.
+
+mod io;
+mod list;
+mod math;
+mod net;
+mod string;
+mod sys;
+
+use std::collections::HashMap;
+
+use super::{enabled_features, RispEnv, RispExp};
+
+pub use self::io::add_builtins as add_file_builtins;
+
+type Install = fn(&mut HashMap<String, RispExp>);
+
+// each group's feature and the builtins it installs up front
+const GROUPS: [(&str, Install); 5] = [
+    ("math", math::add_builtins),
+    ("list", list::add_builtins),
+    ("string", string::add_builtins),
+    ("net", net::add_builtins),
+    ("sys", sys::add_builtins),
+];
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>, features: &[&str]) {
+    for (feature, install) in GROUPS.iter() {
+        if features.contains(feature) {
+            install(data);
+        }
+    }
+}
+
+// the names each group defines, by its feature
+pub fn index() -> Vec<(String, &'static str)> {
+    let mut index = vec![];
+    for (feature, install) in GROUPS.iter().chain([("files", io::add_builtins as Install)].iter()) {
+        let mut data = HashMap::new();
+        install(&mut data);
+        index.extend(data.into_keys().map(|name| (name, *feature)));
+    }
+    index
+}
+
+// the features `env` was built with, from its global `*features*`, or the
+// enabled ones for an environment without it
+fn env_features(env: &RispEnv) -> Vec<String> {
+    let mut root = env;
+    while let Some(outer) = root.outer {
+        root = outer;
+    }
+
+    match root.data.borrow().get("*features*") {
+        Some(RispExp::List(features)) => features.iter().map(|feature| feature.to_string().trim_start_matches(':').to_string()).collect(),
+        _ => enabled_features().iter().map(|feature| feature.to_string()).collect(),
+    }
+}
+
+pub fn has_feature(env: &RispEnv, feature: &str) -> bool {
+    env_features(env).iter().any(|f| f == feature)
+}
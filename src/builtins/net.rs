@@ -0,0 +1,25 @@
+// The network: `(remote-eval addr src [token])` evaluates each top-level form
+// of `src` on a `risp repl --remote` server and gives back its replies, as
+// strings like "=> 3" or "// error".
+
+use std::collections::HashMap;
+
+use crate::{args, remote, split_forms, RispErr, RispExp};
+
+fn remote_eval(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let addr = args::string("remote-eval", args, 0)?;
+    let src = args::string("remote-eval", args, 1)?;
+    let token = match args.get(2) {
+        Some(_) => Some(args::string("remote-eval", args, 2)?.to_string()),
+        None => None,
+    };
+
+    let replies = remote::send(addr, token, &split_forms(src)?)
+        .map_err(|e| RispErr::Reason(format!("could not reach {}: {}", addr, e)))?;
+
+    Ok(RispExp::List(replies.into_iter().map(RispExp::Str).collect()))
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert("remote-eval".to_string(), RispExp::Func(remote_eval));
+}
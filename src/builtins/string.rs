@@ -0,0 +1,45 @@
+// Text: the string builtins, escaping for html, sql and risp source, and
+// writing values out as html, edn or a graphviz graph.
+
+use std::collections::HashMap;
+
+use crate::{dot, edn, html, strings, template, RispExp};
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    strings::add_builtins(data);
+
+    data.insert(
+        "html".to_string(),
+        RispExp::Func(html::html)
+    );
+
+    data.insert(
+        "template".to_string(),
+        RispExp::Func(template::template)
+    );
+
+    data.insert(
+        "escape-html".to_string(),
+        RispExp::Func(template::escape_html_args)
+    );
+
+    data.insert(
+        "escape-sql".to_string(),
+        RispExp::Func(template::escape_sql)
+    );
+
+    data.insert(
+        "escape-risp".to_string(),
+        RispExp::Func(template::escape_risp)
+    );
+
+    data.insert(
+        "edn-write".to_string(),
+        RispExp::Func(edn::write)
+    );
+
+    data.insert(
+        "to-dot".to_string(),
+        RispExp::Func(dot::to_dot_args)
+    );
+}
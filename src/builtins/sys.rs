@@ -0,0 +1,31 @@
+// The interpreter looking at itself: environments as values, watching
+// bindings change, checking calls against specs and the session's counts.
+
+use std::collections::HashMap;
+
+use crate::{args, envs, spec, stats, watch, RispErr, RispExp, RispLambda};
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    watch::add_builtins(data);
+    spec::add_builtins(data);
+    data.insert("make-env".to_string(), RispExp::Func(envs::make_env));
+    data.insert("env-get".to_string(), RispExp::Func(envs::env_get_args));
+
+    data.insert(
+        "session-stats".to_string(),
+        RispExp::Func(stats::session_stats)
+    );
+
+    data.insert(
+        "signature".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                match args::get("signature", args, 0, "a lambda")? {
+                    RispExp::Lambda(RispLambda { signature: Some(signature), .. }) => Ok(RispExp::Str(signature.to_string())),
+                    RispExp::Lambda(_) => Ok(RispExp::Bool(false)),
+                    exp => Err(args::wrong("signature", 0, "a lambda", exp)),
+                }
+            }
+        )
+    );
+}
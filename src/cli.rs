@@ -0,0 +1,453 @@
+// The `risp` command: the REPL and the subcommands, for the binary to run.
+// Embedding the interpreter doesn't need any of it.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+use super::console::{self, Color, Console};
+use super::{audit, diff, doctor, dot, edn, explain, graph, i18n, minify, output, pkg, project, remote, rename, replay, stats, tui, types};
+use super::{default_env, err_message, eval, parse_eval, parse_forms, parse_forms_spanned, run_pending_finalizers, split_forms, RispEnv, RispExp};
+
+fn print_explanation(explanation: &str) {
+    for line in explanation.lines() {
+        match line {
+            "" => println!("//"),
+            _ => println!("//   {}", line),
+        }
+    }
+}
+
+// how many evaluations `:undo` can go back
+const UNDO_DEPTH: usize = 100;
+
+// with `explain`, every error is followed by its longer explanation
+fn repl(env: &mut RispEnv, explain: bool) {
+    let console = Console::new();
+    let mut last_explanation: Option<Option<String>> = None;
+    let mut snapshot = env.data.borrow().clone();
+    // the bindings before each evaluation that changed them, the latest last
+    let mut undo: Vec<HashMap<String, RispExp>> = vec![];
+    // `:set timing on` follows each result with how long it took and what it allocated
+    let mut timing = false;
+    loop {
+        output::end_line();
+        println!("{}", console.paint("risp >", Color::Prompt));
+        let expr = match console::read_line() {
+            Some(expr) => expr,
+            None => break,
+        };
+
+        // what changed since the last `:diff-env`, or since the REPL started
+        if expr.trim() == ":diff-env" {
+            let changes = diff::env_changes(&snapshot, &env.data.borrow());
+            if changes.is_empty() {
+                println!("// no bindings changed");
+            }
+            for change in changes {
+                println!("// {}", change);
+            }
+            snapshot = env.data.borrow().clone();
+            continue
+        }
+
+        // puts the bindings back the way they were before the last evaluation
+        // that changed them, though not what else it did, like writing files
+        if expr.trim() == ":undo" {
+            match undo.pop() {
+                Some(before) => {
+                    for change in diff::env_changes(&env.data.borrow(), &before) {
+                        println!("// {}", change);
+                    }
+                    *env.data.borrow_mut() = before;
+                },
+                None => println!("// nothing to undo"),
+            }
+            continue
+        }
+
+        if let Some(option) = expr.trim().strip_prefix(":set ") {
+            match option.split_whitespace().collect::<Vec<_>>()[..] {
+                ["timing", "on"] => timing = true,
+                ["timing", "off"] => timing = false,
+                _ => println!("// unknown option '{}', expected `:set timing on` or `:set timing off`", option.trim()),
+            }
+            continue
+        }
+
+        if expr.trim() == ":explain" {
+            match &last_explanation {
+                Some(Some(explanation)) => print_explanation(explanation),
+                Some(None) => println!("// there's no longer explanation for the last error"),
+                None => println!("// there's no error to explain"),
+            }
+            continue
+        }
+
+        let before = env.data.borrow().clone();
+        let metrics = stats::current();
+        let res = parse_eval(expr, env);
+        let after = stats::current();
+        output::end_line();
+        if !diff::env_changes(&before, &env.data.borrow()).is_empty() {
+            if undo.len() == UNDO_DEPTH {
+                undo.remove(0);
+            }
+            undo.push(before);
+        }
+
+        match res {
+            Ok(res) => {
+                let mut line = format!("=> {}", res);
+                if timing {
+                    let ms = (after.eval_time - metrics.eval_time).as_secs_f64() * 1000.0;
+                    line.push_str(&format!(" ({:.1}ms, {} allocs)", ms, after.allocations - metrics.allocations));
+                }
+                println!("{}", console.paint(&line, Color::Result));
+            },
+            Err(e) => {
+                let explanation = explain::explain(&e);
+                println!("{}", console.paint(&format!("// {}", err_message(e)), Color::Error));
+                if let (true, Some(explanation)) = (explain, &explanation) {
+                    print_explanation(explanation);
+                }
+                last_explanation = Some(explanation);
+            },
+        }
+
+        for e in run_pending_finalizers(env) {
+            println!("{}", console.paint(&format!("// {}", err_message(e)), Color::Error));
+        }
+    }
+}
+
+const USAGE: &str = "usage: risp [--lang code] [--record log | --replay log] [--audit-log path] [--memory-limit bytes]
+            [repl [--explain] [--remote [--addr host:port] [--token token]]]
+       risp send [--addr host:port] [--token token] (expr | --file path)
+       risp new name
+       risp run [path]
+       risp test
+       risp watch path
+       risp check [--types] path
+       risp diff a.risp b.risp
+       risp graph path
+       risp minify path
+       risp rename old new path...
+       risp pkg add (git-url | name)
+       risp pkg vendor
+       risp --ast-dot path
+       risp doctor";
+
+// value following `--name` in the command line arguments
+fn flag_value(args: &[String], name: &str) -> Result<Option<String>, String> {
+    match args.iter().position(|arg| arg == name) {
+        Some(idx) => args.get(idx + 1)
+            .cloned()
+            .map(Some)
+            .ok_or(format!("{} expects a value", name)),
+        None => Ok(None),
+    }
+}
+
+// arguments that aren't a `--name value` flag
+fn positional_args(args: &[String]) -> Vec<String> {
+    let mut positional = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg.starts_with("--") {
+            args.next();
+        } else {
+            positional.push(arg.clone());
+        }
+    }
+
+    positional
+}
+
+// `risp send` prints the reply to every form and fails if any of them errored
+fn send(args: &[String]) -> Result<(), String> {
+    let addr = flag_value(args, "--addr")?.unwrap_or(remote::DEFAULT_ADDR.to_string());
+    let token = flag_value(args, "--token")?.or(env::var("RISP_REMOTE_TOKEN").ok());
+    let forms = match flag_value(args, "--file")? {
+        Some(path) => {
+            let src = fs::read_to_string(&path).map_err(|e| format!("could not read '{}': {}", path, e))?;
+            split_forms(&src).map_err(|e| format!("{}: {}", path, err_message(e)))?
+        },
+        None => match &positional_args(args)[..] {
+            [expr] => vec![expr.clone()],
+            _ => return Err(USAGE.to_string()),
+        },
+    };
+
+    let replies = remote::send(&addr, token, &forms)
+        .map_err(|e| format!("could not send to {}: {}", addr, e))?;
+
+    for reply in &replies {
+        println!("{}", reply);
+    }
+
+    match replies.iter().flat_map(|reply| reply.lines()).any(|line| line.starts_with("// ")) {
+        true => Err("evaluation failed".to_string()),
+        false => Ok(()),
+    }
+}
+
+// Re-evaluates the whole file into the same environment every time it's
+// saved. `def`s are simply redefined, `defonce` keeps state across reloads.
+fn watch(path: &str, env: &mut RispEnv) -> Result<(), String> {
+    let console = Console::new();
+    let modified = || fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    let mut last_modified = None;
+
+    loop {
+        let current = modified();
+        if current.is_some() && current != last_modified {
+            last_modified = current;
+            let src = fs::read_to_string(path).map_err(|e| format!("could not read '{}': {}", path, e))?;
+
+            match parse_forms_spanned(&src) {
+                Ok(forms) => {
+                    let mut last = None;
+                    let mut failed = 0;
+                    for (form, pos) in &forms {
+                        let res = stats::timed(|| eval(form, env));
+                        output::end_line();
+                        match res {
+                            Ok(res) => last = Some(res),
+                            Err(e) => {
+                                failed += 1;
+                                let msg = format!("// {}:{}: {}", path, pos, err_message(e));
+                                println!("{}", console.paint(&msg, Color::Error));
+                            },
+                        }
+                    }
+
+                    if let Some(res) = last {
+                        println!("{}", console.paint(&format!("=> {}", res), Color::Result));
+                    }
+                    eprintln!("reloaded {} ({} forms, {} failed)", path, forms.len(), failed);
+                },
+                Err(e) => println!("{}", console.paint(&format!("// {}", err_message(e)), Color::Error)),
+            }
+
+            for e in run_pending_finalizers(env) {
+                println!("{}", console.paint(&format!("// {}", err_message(e)), Color::Error));
+            }
+        }
+
+        thread::sleep(Duration::from_millis(250));
+    }
+}
+
+// `risp check` only parses, `--types` also runs the type checker
+fn check(args: &[String]) -> Result<(), String> {
+    let typed = args.iter().any(|arg| arg == "--types");
+    let paths: Vec<&String> = args.iter().filter(|arg| *arg != "--types").collect();
+    let path = match &paths[..] {
+        [path] => path,
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let src = fs::read_to_string(path).map_err(|e| format!("could not read '{}': {}", path, e))?;
+    let diagnostics = match typed {
+        true => types::check(&src),
+        false => parse_forms(src).map(|_| vec![]),
+    }.map_err(|e| format!("{}: {}", path, err_message(e)))?;
+
+    for d in &diagnostics {
+        println!("{}:{}: {}", path, d.pos, d.message);
+    }
+
+    match diagnostics.len() {
+        0 => Ok(()),
+        1 => Err("1 type error".to_string()),
+        n => Err(format!("{} type errors", n)),
+    }
+}
+
+// `risp diff` prints one line per structural difference between the forms of
+// the two files, as `- path old`, `+ path new` or `~ path old -> new`
+fn diff_files(args: &[String]) -> Result<(), String> {
+    let (a, b) = match args {
+        [a, b] => (a, b),
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let read = |path: &String| -> Result<RispExp, String> {
+        let src = fs::read_to_string(path).map_err(|e| format!("could not read '{}': {}", path, e))?;
+        parse_forms(src)
+            .map(|forms| RispExp::List(forms.into()))
+            .map_err(|e| format!("{}: {}", path, err_message(e)))
+    };
+    let write = |exp: &RispExp| edn::write_exp(exp).unwrap_or_else(|_| exp.to_string());
+
+    let edits = diff::diff(&read(a)?, &read(b)?);
+    for edit in &edits {
+        match edit {
+            diff::Edit::Delete(path, x) => println!("- {} {}", write(&RispExp::List(path.clone().into())), write(x)),
+            diff::Edit::Insert(path, x) => println!("+ {} {}", write(&RispExp::List(path.clone().into())), write(x)),
+            diff::Edit::Change(path, old, new) =>
+                println!("~ {} {} -> {}", write(&RispExp::List(path.clone().into())), write(old), write(new)),
+        }
+    }
+
+    match edits.len() {
+        0 => Ok(()),
+        1 => Err("1 difference".to_string()),
+        n => Err(format!("{} differences", n)),
+    }
+}
+
+// `risp graph` prints `name -> deps...` for each definition, then what's
+// unused and what's in a cycle
+fn graph_file(path: &str) -> Result<(), String> {
+    let src = fs::read_to_string(path).map_err(|e| format!("could not read '{}': {}", path, e))?;
+    let forms = parse_forms(src).map_err(|e| format!("{}: {}", path, err_message(e)))?;
+    let graph = graph::graph(&forms);
+
+    for (name, deps) in &graph.deps {
+        let deps: Vec<&str> = deps.iter().map(|dep| dep.as_str()).collect();
+        match deps.is_empty() {
+            true => println!("{}", name),
+            false => println!("{} -> {}", name, deps.join(" ")),
+        }
+    }
+    if !graph.unused.is_empty() {
+        println!("unused: {}", graph.unused.join(" "));
+    }
+    for cycle in &graph.cycles {
+        println!("cycle: {} -> {}", cycle.join(" -> "), cycle[0]);
+    }
+
+    Ok(())
+}
+
+// `risp --ast-dot` prints the forms of a file as a DOT graph, for piping into `dot`
+fn ast_dot(path: &str) -> Result<(), String> {
+    let src = fs::read_to_string(path).map_err(|e| format!("could not read '{}': {}", path, e))?;
+    let forms = parse_forms(src).map_err(|e| format!("{}: {}", path, err_message(e)))?;
+    print!("{}", dot::forms_to_dot(path, &forms));
+
+    Ok(())
+}
+
+// `--explain`, `--lang`, `--record`, `--replay`, `--audit-log` and
+// `--memory-limit` apply to every command, wherever they're given
+fn without_global_flags(args: &[String]) -> Vec<String> {
+    let mut rest = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_ref() {
+            "--explain" => (),
+            "--lang" | "--record" | "--replay" | "--audit-log" | "--memory-limit" => {
+                args.next();
+            },
+            _ => rest.push(arg.clone()),
+        }
+    }
+
+    rest
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let lang = flag_value(args, "--lang")?.or(env::var("RISP_LANG").ok());
+    i18n::set_lang(lang.as_deref());
+    match (flag_value(args, "--record")?, flag_value(args, "--replay")?) {
+        (Some(_), Some(_)) => return Err("--record and --replay can't be used together".to_string()),
+        (Some(log), None) => replay::record(&log)?,
+        (None, Some(log)) => replay::replay(&log)?,
+        (None, None) => (),
+    }
+    if let Some(path) = flag_value(args, "--audit-log")? {
+        audit::log_to(&path)?;
+    }
+    if let Some(limit) = flag_value(args, "--memory-limit")? {
+        let limit = limit.parse().map_err(|_| format!("expected --memory-limit in bytes, got '{}'", limit))?;
+        stats::set_memory_limit(Some(limit));
+    }
+
+    stats::start();
+    let env = &mut default_env();
+    let explain = args.iter().any(|arg| arg == "--explain");
+    let args = &without_global_flags(args);
+    match args.first().map(|arg| arg.as_ref()) {
+        None => {
+            repl(env, explain);
+            Ok(())
+        },
+        Some("repl") if args.iter().any(|arg| arg == "--remote") => {
+            let addr = flag_value(args, "--addr")?.unwrap_or(remote::DEFAULT_ADDR.to_string());
+            let token = flag_value(args, "--token")?.or(env::var("RISP_REMOTE_TOKEN").ok());
+            remote::serve(&addr, token, env).map_err(|e| format!("could not serve on {}: {}", addr, e))
+        },
+        Some("repl") => {
+            repl(env, explain);
+            Ok(())
+        },
+        Some("send") => send(&args[1..]),
+        Some("new") => match &args[1..] {
+            [name] => project::new(name),
+            _ => Err(USAGE.to_string()),
+        },
+        Some("run") => project::run(&args[1..], env),
+        Some("test") => match &args[1..] {
+            [] => project::test(),
+            _ => Err(USAGE.to_string()),
+        },
+        Some("check") => check(&args[1..]),
+        Some("doctor") => doctor::run(),
+        Some("diff") => diff_files(&args[1..]),
+        Some("graph") => match &args[1..] {
+            [path] => graph_file(path),
+            _ => Err(USAGE.to_string()),
+        },
+        Some("minify") => match &args[1..] {
+            [path] => {
+                let src = fs::read_to_string(path).map_err(|e| format!("could not read '{}': {}", path, e))?;
+                println!("{}", minify::minify(src).map_err(|e| format!("{}: {}", path, err_message(e)))?);
+                Ok(())
+            },
+            _ => Err(USAGE.to_string()),
+        },
+        Some("pkg") => pkg::run(&args[1..]),
+        Some("rename") => match &args[1..] {
+            [old, new, paths @ ..] if !paths.is_empty() => rename::rename_files(old, new, paths),
+            _ => Err(USAGE.to_string()),
+        },
+        Some("--ast-dot") => match &args[1..] {
+            [path] => ast_dot(path),
+            _ => Err(USAGE.to_string()),
+        },
+        Some("watch") => match &positional_args(&args[1..])[..] {
+            [path] => watch(path, env),
+            _ => Err(USAGE.to_string()),
+        },
+        Some(_) => Err(USAGE.to_string()),
+    }
+}
+
+// the evaluating thread's stack
+const STACK_SIZE: usize = 256 << 20;
+
+// the `risp` command
+pub fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    // on a thread of its own with a big stack, so deep recursion goes far
+    // before it's stopped, leaving room ahead of its end for the builtins
+    let result = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || {
+            stats::set_stack_limit(STACK_SIZE - (4 << 20));
+            run(&args)
+        })
+        .map_err(|e| format!("could not start: {}", e))
+        .and_then(|evaluator| evaluator.join().unwrap_or_else(|_| process::exit(101)));
+    tui::restore();
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,91 @@
+// Terminal handling for the REPL. Colors are only used when stdout is an
+// interactive terminal that understands ANSI escapes, so piped output stays
+// plain and Windows consoles don't print raw escape codes.
+
+use std::env;
+use std::io::{self, IsTerminal};
+
+pub enum Color {
+    Prompt,
+    Result,
+    Error,
+}
+
+pub struct Console {
+    colors: bool,
+}
+
+impl Console {
+    pub fn new() -> Console {
+        let wants_colors = io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+
+        Console {
+            colors: wants_colors && enable_ansi(),
+        }
+    }
+
+    pub fn colors(&self) -> bool {
+        self.colors
+    }
+
+    pub fn paint(&self, text: &str, color: Color) -> String {
+        if !self.colors {
+            return text.to_string()
+        }
+
+        let code = match color {
+            Color::Prompt => "1;34",
+            Color::Result => "32",
+            Color::Error => "31",
+        };
+
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    }
+}
+
+// Reads one line of input without its line ending, so CRLF from Windows
+// consoles and LF from everywhere else look the same. `None` at end of input.
+pub fn read_line() -> Option<String> {
+    let mut line = String::new();
+    match io::stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim_end_matches(['\n', '\r']).to_string()),
+    }
+}
+
+#[cfg(not(windows))]
+fn enable_ansi() -> bool {
+    env::var("TERM").map(|term| term != "dumb").unwrap_or(true)
+}
+
+// Windows 10+ consoles only interpret ANSI escapes once virtual terminal
+// processing has been switched on for the output handle.
+#[cfg(windows)]
+fn enable_ansi() -> bool {
+    use std::ffi::c_void;
+
+    const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+    const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x0004;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetStdHandle(std_handle: u32) -> *mut c_void;
+        fn GetConsoleMode(console: *mut c_void, mode: *mut u32) -> i32;
+        fn SetConsoleMode(console: *mut c_void, mode: u32) -> i32;
+    }
+
+    unsafe {
+        let handle = GetStdHandle(STD_OUTPUT_HANDLE);
+        if handle.is_null() || handle as isize == -1 {
+            return false
+        }
+
+        let mut mode: u32 = 0;
+        if GetConsoleMode(handle, &mut mode) == 0 {
+            return false
+        }
+
+        mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+            || SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0
+    }
+}
@@ -0,0 +1,234 @@
+// Structural diffs between two values, for comparing generated code and
+// config trees by what changed rather than by which lines did.
+//
+// A diff is a list of `(:insert path x)`, `(:delete path x)` and
+// `(:change path old new)` entries. Paths are the indices and keys leading to
+// the difference in the first value, except for inserts whose path is into
+// the second. Lists are aligned on their longest common subsequence, so one
+// inserted element shows up as one insert instead of a change to every
+// element after it.
+
+use std::collections::{BTreeSet, HashMap};
+use std::rc::Rc;
+
+use super::{RispErr, RispExp};
+
+pub enum Edit {
+    Insert(Vec<RispExp>, RispExp),
+    Delete(Vec<RispExp>, RispExp),
+    Change(Vec<RispExp>, RispExp, RispExp),
+}
+
+pub fn same(a: &RispExp, b: &RispExp) -> bool {
+    match (a, b) {
+        (RispExp::Bool(a), RispExp::Bool(b)) => a == b,
+        (RispExp::Number(a), RispExp::Number(b)) => a == b,
+        (RispExp::Int(a), RispExp::Int(b)) => a == b,
+        #[cfg(feature = "bignum")]
+        (RispExp::Exact(a), RispExp::Exact(b)) => a == b,
+        (RispExp::Str(a), RispExp::Str(b)) | (RispExp::Symbol(a), RispExp::Symbol(b)) => a == b,
+        (RispExp::Keyword(a), RispExp::Keyword(b)) => a == b,
+        (RispExp::Char(a), RispExp::Char(b)) => a == b,
+        (RispExp::List(a), RispExp::List(b)) => all_same(a, b),
+        (RispExp::Tuple(a), RispExp::Tuple(b)) => all_same(a, b),
+        (RispExp::Vector(a), RispExp::Vector(b)) => all_same(&a.borrow(), &b.borrow()),
+        (RispExp::SortedMap(a), RispExp::SortedMap(b)) =>
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|((ka, va), (kb, vb))| ka == kb && same(va, vb)),
+        (RispExp::SortedSet(a), RispExp::SortedSet(b)) => a == b,
+        (RispExp::Map(a), RispExp::Map(b)) =>
+            a.len() == b.len() && a.iter().all(|(k, va)| b.get(k).is_some_and(|vb| same(va, vb))),
+        (RispExp::Tagged(a), RispExp::Tagged(b)) => a.tag() == b.tag() && same(a.value(), b.value()),
+        _ => false,
+    }
+}
+
+fn all_same(a: &[RispExp], b: &[RispExp]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same(a, b))
+}
+
+fn child(path: &[RispExp], key: RispExp) -> Vec<RispExp> {
+    let mut path = path.to_vec();
+    path.push(key);
+    path
+}
+
+fn index(idx: usize) -> RispExp {
+    RispExp::Int(idx as i64)
+}
+
+// pairs of indices of elements that stay, in order
+fn common(a: &[RispExp], b: &[RispExp]) -> Vec<(usize, usize)> {
+    let mut lengths = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if same(&a[i], &b[j]) {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut pairs = vec![];
+    while i < a.len() && j < b.len() {
+        if same(&a[i], &b[j]) {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    pairs
+}
+
+// close enough that a difference between them is a change rather than a
+// deletion and an insertion: lists with the same head, or atoms of one kind
+fn similar(a: &RispExp, b: &RispExp) -> bool {
+    match (a, b) {
+        (RispExp::List(a), RispExp::List(b)) => match (a.first(), b.first()) {
+            (Some(a), Some(b)) => same(a, b),
+            _ => true,
+        },
+        _ => std::mem::discriminant(a) == std::mem::discriminant(b),
+    }
+}
+
+fn diff_seqs(path: &[RispExp], a: &[RispExp], b: &[RispExp], edits: &mut Vec<Edit>) {
+    let mut pairs = common(a, b);
+    pairs.push((a.len(), b.len()));
+
+    let (mut i, mut j) = (0, 0);
+    for (next_i, next_j) in pairs {
+        // between two kept elements, each element of `a` is changed into the
+        // next similar one of `b` and deleted when there's none
+        while i < next_i {
+            match (j..next_j).find(|k| similar(&a[i], &b[*k])) {
+                Some(k) => {
+                    for (idx, x) in b.iter().enumerate().take(k).skip(j) {
+                        edits.push(Edit::Insert(child(path, index(idx)), x.clone()));
+                    }
+                    diff_at(&child(path, index(i)), &a[i], &b[k], edits);
+                    j = k + 1;
+                },
+                None => edits.push(Edit::Delete(child(path, index(i)), a[i].clone())),
+            }
+            i += 1;
+        }
+        for (idx, x) in b.iter().enumerate().take(next_j).skip(j) {
+            edits.push(Edit::Insert(child(path, index(idx)), x.clone()));
+        }
+
+        i = next_i + 1;
+        j = next_j + 1;
+    }
+}
+
+fn diff_at(path: &[RispExp], a: &RispExp, b: &RispExp, edits: &mut Vec<Edit>) {
+    match (a, b) {
+        _ if same(a, b) => (),
+        (RispExp::List(a), RispExp::List(b)) => diff_seqs(path, a, b, edits),
+        (RispExp::Tuple(a), RispExp::Tuple(b)) => diff_seqs(path, a, b, edits),
+        (RispExp::SortedMap(a), RispExp::SortedMap(b)) => {
+            for (key, va) in a.iter() {
+                match b.get(key) {
+                    Some(vb) => diff_at(&child(path, key.exp().clone()), va, vb, edits),
+                    None => edits.push(Edit::Delete(child(path, key.exp().clone()), va.clone())),
+                }
+            }
+            for (key, vb) in b.iter().filter(|(key, _)| !a.contains_key(key)) {
+                edits.push(Edit::Insert(child(path, key.exp().clone()), vb.clone()));
+            }
+        },
+        (RispExp::Map(a), RispExp::Map(b)) => {
+            let mut keys: Vec<_> = a.keys().chain(b.keys().filter(|key| !a.contains_key(key))).collect();
+            keys.sort_by_key(|key| key.exp().to_string());
+            for key in keys {
+                match (a.get(key), b.get(key)) {
+                    (Some(va), Some(vb)) => diff_at(&child(path, key.exp()), va, vb, edits),
+                    (Some(va), None) => edits.push(Edit::Delete(child(path, key.exp()), va.clone())),
+                    (None, Some(vb)) => edits.push(Edit::Insert(child(path, key.exp()), vb.clone())),
+                    (None, None) => (),
+                }
+            }
+        },
+        // set members can only be taken out or added, their path is the member
+        (RispExp::SortedSet(a), RispExp::SortedSet(b)) => {
+            for x in a.difference(b) {
+                edits.push(Edit::Delete(child(path, x.exp().clone()), x.exp().clone()));
+            }
+            for x in b.difference(a) {
+                edits.push(Edit::Insert(child(path, x.exp().clone()), x.exp().clone()));
+            }
+        },
+        _ => edits.push(Edit::Change(path.to_vec(), a.clone(), b.clone())),
+    }
+}
+
+pub fn diff(a: &RispExp, b: &RispExp) -> Vec<Edit> {
+    let mut edits = vec![];
+    diff_at(&[], a, b, &mut edits);
+    edits
+}
+
+fn keyword(k: &str) -> RispExp {
+    RispExp::Keyword(k.to_string())
+}
+
+// `(sexp-diff a b)`
+pub fn sexp_diff(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let (a, b) = match args {
+        [a, b] => (a, b),
+        _ => return Err(RispErr::Reason("expected two values to compare".to_string())),
+    };
+
+    let edits = diff(a, b)
+        .into_iter()
+        .map(|edit| match edit {
+            Edit::Insert(path, x) => RispExp::List(vec![keyword(":insert"), RispExp::List(path.into()), x].into()),
+            Edit::Delete(path, x) => RispExp::List(vec![keyword(":delete"), RispExp::List(path.into()), x].into()),
+            Edit::Change(path, old, new) => RispExp::List(vec![keyword(":change"), RispExp::List(path.into()), old, new].into()),
+        })
+        .collect();
+
+    Ok(RispExp::List(edits))
+}
+
+// functions and handles are only the same binding when they're the same one
+fn same_binding(a: &RispExp, b: &RispExp) -> bool {
+    match (a, b) {
+        (RispExp::Func(a), RispExp::Func(b)) => *a as usize == *b as usize,
+        (RispExp::EnvFunc(a), RispExp::EnvFunc(b)) => *a as usize == *b as usize,
+        (RispExp::Lambda(a), RispExp::Lambda(b)) => Rc::ptr_eq(&a.body_exps, &b.body_exps),
+        (RispExp::Resource(a), RispExp::Resource(b)) => Rc::ptr_eq(a, b),
+        _ => same(a, b),
+    }
+}
+
+// the bindings added or changed since `before`
+pub fn defined_names(before: &HashMap<String, RispExp>, after: &HashMap<String, RispExp>) -> Vec<String> {
+    after
+        .iter()
+        .filter(|(name, new)| !before.get(*name).is_some_and(|old| same_binding(old, new)))
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+// what `def`s and the like did to an environment since `before`, a line for
+// each binding that was added, changed or removed, for the REPL's `:diff-env`
+pub fn env_changes(before: &HashMap<String, RispExp>, after: &HashMap<String, RispExp>) -> Vec<String> {
+    let names: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    names
+        .into_iter()
+        .filter_map(|name| match (before.get(name), after.get(name)) {
+            (None, Some(new)) => Some(format!("+ {} = {}", name, new)),
+            (Some(old), Some(new)) if !same_binding(old, new) => Some(format!("~ {} = {} (was {})", name, new, old)),
+            (Some(_), None) => Some(format!("- {}", name)),
+            _ => None,
+        })
+        .collect()
+}
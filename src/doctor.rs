@@ -0,0 +1,77 @@
+// `risp doctor` prints what the interpreter sees of its environment and runs
+// a quick self-test of the evaluator, so a report of "it doesn't work" can
+// start from its output.
+
+use std::env;
+use std::io::{self, IsTerminal};
+
+use super::console::Console;
+use super::{default_env, enabled_features, err_message, i18n, parse_eval, COMPILED_FEATURES};
+
+// each expression and what it has to print as
+const SELF_TEST: &[(&str, &str)] = &[
+    ("(+ 1 2)", "3"),
+    ("(- 10 4 1)", "5"),
+    ("(if (< 1 2) 1 2)", "1"),
+    ("(def x 4)", "x"),
+    ("(+ x x)", "8"),
+    ("((fn (a b) (- a b)) 5 3)", "2"),
+    ("(list 1 (list 2 3))", "(1,(2,3))"),
+    ("(take 2 (generator (fn () (yield 1) (yield 2) (yield 3))))", "(1,2)"),
+];
+
+fn yes_no(b: bool) -> &'static str {
+    if b { "yes" } else { "no" }
+}
+
+fn var(name: &str) -> String {
+    env::var(name).unwrap_or("unset".to_string())
+}
+
+fn list(names: &[&str]) -> String {
+    match names {
+        [] => "none".to_string(),
+        _ => names.join(", "),
+    }
+}
+
+pub fn run() -> Result<(), String> {
+    println!("risp {}", env!("CARGO_PKG_VERSION"));
+
+    println!("terminal");
+    println!("  stdin is a terminal: {}", yes_no(io::stdin().is_terminal()));
+    println!("  stdout is a terminal: {}", yes_no(io::stdout().is_terminal()));
+    println!("  TERM: {}", var("TERM"));
+    println!("  NO_COLOR: {}", var("NO_COLOR"));
+    println!("  colors: {}", yes_no(Console::new().colors()));
+
+    println!("language: {} (RISP_LANG: {})", i18n::lang().unwrap_or("en"), var("RISP_LANG"));
+    println!("history file: none, the REPL doesn't keep history");
+    println!("rc file: none, the REPL doesn't load one");
+
+    println!("features");
+    println!("  compiled: {}", list(COMPILED_FEATURES));
+    println!("  enabled: {}", list(&enabled_features()));
+    println!("  RISP_DISABLED_FEATURES: {}", var("RISP_DISABLED_FEATURES"));
+
+    println!("self-test");
+    let env = &mut default_env();
+    let mut failed = 0;
+    for (expr, expected) in SELF_TEST {
+        let res = parse_eval(expr.to_string(), env)
+            .map(|res| res.to_string())
+            .unwrap_or_else(|e| format!("error: {}", err_message(e)));
+
+        if res == *expected {
+            println!("  ok      {} => {}", expr, res);
+        } else {
+            failed += 1;
+            println!("  FAILED  {} => {}, expected {}", expr, res, expected);
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        n => Err(format!("{} of {} self-tests failed", n, SELF_TEST.len())),
+    }
+}
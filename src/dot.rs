@@ -0,0 +1,90 @@
+// GraphViz DOT for looking at the shape of data. `(to-dot value)` draws every
+// list, vector, tuple, map and set as a node with an edge to each of its elements,
+// labelled with the index or key, and atoms as leaves. `risp --ast-dot path`
+// does the same for the forms of a source file, as read before evaluation.
+
+use super::{RispErr, RispExp};
+
+struct Dot {
+    out: String,
+    nodes: usize,
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn indexed(xs: &[RispExp]) -> impl Iterator<Item = (String, &RispExp)> {
+    xs.iter().enumerate().map(|(idx, x)| (idx.to_string(), x))
+}
+
+impl Dot {
+    fn new() -> Dot {
+        Dot { out: "digraph risp {\n    node [fontname=\"monospace\"];\n".to_string(), nodes: 0 }
+    }
+
+    fn node(&mut self, label: &str, shape: &str) -> usize {
+        let id = self.nodes;
+        self.nodes += 1;
+        self.out.push_str(&format!("    n{} [label=\"{}\", shape={}];\n", id, escape(label), shape));
+        id
+    }
+
+    fn edge(&mut self, from: usize, to: usize, label: &str) {
+        self.out.push_str(&format!("    n{} -> n{} [label=\"{}\"];\n", from, to, escape(label)));
+    }
+
+    fn children<'a>(&mut self, label: &str, children: impl Iterator<Item = (String, &'a RispExp)>) -> usize {
+        let id = self.node(label, "box");
+        for (edge, child) in children {
+            let child = self.value(child);
+            self.edge(id, child, &edge);
+        }
+        id
+    }
+
+    fn value(&mut self, exp: &RispExp) -> usize {
+        match exp {
+            RispExp::List(xs) => self.children("list", indexed(xs)),
+            RispExp::Vector(xs) => self.children("vector", indexed(&xs.borrow())),
+            RispExp::Tuple(xs) => self.children("tuple", indexed(xs)),
+            RispExp::SortedMap(map) => self.children("map", map.iter().map(|(k, v)| (k.exp().to_string(), v))),
+            RispExp::Map(map) => {
+                let mut entries: Vec<(String, &RispExp)> = map.iter().map(|(k, v)| (k.exp().to_string(), v)).collect();
+                entries.sort_by(|a, b| a.0.cmp(&b.0));
+                self.children("map", entries.into_iter())
+            },
+            RispExp::SortedSet(set) => self.children("set", set.iter().map(|x| (String::new(), x.exp()))),
+            RispExp::Tagged(tagged) => self.children(&format!("#{}", tagged.tag()), std::iter::once((String::new(), tagged.value()))),
+            RispExp::Str(s) => self.node(&format!("\"{}\"", s), "ellipse"),
+            RispExp::Lambda(_) | RispExp::Func(_) | RispExp::EnvFunc(_) => self.node("fn", "diamond"),
+            _ => self.node(&exp.to_string(), "ellipse"),
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.out.push_str("}\n");
+        self.out
+    }
+}
+
+pub fn to_dot(exp: &RispExp) -> String {
+    let mut dot = Dot::new();
+    dot.value(exp);
+    dot.finish()
+}
+
+// the forms of a file hang off one node for the file itself
+pub fn forms_to_dot(name: &str, forms: &[RispExp]) -> String {
+    let mut dot = Dot::new();
+    dot.children(name, indexed(forms));
+    dot.finish()
+}
+
+// `(to-dot value)`
+pub fn to_dot_args(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    match args {
+        [exp] => Ok(RispExp::Str(to_dot(exp))),
+        _ => Err(RispErr::Reason("expected one value to draw".to_string())),
+    }
+}
@@ -0,0 +1,337 @@
+// EDN, https://github.com/edn-format/edn, so risp data can be exchanged with
+// Clojure tooling and read from config files written by other programs.
+//
+// `(edn-read text)` reads the first element of the text and `(edn-write x)`
+// writes a value back. `nil` reads as the empty list, which is risp's nil.
+// Integers read as ints, and floats are always written with a decimal point so
+// they read back as floats. Tuples are written as vectors. Tags are read by the
+// reader macro or `deftag` reader of the same name, and stay tagged values
+// without one.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::rc::Rc;
+
+use super::queue;
+use super::sorted::SortKey;
+use super::tagged::{self, Tagged};
+use super::{args, numeric, reader, RispEnv, RispErr, RispExp};
+
+struct Parser<'e, 'a> {
+    chars: Vec<char>,
+    pos: usize,
+    // without one, tags are left as tagged values
+    env: Option<&'e mut RispEnv<'a>>,
+}
+
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || matches!(c, ',' | '(' | ')' | '[' | ']' | '{' | '}' | '"' | ';')
+}
+
+impl<'e, 'a> Parser<'e, 'a> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    // commas are whitespace, `;` comments run to the end of the line
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            match c {
+                ';' => while !matches!(self.next(), Some('\n') | None) {},
+                _ if c.is_whitespace() || c == ',' => self.pos += 1,
+                _ => return,
+            }
+        }
+    }
+
+    fn token(&mut self) -> String {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if !is_delimiter(c)) {
+            self.pos += 1;
+        }
+
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    // `None` at the `close` of the enclosing collection
+    fn element(&mut self, close: Option<char>) -> Result<Option<RispExp>, RispErr> {
+        self.skip_whitespace();
+        let c = match self.peek() {
+            Some(c) => c,
+            None if close.is_none() => return Err(RispErr::Reason("expected an EDN element".to_string())),
+            None => return Err(RispErr::Reason(format!("could not find closing `{}`", close.unwrap_or(')')))),
+        };
+
+        if Some(c) == close {
+            self.pos += 1;
+            return Ok(None)
+        }
+
+        let exp = match c {
+            '(' => {
+                self.pos += 1;
+                RispExp::List(self.elements(')')?.into())
+            },
+            '[' => {
+                self.pos += 1;
+                RispExp::Vector(Rc::new(RefCell::new(self.elements(']')?)))
+            },
+            '{' => {
+                self.pos += 1;
+                let xs = self.elements('}')?;
+                if !xs.len().is_multiple_of(2) {
+                    return Err(RispErr::Reason("expected a map to have a value for every key".to_string()))
+                }
+
+                let mut map = BTreeMap::new();
+                for pair in xs.chunks(2) {
+                    map.insert(SortKey::new(pair[0].clone())?, pair[1].clone());
+                }
+                RispExp::SortedMap(Rc::new(map))
+            },
+            ')' | ']' | '}' => return Err(RispErr::Reason(format!("unexpected `{}`", c))),
+            '"' => {
+                self.pos += 1;
+                RispExp::Str(self.string()?)
+            },
+            '\\' => {
+                self.pos += 1;
+                RispExp::Char(self.character()?)
+            },
+            '#' => {
+                self.pos += 1;
+                return self.dispatch(close)
+            },
+            _ => atom(&self.token())?,
+        };
+
+        Ok(Some(exp))
+    }
+
+    fn elements(&mut self, close: char) -> Result<Vec<RispExp>, RispErr> {
+        let mut xs = vec![];
+        while let Some(x) = self.element(Some(close))? {
+            xs.push(x);
+        }
+
+        Ok(xs)
+    }
+
+    fn dispatch(&mut self, close: Option<char>) -> Result<Option<RispExp>, RispErr> {
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                let mut set = BTreeSet::new();
+                for x in self.elements('}')? {
+                    set.insert(SortKey::new(x)?);
+                }
+
+                Ok(Some(RispExp::SortedSet(Rc::new(set))))
+            },
+            Some('#') => {
+                self.pos += 1;
+                match self.token().as_ref() {
+                    "Inf" => Ok(Some(RispExp::Number(f64::INFINITY))),
+                    "-Inf" => Ok(Some(RispExp::Number(f64::NEG_INFINITY))),
+                    "NaN" => Ok(Some(RispExp::Number(f64::NAN))),
+                    token => Err(RispErr::Reason(format!("unknown symbolic value `##{}`", token))),
+                }
+            },
+            // `#_` discards the next element
+            Some('_') => {
+                self.pos += 1;
+                self.element(None)?;
+                self.element(close)
+            },
+            _ => {
+                let tag = self.token();
+                if tag.is_empty() {
+                    return Err(RispErr::Reason("expected a tag after `#`".to_string()))
+                }
+
+                let value = self.element(None)?.expect("element without a closing delimiter");
+                match reader::lookup(&tag) {
+                    Some(f) => f(value).map(Some),
+                    None => {
+                        let tagged = Rc::new(Tagged::new(tag, value));
+                        match self.env.as_deref_mut() {
+                            Some(env) => tagged::eval_tagged(&tagged, env).map(Some),
+                            None => Ok(Some(RispExp::Tagged(tagged))),
+                        }
+                    },
+                }
+            },
+        }
+    }
+
+    fn string(&mut self) -> Result<String, RispErr> {
+        let mut s = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(s),
+                Some('\\') => match self.next() {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some('r') => s.push('\r'),
+                    Some('"') => s.push('"'),
+                    Some('\\') => s.push('\\'),
+                    Some('u') => s.push(self.unicode()?),
+                    Some(c) => return Err(RispErr::Reason(format!("unknown escape `\\{}`", c))),
+                    None => return Err(RispErr::Reason("could not find closing `\"`".to_string())),
+                },
+                Some(c) => s.push(c),
+                None => return Err(RispErr::Reason("could not find closing `\"`".to_string())),
+            }
+        }
+    }
+
+    fn unicode(&mut self) -> Result<char, RispErr> {
+        let hex: String = (0..4).filter_map(|_| self.next()).collect();
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or(RispErr::Reason(format!("invalid unicode escape `\\u{}`", hex)))
+    }
+
+    fn character(&mut self) -> Result<char, RispErr> {
+        // the first char is taken even when it's a delimiter, like `\(`
+        let first = self.next().ok_or(RispErr::Reason("expected a character after `\\`".to_string()))?;
+        let rest = self.token();
+        let c = match (first, rest.as_ref()) {
+            (c, "") => c,
+            ('n', "ewline") => '\n',
+            ('s', "pace") => ' ',
+            ('t', "ab") => '\t',
+            ('r', "eturn") => '\r',
+            ('u', hex) if hex.len() == 4 => {
+                self.pos -= 4;
+                self.unicode()?
+            },
+            _ => return Err(RispErr::Reason(format!("unknown character `\\{}{}`", first, rest))),
+        };
+
+        Ok(c)
+    }
+}
+
+fn atom(token: &str) -> Result<RispExp, RispErr> {
+    let numeric = match token.strip_prefix(['+', '-']) {
+        Some(rest) => rest.starts_with(|c: char| c.is_ascii_digit()),
+        None => token.starts_with(|c: char| c.is_ascii_digit()),
+    };
+
+    match token {
+        "nil" => Ok(RispExp::List(vec![].into())),
+        "false" => Ok(RispExp::Bool(false)),
+        "true" => Ok(RispExp::Bool(true)),
+        _ if numeric => match numeric::parse(token.trim_end_matches('N')) {
+            Some(n) => Ok(n),
+            None => token
+                .trim_end_matches(['N', 'M'])
+                .parse()
+                .map(RispExp::Number)
+                .map_err(|_| RispErr::Reason(format!("invalid number '{}'", token))),
+        },
+        _ if token.starts_with(':') => Ok(RispExp::Keyword(token.to_string())),
+        _ => Ok(RispExp::Symbol(token.to_string())),
+    }
+}
+
+// `(edn-read text)`
+pub fn read(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let text = args::string("edn-read", args, 0)?;
+
+    let mut parser = Parser { chars: text.chars().collect(), pos: 0, env: Some(env) };
+    Ok(parser.element(None)?.expect("element without a closing delimiter"))
+}
+
+// reads text written by `write_exp` back, outside of any evaluation
+pub fn read_data(text: &str) -> Result<RispExp, RispErr> {
+    let mut parser = Parser { chars: text.chars().collect(), pos: 0, env: None };
+    Ok(parser.element(None)?.expect("element without a closing delimiter"))
+}
+
+pub fn write_string(s: &str) -> String {
+    let mut out = "\"".to_string();
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn write_all<'a>(xs: impl Iterator<Item = &'a RispExp>) -> Result<String, RispErr> {
+    let xs: Vec<String> = xs.map(write_exp).collect::<Result<_, _>>()?;
+    Ok(xs.join(" "))
+}
+
+pub fn write_exp(exp: &RispExp) -> Result<String, RispErr> {
+    if let Some(literal) = tagged::literal(exp) {
+        return Ok(format!("#{} {}", literal.tag(), write_exp(literal.value())?))
+    }
+
+    match exp {
+        RispExp::Bool(b) => Ok(b.to_string()),
+        RispExp::Number(n) if n.is_nan() => Ok("##NaN".to_string()),
+        RispExp::Number(n) if n.is_infinite() => Ok(if *n > 0.0 { "##Inf" } else { "##-Inf" }.to_string()),
+        RispExp::Number(n) => Ok(format!("{:?}", n)),
+        RispExp::Int(n) => Ok(n.to_string()),
+        // the `N` suffix of big integers, rationals are written like Clojure's
+        #[cfg(feature = "bignum")]
+        RispExp::Exact(r) if r.is_integer() => Ok(format!("{}N", r)),
+        #[cfg(feature = "bignum")]
+        RispExp::Exact(r) => Ok(r.to_string()),
+        RispExp::Str(s) => Ok(write_string(s)),
+        RispExp::Char(c) => Ok(match c {
+            '\n' => "\\newline".to_string(),
+            ' ' => "\\space".to_string(),
+            '\t' => "\\tab".to_string(),
+            '\r' => "\\return".to_string(),
+            _ => format!("\\{}", c),
+        }),
+        RispExp::Symbol(s) | RispExp::Keyword(s) => Ok(s.clone()),
+        RispExp::List(xs) => Ok(format!("({})", write_all(xs.iter())?)),
+        RispExp::Tuple(xs) => Ok(format!("[{}]", write_all(xs.iter())?)),
+        RispExp::Vector(xs) => Ok(format!("[{}]", write_all(xs.borrow().iter())?)),
+        RispExp::SortedMap(map) => {
+            let pairs: Vec<String> = map
+                .iter()
+                .map(|(k, v)| Ok(format!("{} {}", write_exp(k.exp())?, write_exp(v)?)))
+                .collect::<Result<_, RispErr>>()?;
+            Ok(format!("{{{}}}", pairs.join(", ")))
+        },
+        RispExp::SortedSet(set) => Ok(format!("#{{{}}}", write_all(set.iter().map(SortKey::exp))?)),
+        RispExp::Map(map) => {
+            let pairs: Vec<String> = map
+                .iter()
+                .map(|(k, v)| Ok(format!("{} {}", write_exp(&k.exp())?, write_exp(v)?)))
+                .collect::<Result<_, RispErr>>()?;
+            Ok(format!("{{{}}}", pairs.join(", ")))
+        },
+        RispExp::Queue(queue) => Ok(format!("#queue ({})", write_all(queue.values().iter())?)),
+        RispExp::Stack(stack) => Ok(format!("#stack ({})", write_all(queue::stack_values(stack).iter())?)),
+        RispExp::Tagged(tagged) => Ok(format!("#{} {}", tagged.tag(), write_exp(tagged.value())?)),
+        _ => Err(RispErr::Reason(format!("'{}' can not be written as EDN", exp))),
+    }
+}
+
+// `(edn-write x)`
+pub fn write(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    match args {
+        [exp] => Ok(RispExp::Str(write_exp(exp)?)),
+        _ => Err(RispErr::Reason("expected one value to write".to_string())),
+    }
+}
@@ -0,0 +1,89 @@
+// Environments as values. `(current-env)` is the scope it's evaluated in and
+// `(make-env parent)` a new empty scope inside another, or inside a fresh
+// global environment without one, for evaluating code away from the program's
+// own bindings. `(eval expr env)` evaluates in one, where `def`s stay, and
+// `(read s)`, the one form written in `s`
+pub fn read(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let src = args::string("read", args, 0)?;
+    let mut forms = parse_forms(src.to_string())?;
+    match forms.len() {
+        1 => Ok(forms.remove(0)),
+        0 => Err(RispErr::Reason("`read` expected a form, got an empty string".to_string())),
+        n => Err(RispErr::Reason(format!("`read` expected one form, got {}", n))),
+    }
+}
+
+// `(env-get env 'x)` looks a name up in it. `(read "(+ 1 2)")` reads text into
+// the data `eval` takes, without evaluating it.
+//
+// A value holds on to the bindings of each scope, the outermost first, which
+// are shared with the scopes themselves, so a `def` in one shows up in the
+// other.
+
+use std::rc::Rc;
+
+use super::{args, default_env, env_get, eval, parse_forms, unbound_symbol, Frame, RispEnv, RispErr, RispExp};
+
+fn frames(env: &RispEnv) -> Vec<Frame> {
+    let mut frames = match env.outer {
+        Some(outer) => frames(outer),
+        None => vec![],
+    };
+    frames.push(env.data.clone());
+    frames
+}
+
+// runs `f` in the scopes, each inside the one before it
+pub fn within<T>(frames: &[Frame], outer: Option<&RispEnv>, f: impl FnOnce(&mut RispEnv) -> T) -> T {
+    match frames {
+        [] | [_] => {
+            let data = frames.first().cloned().unwrap_or_default();
+            f(&mut RispEnv { data, outer })
+        },
+        [first, rest @ ..] => within(rest, Some(&RispEnv { data: first.clone(), outer }), f),
+    }
+}
+
+fn env_arg<'a>(op: &str, args: &'a [RispExp], idx: usize) -> Result<&'a [Frame], RispErr> {
+    match args::get(op, args, idx, "an environment")? {
+        RispExp::Env(frames) => Ok(frames),
+        exp => Err(args::wrong(op, idx, "an environment", exp)),
+    }
+}
+
+// `(current-env)`
+pub fn current(env: &RispEnv) -> RispExp {
+    RispExp::Env(Rc::new(frames(env)))
+}
+
+// `(make-env [parent])`
+pub fn make_env(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let mut frames = match args {
+        [] => vec![default_env().data],
+        [_] => env_arg("make-env", args, 0)?.to_vec(),
+        _ => return Err(RispErr::Reason("expected nothing or a parent environment".to_string())),
+    };
+    frames.push(Frame::default());
+
+    Ok(RispExp::Env(Rc::new(frames)))
+}
+
+// `(eval expr [env])`, in the current environment without one
+pub fn eval_in(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    match args {
+        [expr] => eval(expr, env),
+        [expr, _] => within(env_arg("eval", args, 1)?, None, |env| eval(expr, env)),
+        _ => Err(RispErr::Reason("expected an expression and an environment".to_string())),
+    }
+}
+
+// `(env-get env 'x)`
+pub fn env_get_args(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let frames = env_arg("env-get", args, 0)?;
+    let name = match args::get("env-get", args, 1, "a quoted name")? {
+        RispExp::Symbol(name) => name,
+        exp => return Err(args::wrong("env-get", 1, "a quoted name", exp)),
+    };
+
+    within(frames, None, |env| env_get(name, env)).ok_or_else(|| unbound_symbol(name))
+}
@@ -0,0 +1,148 @@
+// Longer, teaching-oriented explanations of the errors beginners hit most,
+// each with an example of correct usage. The REPL shows one for the last
+// error on `:explain`, or for every error when started with `--explain`.
+
+use super::args::ArgErr;
+use super::RispErr;
+
+enum Topic {
+    UnknownSymbol(String),
+    NotAFunction,
+    Arity,
+    ExpectedNumber,
+    // what a builtin's argument should have been, and its position from 1
+    WrongArgument { op: String, expected: String, position: usize },
+    UnclosedList,
+    UnexpectedClose,
+    UnclosedString,
+    IfTest,
+    Def,
+    Yield,
+    Restart,
+}
+
+fn topic(e: &RispErr) -> Option<Topic> {
+    let msg = match e {
+        RispErr::Reason(msg) => msg,
+        RispErr::Args(ArgErr::Count { .. }) => return Some(Topic::Arity),
+        RispErr::Args(ArgErr::Wrong { expected, .. }) if expected == "a number" => return Some(Topic::ExpectedNumber),
+        RispErr::Args(ArgErr::Wrong { op, idx, expected, .. }) => return Some(Topic::WrongArgument {
+            op: op.clone(),
+            expected: expected.clone(),
+            position: idx + 1,
+        }),
+        RispErr::GeneratorFull => return Some(Topic::Yield),
+        RispErr::Restart { .. } => return Some(Topic::Restart),
+    };
+
+    match msg.as_ref() {
+        _ if msg.starts_with("unexpected symbol k=") => {
+            let name = msg.trim_start_matches("unexpected symbol k='");
+            let name = name.split_once("', it needs").map_or(name.trim_end_matches('\''), |(name, _)| name);
+            Some(Topic::UnknownSymbol(name.to_string()))
+        },
+        "first form must be a function" => Some(Topic::NotAFunction),
+        _ if msg.contains("expected ") && msg.contains(" arguments, got ") => Some(Topic::Arity),
+        "expected a number" | "expected at least one number" | "expected two numbers" => Some(Topic::ExpectedNumber),
+        "could not find closing `)`" => Some(Topic::UnclosedList),
+        "unexpected `)`" => Some(Topic::UnexpectedClose),
+        "could not find closing `\"`" => Some(Topic::UnclosedString),
+        _ if msg.starts_with("unexpected test form") => Some(Topic::IfTest),
+        _ if msg.starts_with("no restart named") => Some(Topic::Restart),
+        "expected first form to be a symbol" | "def can only have two forms " => Some(Topic::Def),
+        _ => None,
+    }
+}
+
+fn text(topic: &Topic) -> (String, &'static str) {
+    match topic {
+        Topic::UnknownSymbol(name) => (
+            format!(
+                "'{}' isn't defined. A symbol evaluates to the value it was bound to with\n\
+                 `def` or as a function parameter, so it has to be defined before it's\n\
+                 used. Check the spelling, or define it first.",
+                name
+            ),
+            "(def x 10)\n(+ x 1)",
+        ),
+        Topic::NotAFunction => (
+            "The first thing in a list is called as a function, but this one isn't a\n\
+             function. A list of data needs `list`, and a value on its own doesn't\n\
+             need parentheses at all."
+                .to_string(),
+            "(list 1 2 3)\n(+ 1 2)",
+        ),
+        Topic::Arity => (
+            "A function was called with a different number of arguments than it has\n\
+             parameters. Every parameter needs exactly one argument."
+                .to_string(),
+            "(def add (fn (a b) (+ a b)))\n(add 1 2)",
+        ),
+        Topic::ExpectedNumber => (
+            "Arithmetic and comparisons only work on numbers. A string like \"1\" is\n\
+             text, not a number, even when it looks like one."
+                .to_string(),
+            "(+ 1 2)\n(< 1 2 3)",
+        ),
+        Topic::WrongArgument { op, expected, position } => (
+            format!(
+                "`{}` needs {} as argument {}. Arguments are evaluated before the\n\
+                 call, so check what the one in that position evaluates to, and that\n\
+                 the arguments are in the right order.",
+                op, expected, position
+            ),
+            "(string? \"abc\")\n(number? 1)",
+        ),
+        Topic::UnclosedList => (
+            "A list was opened with `(` but never closed. Every `(` needs a matching\n\
+             `)`, count them from the start of the expression."
+                .to_string(),
+            "(+ 1 (+ 2 3))",
+        ),
+        Topic::UnexpectedClose => (
+            "There's a `)` that doesn't close anything, usually one too many at the\n\
+             end of an expression."
+                .to_string(),
+            "(+ 1 2)",
+        ),
+        Topic::UnclosedString => (
+            "A string was started with `\"` but never ended. Strings need a `\"` on\n\
+             both sides."
+                .to_string(),
+            "(list \"hello\" \"world\")",
+        ),
+        Topic::IfTest => (
+            "`if` decides which branch to evaluate from its test, and the test has\n\
+             to be `true` or `false`. Comparisons like `=` or `<` give you one."
+                .to_string(),
+            "(if (< 1 2) \"smaller\" \"not smaller\")",
+        ),
+        Topic::Def => (
+            "`def` takes exactly two things: the name to define, which must be a\n\
+             symbol, and the expression whose value it gets."
+                .to_string(),
+            "(def answer 42)",
+        ),
+        Topic::Yield => (
+            "`yield` hands a value to whoever consumes a generator, so it can only\n\
+             be used inside the function given to `generator`."
+                .to_string(),
+            "(def nums (generator (fn () (yield 1) (yield 2))))\n(take 2 nums)",
+        ),
+        Topic::Restart => (
+            "`invoke-restart` can only pick a restart set up by an enclosing\n\
+             `restart-case` that is still running, usually from inside a handler\n\
+             installed with `handler-bind`."
+                .to_string(),
+            "(handler-bind (fn (c) (invoke-restart 'use-zero))\n  (restart-case (signal :oops) (use-zero () 0)))",
+        ),
+    }
+}
+
+// `None` when there's nothing more to say about the error than its message
+pub fn explain(e: &RispErr) -> Option<String> {
+    topic(e).map(|topic| {
+        let (text, example) = text(&topic);
+        format!("{}\n\nFor example:\n\n{}", text, example)
+    })
+}
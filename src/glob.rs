@@ -0,0 +1,100 @@
+// Shell-style file name patterns: `(glob "logs/*.txt")` is the sorted list of
+// files matching the pattern, where `*` matches any run of characters in a
+// name and `?` any one character. Like a shell, a wildcard doesn't match a
+// leading `.` unless the pattern has one too. Paths come back the way the
+// pattern spelled them, so they can be handed to the other file builtins.
+
+use std::fs;
+
+use super::{args, replay, sandbox, RispErr, RispExp};
+
+// whether `name` matches `pattern`, backtracking to the last `*` on a mismatch
+fn matches(pattern: &str, name: &str) -> bool {
+    if name.starts_with('.') && !pattern.starts_with('.') {
+        return false
+    }
+
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut last_star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                last_star = Some((p, n));
+                p += 1;
+            },
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            },
+            _ => match last_star {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    last_star = Some((star, matched + 1));
+                },
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn join(dir: &str, name: &str) -> String {
+    match dir {
+        "" => name.to_string(),
+        _ if dir.ends_with('/') => format!("{}{}", dir, name),
+        _ => format!("{}/{}", dir, name),
+    }
+}
+
+pub fn glob(pattern: &str) -> Result<Vec<String>, RispErr> {
+    let mut paths = vec![if pattern.starts_with('/') { "/".to_string() } else { String::new() }];
+
+    for component in pattern.split('/').filter(|c| !c.is_empty()) {
+        if !component.contains(['*', '?']) {
+            paths = paths.iter().map(|path| join(path, component)).collect();
+            continue
+        }
+
+        let mut matched = vec![];
+        for path in &paths {
+            let dir = sandbox::resolve(if path.is_empty() { "." } else { path })?;
+            // a directory that isn't there has nothing in it to match
+            let entries = match fs::read_dir(dir) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if matches(component, &name) {
+                    matched.push(join(path, &name));
+                }
+            }
+        }
+        paths = matched;
+    }
+
+    let mut files = vec![];
+    for path in paths {
+        if sandbox::resolve(&path)?.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+// `(glob pattern)`
+pub fn glob_builtin(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    args::exactly("glob", args, 1)?;
+    let pattern = args::string("glob", args, 0)?;
+
+    replay::recorded("glob", || {
+        Ok(RispExp::List(glob(pattern)?.into_iter().map(RispExp::Str).collect()))
+    })
+}
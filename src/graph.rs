@@ -0,0 +1,203 @@
+// `risp graph path` reads the top level `def`, `defn` and `defonce` forms of a
+// file and prints which definitions each one refers to, then the definitions
+// nothing else uses and the groups of definitions that refer to each other in
+// a cycle. A function calling itself isn't reported as a cycle, and the other
+// top level forms of the file count as uses.
+//
+// In a running program, `(who-calls 'foo)` is the global functions whose body
+// refers to `foo` and `(uses 'foo)` the global definitions `foo` refers to.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{args, global_env, RispEnv, RispErr, RispExp};
+
+const DEFINING: [&str; 5] = ["def", "define", "defn", "defonce", "defmacro"];
+
+pub struct Graph {
+    // each definition, in order, with the definitions it refers to
+    pub deps: Vec<(String, BTreeSet<String>)>,
+    pub unused: Vec<String>,
+    pub cycles: Vec<Vec<String>>,
+}
+
+fn definition(form: &RispExp) -> Option<(&str, &[RispExp])> {
+    match form {
+        RispExp::List(list) => match &list[..] {
+            [RispExp::Symbol(head), RispExp::Symbol(name), rest @ ..] if DEFINING.contains(&head.as_str()) =>
+                Some((name, rest)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// the names a param list binds, including annotated ones like `(x : number)`
+fn params(exp: Option<&RispExp>) -> Vec<&str> {
+    match exp {
+        Some(RispExp::List(params)) => params
+            .iter()
+            .filter_map(|param| match param {
+                RispExp::Symbol(name) => Some(name.as_str()),
+                RispExp::List(annotated) => match annotated.first() {
+                    Some(RispExp::Symbol(name)) => Some(name.as_str()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+// free symbols of `exp` that name one of `names`, leaving out lambda params
+fn references(exp: &RispExp, names: &BTreeSet<String>, bound: &[&str], found: &mut BTreeSet<String>) {
+    match exp {
+        RispExp::Symbol(s) if names.contains(s) && !bound.contains(&s.as_str()) => {
+            found.insert(s.clone());
+        },
+        // quoted symbols are data
+        RispExp::List(list) if matches!(list.first(), Some(RispExp::Symbol(head)) if head == "quote") => (),
+        RispExp::List(list) => {
+            let bound: Vec<&str> = match list.first() {
+                Some(RispExp::Symbol(head)) if head == "fn" => bound.iter().copied().chain(params(list.get(1))).collect(),
+                _ => bound.to_vec(),
+            };
+            for x in list.iter() {
+                references(x, names, &bound, found);
+            }
+        },
+        RispExp::Vector(xs) => xs.borrow().iter().for_each(|x| references(x, names, bound, found)),
+        RispExp::Map(map) => map.values().for_each(|x| references(x, names, bound, found)),
+        RispExp::Tuple(xs) => xs.iter().for_each(|x| references(x, names, bound, found)),
+        _ => (),
+    }
+}
+
+// Tarjan's strongly connected components, keeping the ones of more than one
+struct Components<'g> {
+    deps: &'g BTreeMap<&'g str, &'g BTreeSet<String>>,
+    index: BTreeMap<&'g str, (usize, usize)>,
+    stack: Vec<&'g str>,
+    cycles: Vec<Vec<String>>,
+}
+
+impl<'g> Components<'g> {
+    fn visit(&mut self, name: &'g str) {
+        let idx = self.index.len();
+        self.index.insert(name, (idx, idx));
+        self.stack.push(name);
+
+        for dep in self.deps[name].iter() {
+            let dep = dep.as_str();
+            match self.index.get(dep) {
+                None => {
+                    self.visit(dep);
+                    let low = self.index[dep].1.min(self.index[name].1);
+                    self.index.get_mut(name).expect("visited").1 = low;
+                },
+                Some(&(dep_idx, _)) if self.stack.contains(&dep) => {
+                    let low = dep_idx.min(self.index[name].1);
+                    self.index.get_mut(name).expect("visited").1 = low;
+                },
+                Some(_) => (),
+            }
+        }
+
+        let (idx, low) = self.index[name];
+        if idx == low {
+            let at = self.stack.iter().rposition(|n| *n == name).expect("on the stack");
+            let component: Vec<String> = self.stack.drain(at..).map(|n| n.to_string()).collect();
+            if component.len() > 1 {
+                self.cycles.push(component);
+            }
+        }
+    }
+}
+
+pub fn graph(forms: &[RispExp]) -> Graph {
+    let names: BTreeSet<String> = forms.iter().filter_map(definition).map(|(name, _)| name.to_string()).collect();
+
+    let mut deps: Vec<(String, BTreeSet<String>)> = vec![];
+    let mut used = BTreeSet::new();
+    for form in forms {
+        match definition(form) {
+            Some((name, rest)) => {
+                let mut found = BTreeSet::new();
+                let bound = match form {
+                    RispExp::List(list) if matches!(&list[0], RispExp::Symbol(head) if matches!(head.as_str(), "defn" | "defmacro")) => params(rest.first()),
+                    _ => vec![],
+                };
+                rest.iter().for_each(|x| references(x, &names, &bound, &mut found));
+                used.extend(found.iter().filter(|dep| *dep != name).cloned());
+                match deps.iter_mut().find(|(def, _)| def == name) {
+                    Some((_, existing)) => existing.extend(found),
+                    None => deps.push((name.to_string(), found)),
+                }
+            },
+            None => references(form, &names, &[], &mut used),
+        }
+    }
+
+    let unused = deps.iter().map(|(name, _)| name.clone()).filter(|name| !used.contains(name)).collect();
+
+    let by_name: BTreeMap<&str, &BTreeSet<String>> = deps.iter().map(|(name, deps)| (name.as_str(), deps)).collect();
+    let mut components = Components { deps: &by_name, index: BTreeMap::new(), stack: vec![], cycles: vec![] };
+    for (name, _) in &deps {
+        if !components.index.contains_key(name.as_str()) {
+            components.visit(name);
+        }
+    }
+    let cycles = components.cycles;
+
+    Graph { deps, unused, cycles }
+}
+
+// the global functions with the definitions each one refers to, builtins
+// aren't definitions
+fn defined_deps(env: &RispEnv) -> BTreeMap<String, BTreeSet<String>> {
+    let globals = global_env(env).data.borrow();
+    let names: BTreeSet<String> =
+        globals.iter().filter(|(_, v)| !matches!(v, RispExp::Func(_) | RispExp::EnvFunc(_))).map(|(k, _)| k.clone()).collect();
+
+    globals
+        .iter()
+        .filter_map(|(name, v)| match v {
+            RispExp::Lambda(lambda) => {
+                let closure = lambda.closed_over();
+                let bound: Vec<&str> =
+                    params(Some(&lambda.params_exp)).into_iter().chain(closure.keys().map(String::as_str)).collect();
+                let mut found = BTreeSet::new();
+                lambda.body_exps.iter().for_each(|x| references(x, &names, &bound, &mut found));
+                Some((name.clone(), found))
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+fn name_arg<'a>(op: &str, args: &'a [RispExp]) -> Result<&'a str, RispErr> {
+    args::exactly(op, args, 1)?;
+    match &args[0] {
+        RispExp::Symbol(name) => Ok(name),
+        exp => Err(args::wrong(op, 0, "a quoted symbol", exp)),
+    }
+}
+
+// `(who-calls 'name)`
+pub fn who_calls(args: &[RispExp], env: &RispEnv) -> Result<RispExp, RispErr> {
+    let name = name_arg("who-calls", args)?;
+    Ok(RispExp::List(
+        defined_deps(env)
+            .into_iter()
+            .filter(|(_, deps)| deps.contains(name))
+            .map(|(caller, _)| RispExp::Symbol(caller))
+            .collect(),
+    ))
+}
+
+// `(uses 'name)`
+pub fn uses(args: &[RispExp], env: &RispEnv) -> Result<RispExp, RispErr> {
+    let name = name_arg("uses", args)?;
+    let deps = defined_deps(env).remove(name).unwrap_or_default();
+    Ok(RispExp::List(deps.into_iter().map(RispExp::Symbol).collect()))
+}
@@ -0,0 +1,89 @@
+// Hash maps, `{:a 1 :b 2}`, for looking values up by key without keeping the
+// keys in order like a `sorted-map` does. Keys are symbols, keywords, strings
+// and numbers, where an int and a float are different keys even when they're
+// equal. The literal reads as a map, and evaluating it gives a new one of the
+// values of its keys and values, so `{:total (+ a b)}` works like the
+// `(hash-map :total (+ a b))` it's the same as.
+//
+// The hasher has no random seed, so a map with the same keys always lists
+// them in the same order, run after run.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::BuildHasherDefault;
+use std::rc::Rc;
+
+use super::{RispErr, RispExp};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MapKey {
+    Symbol(String),
+    Keyword(String),
+    Str(String),
+    Int(i64),
+    // by its bits, so it can be hashed
+    Float(u64),
+}
+
+pub type Entries = HashMap<MapKey, RispExp, BuildHasherDefault<DefaultHasher>>;
+
+impl MapKey {
+    pub fn new(exp: &RispExp) -> Result<MapKey, RispErr> {
+        match exp {
+            RispExp::Symbol(s) => Ok(MapKey::Symbol(s.clone())),
+            RispExp::Keyword(k) => Ok(MapKey::Keyword(k.clone())),
+            RispExp::Str(s) => Ok(MapKey::Str(s.clone())),
+            RispExp::Int(n) => Ok(MapKey::Int(*n)),
+            RispExp::Number(n) => Ok(MapKey::Float(n.to_bits())),
+            _ => Err(RispErr::Reason(format!("'{}' can not be used as a map key", exp))),
+        }
+    }
+
+    pub fn exp(&self) -> RispExp {
+        match self {
+            MapKey::Symbol(s) => RispExp::Symbol(s.clone()),
+            MapKey::Keyword(k) => RispExp::Keyword(k.clone()),
+            MapKey::Str(s) => RispExp::Str(s.clone()),
+            MapKey::Int(n) => RispExp::Int(*n),
+            MapKey::Float(bits) => RispExp::Number(f64::from_bits(*bits)),
+        }
+    }
+}
+
+pub fn display(map: &Entries) -> String {
+    let xs: Vec<String> = map.iter().map(|(k, v)| format!("{} {}", k.exp(), v)).collect();
+    format!("{{{}}}", xs.join(", "))
+}
+
+// `(k v)` for each entry
+pub fn entries(map: &Entries) -> Vec<RispExp> {
+    map.iter().map(|(k, v)| RispExp::List(vec![k.exp(), v.clone()].into())).collect()
+}
+
+// the map of `k v...`, the last value of a key that's there twice
+pub fn from_pairs(xs: &[RispExp]) -> Result<RispExp, RispErr> {
+    if !xs.len().is_multiple_of(2) {
+        return Err(RispErr::Reason("expected a value for every key".to_string()))
+    }
+
+    let mut map = Entries::default();
+    for pair in xs.chunks(2) {
+        map.insert(MapKey::new(&pair[0])?, pair[1].clone());
+    }
+
+    Ok(RispExp::Map(Rc::new(map)))
+}
+
+// `(hash-map k v...)`
+fn hash_map(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    from_pairs(args)
+}
+
+fn is_map(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    Ok(RispExp::Bool(matches!(args.first(), Some(RispExp::Map(_)))))
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert("hash-map".to_string(), RispExp::Func(hash_map));
+    data.insert("map?".to_string(), RispExp::Func(is_map));
+}
@@ -0,0 +1,139 @@
+// Hiccup-style HTML. An element is a list, vector or tuple starting with a
+// `:tag`, optionally followed by a map of attributes, then its children:
+//
+//     (html (list :div.note {:id "x"} "hello " (list :b "you")))
+//
+// renders `<div class="note" id="x">hello <b>you</b></div>`. Tags can carry
+// an id and classes as `:div#main.a.b`. Text and attribute values are
+// escaped, `true` attributes are written bare and `false` ones left out, and
+// a list that doesn't start with a tag is a sequence of children.
+
+use super::template::escape_html;
+use super::{RispErr, RispExp};
+
+const VOID_ELEMENTS: [&str; 13] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+
+// `div#main.a.b` into its tag name, id and classes
+fn split_tag(tag: &str) -> (&str, Option<&str>, Vec<&str>) {
+    let end = tag.find(['#', '.']).unwrap_or(tag.len());
+    let (name, mut rest) = tag.split_at(end);
+    let mut id = None;
+    let mut classes = vec![];
+
+    while !rest.is_empty() {
+        let marker = &rest[..1];
+        let end = rest[1..].find(['#', '.']).map(|idx| idx + 1).unwrap_or(rest.len());
+        match marker {
+            "#" => id = Some(&rest[1..end]),
+            _ => classes.push(&rest[1..end]),
+        }
+        rest = &rest[end..];
+    }
+
+    (name, id, classes)
+}
+
+fn text(exp: &RispExp) -> String {
+    match exp {
+        RispExp::Str(s) => s.clone(),
+        _ => exp.to_string(),
+    }
+}
+
+fn attributes(out: &mut String, id: Option<&str>, classes: &[&str], attrs: Option<&RispExp>) -> Result<(), RispErr> {
+    if let Some(id) = id {
+        out.push_str(&format!(" id=\"{}\"", escape_html(id)));
+    }
+    if !classes.is_empty() {
+        out.push_str(&format!(" class=\"{}\"", escape_html(&classes.join(" "))));
+    }
+
+    // a hash map's attributes are written in order too, like a sorted one's
+    let attrs: Vec<(RispExp, &RispExp)> = match attrs {
+        Some(RispExp::SortedMap(attrs)) => attrs.iter().map(|(k, v)| (k.exp().clone(), v)).collect(),
+        Some(RispExp::Map(attrs)) => {
+            let mut attrs: Vec<_> = attrs.iter().map(|(k, v)| (k.exp(), v)).collect();
+            attrs.sort_by_key(|(k, _)| k.to_string());
+            attrs
+        },
+        _ => return Ok(()),
+    };
+
+    for (key, value) in attrs {
+        let name = match &key {
+            RispExp::Symbol(s) | RispExp::Keyword(s) | RispExp::Str(s) => s.trim_start_matches(':'),
+            _ => return Err(RispErr::Reason(format!("expected an attribute name, got '{}'", key))),
+        };
+
+        match value {
+            RispExp::Bool(false) => (),
+            RispExp::Bool(true) => out.push_str(&format!(" {}", name)),
+            _ => out.push_str(&format!(" {}=\"{}\"", name, escape_html(&text(value)))),
+        }
+    }
+
+    Ok(())
+}
+
+fn render(out: &mut String, exp: &RispExp) -> Result<(), RispErr> {
+    let vector;
+    let nodes: &[RispExp] = match exp {
+        RispExp::List(nodes) => nodes,
+        RispExp::Vector(nodes) => {
+            vector = nodes.borrow().clone();
+            &vector
+        },
+        RispExp::Tuple(nodes) => nodes,
+        _ => {
+            out.push_str(&escape_html(&text(exp)));
+            return Ok(())
+        },
+    };
+
+    let tag = match nodes.first() {
+        Some(RispExp::Keyword(tag)) => &tag[1..],
+        _ => {
+            for node in nodes {
+                render(out, node)?;
+            }
+            return Ok(())
+        },
+    };
+
+    let (name, id, classes) = split_tag(tag);
+    let (attrs, children) = match nodes.get(1) {
+        Some(attrs @ (RispExp::SortedMap(_) | RispExp::Map(_))) => (Some(attrs), &nodes[2..]),
+        _ => (None, &nodes[1..]),
+    };
+
+    out.push('<');
+    out.push_str(name);
+    attributes(out, id, &classes, attrs)?;
+    out.push('>');
+
+    if VOID_ELEMENTS.contains(&name) {
+        if !children.is_empty() {
+            return Err(RispErr::Reason(format!("<{}> can not have children", name)))
+        }
+        return Ok(())
+    }
+
+    for child in children {
+        render(out, child)?;
+    }
+    out.push_str(&format!("</{}>", name));
+
+    Ok(())
+}
+
+// `(html element...)`
+pub fn html(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let mut out = String::new();
+    for arg in args {
+        render(&mut out, arg)?;
+    }
+
+    Ok(RispExp::Str(out))
+}
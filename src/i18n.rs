@@ -0,0 +1,245 @@
+// Error messages in other languages, picked with `RISP_LANG` or `--lang`.
+//
+// The catalog maps each English message template to its translation, `{}`
+// stands for the parts that vary (names, counts, values) and is filled in the
+// same order. Messages without a translation are shown in English.
+//
+// The builtins' argument errors aren't matched by their message, each
+// language has a template for every kind of them and the names of the kinds
+// of values they expect.
+
+use std::cell::RefCell;
+
+use super::args::{self, ArgErr, Bound};
+use super::RispExp;
+
+type Catalog = &'static [(&'static str, &'static str)];
+
+struct Lang {
+    catalog: Catalog,
+    args: ArgTexts,
+}
+
+// `{}` are the builtin, what it expected, the argument's position and what it
+// got instead, or the builtin, the argument count and how many it got
+struct ArgTexts {
+    wrong: &'static str,
+    missing: &'static str,
+    exactly: &'static str,
+    at_least: &'static str,
+    at_most: &'static str,
+    one_argument: &'static str,
+    arguments: &'static str,
+    // `a number` and the other kinds of values, as `args::type_name` has them
+    kinds: Catalog,
+}
+
+thread_local! {
+    static LANG: RefCell<Option<(&'static str, &'static Lang)>> = const { RefCell::new(None) };
+}
+
+const ES: &[(&str, &str)] = &[
+    ("unexpected symbol k='{}', it needs the `{}` feature", "símbolo desconocido '{}', necesita la característica `{}`"),
+    ("unexpected symbol k='{}'", "símbolo desconocido '{}'"),
+    ("first form must be a function", "la primera forma debe ser una función"),
+    ("expected {} arguments, got {}", "se esperaban {} argumentos, se recibieron {}"),
+    ("expected a number", "se esperaba un número"),
+    ("expected at least one number", "se esperaba al menos un número"),
+    ("expected two numbers", "se esperaban dos números"),
+    ("expected a string", "se esperaba una cadena"),
+    ("division by zero", "división por cero"),
+    ("could not get token", "no se pudo obtener un token"),
+    ("could not find closing `)`", "no se encontró el `)` de cierre"),
+    ("unexpected `)`", "`)` inesperado"),
+    ("could not find closing `\"`", "no se encontró la `\"` de cierre"),
+    ("unexpected form", "forma inesperada"),
+    ("unexpected test form='{}'", "condición inesperada '{}'"),
+    ("expected first form to be a symbol", "se esperaba que la primera forma fuera un símbolo"),
+    ("unknown keyword argument ':{}'", "argumento con nombre desconocido ':{}'"),
+    ("yield outside of a generator", "yield fuera de un generador"),
+    ("no restart named '{}' is active", "no hay ningún reinicio activo llamado '{}'"),
+    ("unhandled condition '{}'", "condición no manejada '{}'"),
+    ("could not read '{}': {}", "no se pudo leer '{}': {}"),
+];
+
+const ES_ARGS: ArgTexts = ArgTexts {
+    wrong: "`{}` esperaba {} como argumento {}, recibió {}",
+    missing: "`{}` esperaba {} como argumento {}",
+    exactly: "`{}` esperaba {}, recibió {}",
+    at_least: "`{}` esperaba al menos {}, recibió {}",
+    at_most: "`{}` esperaba como mucho {}, recibió {}",
+    one_argument: "1 argumento",
+    arguments: "{} argumentos",
+    kinds: &[
+        ("a number", "un número"),
+        ("an int", "un entero"),
+        ("a float", "un decimal"),
+        ("an index", "un índice"),
+        ("a string", "una cadena"),
+        ("a character", "un carácter"),
+        ("a bool", "un booleano"),
+        ("a symbol", "un símbolo"),
+        ("a keyword", "una palabra clave"),
+        ("a list", "una lista"),
+        ("a vector", "un vector"),
+        ("a map", "un mapa"),
+        ("a set", "un conjunto"),
+        ("a sequence", "una secuencia"),
+        ("a collection", "una colección"),
+        ("a key", "una clave"),
+        ("a value", "un valor"),
+        ("a fn", "una función"),
+    ],
+};
+
+const FR: &[(&str, &str)] = &[
+    ("unexpected symbol k='{}', it needs the `{}` feature", "symbole inconnu '{}', il faut la fonctionnalité `{}`"),
+    ("unexpected symbol k='{}'", "symbole inconnu '{}'"),
+    ("first form must be a function", "la première forme doit être une fonction"),
+    ("expected {} arguments, got {}", "{} arguments attendus, {} reçus"),
+    ("expected a number", "un nombre était attendu"),
+    ("expected at least one number", "au moins un nombre était attendu"),
+    ("expected two numbers", "deux nombres étaient attendus"),
+    ("expected a string", "une chaîne était attendue"),
+    ("division by zero", "division par zéro"),
+    ("could not get token", "impossible de lire un jeton"),
+    ("could not find closing `)`", "`)` fermante introuvable"),
+    ("unexpected `)`", "`)` inattendue"),
+    ("could not find closing `\"`", "`\"` fermant introuvable"),
+    ("unexpected form", "forme inattendue"),
+    ("unexpected test form='{}'", "condition inattendue '{}'"),
+    ("expected first form to be a symbol", "la première forme doit être un symbole"),
+    ("unknown keyword argument ':{}'", "argument nommé inconnu ':{}'"),
+    ("yield outside of a generator", "yield en dehors d'un générateur"),
+    ("no restart named '{}' is active", "aucun redémarrage nommé '{}' n'est actif"),
+    ("unhandled condition '{}'", "condition non gérée '{}'"),
+    ("could not read '{}': {}", "impossible de lire '{}' : {}"),
+];
+
+const FR_ARGS: ArgTexts = ArgTexts {
+    wrong: "`{}` attendait {} comme argument {}, a reçu {}",
+    missing: "`{}` attendait {} comme argument {}",
+    exactly: "`{}` attendait {}, a reçu {}",
+    at_least: "`{}` attendait au moins {}, a reçu {}",
+    at_most: "`{}` attendait au plus {}, a reçu {}",
+    one_argument: "1 argument",
+    arguments: "{} arguments",
+    kinds: &[
+        ("a number", "un nombre"),
+        ("an int", "un entier"),
+        ("a float", "un flottant"),
+        ("an index", "un indice"),
+        ("a string", "une chaîne"),
+        ("a character", "un caractère"),
+        ("a bool", "un booléen"),
+        ("a symbol", "un symbole"),
+        ("a keyword", "un mot-clé"),
+        ("a list", "une liste"),
+        ("a vector", "un vecteur"),
+        ("a map", "une table"),
+        ("a set", "un ensemble"),
+        ("a sequence", "une séquence"),
+        ("a collection", "une collection"),
+        ("a key", "une clé"),
+        ("a value", "une valeur"),
+        ("a fn", "une fonction"),
+    ],
+};
+
+const LANG_ES: Lang = Lang { catalog: ES, args: ES_ARGS };
+const LANG_FR: Lang = Lang { catalog: FR, args: FR_ARGS };
+
+// `lang` can be a bare code like `es` or a locale like `es_ES.UTF-8`
+pub fn set_lang(lang: Option<&str>) {
+    let code = lang.map(|lang| lang.split(['_', '-', '.']).next().unwrap_or("").to_lowercase());
+    let lang = match code.as_deref() {
+        Some("es") => Some(("es", &LANG_ES)),
+        Some("fr") => Some(("fr", &LANG_FR)),
+        _ => None,
+    };
+
+    LANG.with(|current| *current.borrow_mut() = lang);
+}
+
+// the language messages are translated to, `None` for English
+pub fn lang() -> Option<&'static str> {
+    LANG.with(|current| current.borrow().map(|(code, _)| code))
+}
+
+// the parts of `msg` standing in for each `{}` of `template`, if it matches
+fn matches<'a>(template: &str, msg: &'a str) -> Option<Vec<&'a str>> {
+    let mut pieces = template.split("{}");
+    let mut rest = msg.strip_prefix(pieces.next()?)?;
+    let mut args = vec![];
+
+    for piece in pieces {
+        let end = match piece {
+            "" => rest.len(),
+            _ => rest.find(piece)?,
+        };
+        args.push(&rest[..end]);
+        rest = &rest[end + piece.len()..];
+    }
+
+    match rest.is_empty() {
+        true => Some(args),
+        false => None,
+    }
+}
+
+fn fill(template: &str, args: &[&str]) -> String {
+    let mut pieces = template.split("{}");
+    let mut res = pieces.next().unwrap_or("").to_string();
+    for (piece, arg) in pieces.zip(args) {
+        res.push_str(arg);
+        res.push_str(piece);
+    }
+
+    res
+}
+
+pub fn translate(msg: String) -> String {
+    let catalog = LANG.with(|current| current.borrow().map(|(_, lang)| lang.catalog)).unwrap_or(&[]);
+    catalog
+        .iter()
+        .find_map(|(english, translated)| matches(english, &msg).map(|args| fill(translated, &args)))
+        .unwrap_or(msg)
+}
+
+// a kind of value like `a string`, in English when it has no translation
+fn kind(texts: &ArgTexts, kind: &str) -> String {
+    texts.kinds.iter().find(|(english, _)| *english == kind).map_or(kind, |(_, translated)| translated).to_string()
+}
+
+pub fn translate_args(e: &ArgErr) -> String {
+    let texts = match LANG.with(|current| *current.borrow()) {
+        Some((_, lang)) => &lang.args,
+        None => return e.to_string(),
+    };
+
+    match e {
+        ArgErr::Wrong { op, idx, expected, got } => {
+            let (expected, position) = (kind(texts, expected), (idx + 1).to_string());
+            match got {
+                Some(RispExp::List(list)) if list.is_empty() => fill(texts.wrong, &[op, &expected, &position, "nil"]),
+                Some(got) => {
+                    let got = format!("{} {}", kind(texts, args::type_name(got)), got);
+                    fill(texts.wrong, &[op, &expected, &position, &got])
+                },
+                None => fill(texts.missing, &[op, &expected, &position]),
+            }
+        },
+        ArgErr::Count { op, bound, expected, got } => {
+            let template = match bound {
+                Bound::Exactly => texts.exactly,
+                Bound::AtLeast => texts.at_least,
+                Bound::AtMost => texts.at_most,
+            };
+            let count = match expected {
+                1 => texts.one_argument.to_string(),
+                n => fill(texts.arguments, &[&n.to_string()]),
+            };
+            fill(template, &[op, &count, &got.to_string()])
+        },
+    }
+}
@@ -0,0 +1,58 @@
+// `(write-ppm path width height pixel-fn)` renders an image by calling
+// `(pixel-fn x y)` for every pixel, from the top left, and writes it as a
+// binary PPM, which most image viewers and converters open. A pixel is a list
+// of red, green and blue from 0 to 255, or one number for a shade of grey.
+
+use std::fs;
+
+use super::{as_float, as_index, call_exp, sandbox, RispEnv, RispErr, RispExp};
+
+fn channel(exp: &RispExp) -> Result<u8, RispErr> {
+    match as_float(exp) {
+        Some(n) if !n.is_nan() => Ok(n.round().clamp(0.0, 255.0) as u8),
+        _ => Err(RispErr::Reason(format!("expected a color channel from 0 to 255, got '{}'", exp))),
+    }
+}
+
+fn pixel(exp: &RispExp) -> Result<[u8; 3], RispErr> {
+    let rgb: &[RispExp] = match exp {
+        RispExp::List(rgb) => rgb,
+        RispExp::Tuple(rgb) => rgb,
+        _ => {
+            let grey = channel(exp)?;
+            return Ok([grey, grey, grey])
+        },
+    };
+
+    match rgb {
+        [r, g, b] => Ok([channel(r)?, channel(g)?, channel(b)?]),
+        _ => Err(RispErr::Reason(format!("expected a pixel as (r g b), got '{}'", exp))),
+    }
+}
+
+fn dimension(exp: Option<&RispExp>) -> Result<usize, RispErr> {
+    match exp.and_then(as_index) {
+        Some(n) if n >= 1 => Ok(n),
+        _ => Err(RispErr::Reason("expected the width and height as positive whole numbers".to_string())),
+    }
+}
+
+pub fn write_ppm(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let (path, f) = match args {
+        [RispExp::Str(path), _, _, f] => (path, f),
+        _ => return Err(RispErr::Reason("expected a path, width, height and pixel function".to_string())),
+    };
+    let (width, height) = (dimension(args.get(1))?, dimension(args.get(2))?);
+
+    let mut out = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    out.reserve(width * height * 3);
+    for y in 0..height {
+        for x in 0..width {
+            let coords = vec![RispExp::Int(x as i64), RispExp::Int(y as i64)];
+            out.extend_from_slice(&pixel(&call_exp(f, coords, env)?)?);
+        }
+    }
+    fs::write(sandbox::resolve(path)?, out).map_err(|e| RispErr::Reason(format!("could not write '{}': {}", path, e)))?;
+
+    Ok(RispExp::Str(path.clone()))
+}
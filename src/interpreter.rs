@@ -0,0 +1,276 @@
+// The entry point for programs embedding risp. An `Interpreter` owns a global
+// environment with the builtins loaded and keeps definitions between calls.
+//
+// A `RispPool` keeps interpreters warmed up with a prelude for servers that
+// evaluate each request in a fresh one. Interpreters belong to the thread that
+// made them, so a server keeps a pool per worker thread and moves data between
+// them as `Portable` values.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use super::audit::{self, AuditEntry, AuditHook};
+use super::objects::Class;
+use super::stats::{self, Metrics, Stats};
+use super::{macros, namespace, objects, tagged, watch};
+use super::{default_env, enabled_features, env_with, eval, output, parse_forms, sandbox, RispEnv, RispErr, RispExp};
+
+pub struct EvalOutcome {
+    pub value: Result<RispExp, RispErr>,
+    // what `print` and the terminal builtins wrote
+    pub stdout: String,
+    pub warnings: Vec<String>,
+    pub duration: Duration,
+}
+
+pub struct Interpreter {
+    env: RispEnv<'static>,
+    fs_root: Option<PathBuf>,
+    audit_hook: Option<AuditHook>,
+    memory_limit: Option<u64>,
+    stats: Stats,
+}
+
+impl Interpreter {
+    pub fn new() -> Interpreter {
+        Interpreter { env: default_env(), fs_root: None, audit_hook: None, memory_limit: None, stats: Stats::new() }
+    }
+
+    // only the builtin groups of `features`, like `&["math", "list"]` for
+    // evaluating formulas, each of them enabled in this build
+    pub fn with_features(features: &[&str]) -> Result<Interpreter, RispErr> {
+        let enabled = enabled_features();
+        if let Some(feature) = features.iter().find(|feature| !enabled.contains(feature)) {
+            return Err(RispErr::Reason(format!("the `{}` feature isn't enabled in this build", feature)))
+        }
+
+        let env = env_with(features);
+        Ok(Interpreter { env, fs_root: None, audit_hook: None, memory_limit: None, stats: Stats::new() })
+    }
+
+    // skips building the default environment, for interpreters on a thread
+    // that already has one
+    fn with_globals(globals: HashMap<String, RispExp>) -> Interpreter {
+        let env = RispEnv::new(globals, None);
+        Interpreter { env, fs_root: None, audit_hook: None, memory_limit: None, stats: Stats::new() }
+    }
+
+    // confines the file builtins to `root` and the directories below it
+    pub fn set_fs_root(&mut self, root: impl AsRef<Path>) -> io::Result<()> {
+        self.fs_root = Some(root.as_ref().canonicalize()?);
+        Ok(())
+    }
+
+    // calls `hook` with every call to a builtin with side effects, like `spit`
+    pub fn set_audit_hook(&mut self, hook: impl FnMut(&AuditEntry) + 'static) {
+        self.audit_hook = Some(Box::new(hook));
+    }
+
+    // fails an `eval` once it holds on to more than `bytes` of memory, which
+    // is only known with `CountingAllocator` as the global allocator. Without
+    // it, only a builtin about to make something bigger than that fails
+    pub fn set_memory_limit(&mut self, bytes: Option<u64>) {
+        self.memory_limit = bytes;
+    }
+
+    // the counts of everything this interpreter has evaluated
+    pub fn metrics(&self) -> Metrics {
+        self.stats.metrics()
+    }
+
+    // binds `name` in the global environment, like a value attached from
+    // another interpreter's `Portable`
+    pub fn define(&mut self, name: &str, value: RispExp) {
+        self.env.insert(name.to_string(), value);
+    }
+
+    // runs `f` on the environment with this interpreter's settings in place
+    fn evaluating<T>(&mut self, f: impl FnOnce(&mut RispEnv<'static>) -> T) -> T {
+        let env = &mut self.env;
+        let outer_hook = audit::set_hook(self.audit_hook.take());
+        let outer_limit = stats::set_memory_limit(self.memory_limit);
+        let outer_stats = stats::replace(std::mem::replace(&mut self.stats, Stats::new()));
+        let res = sandbox::with_root(self.fs_root.as_deref(), || f(env));
+        self.stats = stats::replace(outer_stats);
+        stats::set_memory_limit(outer_limit);
+        self.audit_hook = audit::set_hook(outer_hook);
+
+        res
+    }
+
+    // evaluates every form of `src` in order, with the value of the last and
+    // what was printed along the way
+    pub fn eval(&mut self, src: &str) -> EvalOutcome {
+        let start = Instant::now();
+        let (value, captured) = self.evaluating(|env| output::capture(|| stats::timed(|| {
+            let mut value = RispExp::Bool(false);
+            for form in parse_forms(src.to_string())? {
+                value = eval(&form, env)?;
+            }
+            Ok(value)
+        })));
+
+        EvalOutcome { value, stdout: captured.stdout, warnings: captured.warnings, duration: start.elapsed() }
+    }
+
+    // evaluates each top-level form of `src` on its own, with a result for
+    // every one; text that doesn't parse is a single error
+    pub fn eval_forms(&mut self, src: &str) -> Vec<Result<RispExp, RispErr>> {
+        self.eval_each(src, false)
+    }
+
+    // like `eval_forms`, but stops after the first form that fails
+    pub fn eval_forms_until_error(&mut self, src: &str) -> Vec<Result<RispExp, RispErr>> {
+        self.eval_each(src, true)
+    }
+
+    fn eval_each(&mut self, src: &str, stop_on_error: bool) -> Vec<Result<RispExp, RispErr>> {
+        let forms = match parse_forms(src.to_string()) {
+            Ok(forms) => forms,
+            Err(e) => return vec![Err(e)],
+        };
+
+        self.evaluating(|env| {
+            let mut results = vec![];
+            for form in &forms {
+                let res = stats::timed(|| eval(form, env));
+                let failed = res.is_err();
+                results.push(res);
+                if failed && stop_on_error {
+                    break
+                }
+            }
+            results
+        })
+    }
+}
+
+// what scripts define for their whole thread rather than in an environment,
+// as it was when a pool was made
+struct Registered {
+    tag_readers: HashMap<String, RispExp>,
+    macros: HashMap<String, RispExp>,
+    classes: HashMap<String, Rc<Class>>,
+    watches: HashMap<String, Option<RispExp>>,
+    namespace: String,
+}
+
+impl Registered {
+    fn saved() -> Registered {
+        Registered {
+            tag_readers: tagged::saved(),
+            macros: macros::saved(),
+            classes: objects::saved(),
+            watches: watch::saved(),
+            namespace: namespace::current(),
+        }
+    }
+
+    fn restore(&self) {
+        tagged::restore(&self.tag_readers);
+        macros::restore(&self.macros);
+        objects::restore(&self.classes);
+        watch::restore(&self.watches);
+        namespace::restore(&self.namespace);
+    }
+}
+
+pub struct RispPool {
+    // the global environment every interpreter starts each request with
+    globals: HashMap<String, RispExp>,
+    registered: Registered,
+    idle: RefCell<Vec<Interpreter>>,
+    size: usize,
+}
+
+impl RispPool {
+    pub fn new(size: usize) -> RispPool {
+        RispPool::warmed(Interpreter::new(), size)
+    }
+
+    // evaluates `prelude` once, the definitions it makes are there for every
+    // request
+    pub fn with_prelude(size: usize, prelude: &str) -> Result<RispPool, RispErr> {
+        let mut interpreter = Interpreter::new();
+        interpreter.eval(prelude).value?;
+        Ok(RispPool::warmed(interpreter, size))
+    }
+
+    fn warmed(interpreter: Interpreter, size: usize) -> RispPool {
+        let globals = interpreter.env.data.borrow().clone();
+        let idle = (1..size).map(|_| Interpreter::with_globals(globals.clone())).collect::<Vec<_>>();
+        let pool = RispPool { globals, registered: Registered::saved(), idle: RefCell::new(idle), size };
+        pool.idle.borrow_mut().push(interpreter);
+        pool
+    }
+
+    // an interpreter to use for one request, a new one when they're all
+    // checked out
+    pub fn checkout(&self) -> Pooled<'_> {
+        let interpreter = self
+            .idle
+            .borrow_mut()
+            .pop()
+            .unwrap_or_else(|| Interpreter::with_globals(self.globals.clone()));
+        Pooled { pool: self, interpreter: Some(interpreter) }
+    }
+
+    pub fn idle(&self) -> usize {
+        self.idle.borrow().len()
+    }
+}
+
+// an interpreter checked out of a pool, reset and handed back when dropped
+pub struct Pooled<'p> {
+    pool: &'p RispPool,
+    interpreter: Option<Interpreter>,
+}
+
+impl Deref for Pooled<'_> {
+    type Target = Interpreter;
+
+    fn deref(&self) -> &Interpreter {
+        self.interpreter.as_ref().expect("interpreter handed back")
+    }
+}
+
+impl DerefMut for Pooled<'_> {
+    fn deref_mut(&mut self) -> &mut Interpreter {
+        self.interpreter.as_mut().expect("interpreter handed back")
+    }
+}
+
+impl Drop for Pooled<'_> {
+    fn drop(&mut self) {
+        // tag readers, macros and the like are the thread's, so they're put
+        // back even when the interpreter itself isn't kept
+        self.pool.registered.restore();
+
+        let mut idle = self.pool.idle.borrow_mut();
+        if idle.len() >= self.pool.size {
+            return
+        }
+
+        // the settings go along with the definitions, the next request gets
+        // an interpreter as it was when the pool was made
+        if let Some(mut interpreter) = self.interpreter.take() {
+            interpreter.env.data.borrow_mut().clone_from(&self.pool.globals);
+            interpreter.fs_root = None;
+            interpreter.audit_hook = None;
+            interpreter.memory_limit = None;
+            interpreter.stats = Stats::new();
+            idle.push(interpreter);
+        }
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Interpreter {
+        Interpreter::new()
+    }
+}
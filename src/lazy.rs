@@ -0,0 +1,69 @@
+// Builtins most programs never call are installed the first time a name
+// isn't found anywhere else, only the module that defines it, so starting an
+// interpreter only pays for the core ones and a misspelled name for none. The
+// installed builtins are shared by every environment on the thread and
+// anything defined under the same name shadows them. `(session-stats)` counts
+// the modules installed as `:lazy-modules`.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use super::{add_feature_builtins, audit, output, replay, svg, tui, turtle, RispExp};
+
+type Install = fn(&mut HashMap<String, RispExp>);
+
+const MODULES: [Install; 6] = [
+    output::add_builtins,
+    replay::add_builtins,
+    add_feature_builtins,
+    tui::add_builtins,
+    turtle::add_builtins,
+    svg::add_builtins,
+];
+
+thread_local! {
+    static INSTALLED: RefCell<HashMap<String, RispExp>> = RefCell::new(HashMap::new());
+    // which of `MODULES` are installed
+    static MODULES_INSTALLED: Cell<[bool; MODULES.len()]> = const { Cell::new([false; MODULES.len()]) };
+    // the position in `MODULES` of the one defining each name
+    static INDEX: HashMap<String, usize> = index();
+}
+
+fn index() -> HashMap<String, usize> {
+    let mut index = HashMap::new();
+    for (idx, install) in MODULES.iter().enumerate() {
+        let mut data = HashMap::new();
+        install(&mut data);
+        index.extend(data.into_keys().map(|name| (name, idx)));
+    }
+    index
+}
+
+// how many of the modules are installed on this thread
+pub fn installed_modules() -> usize {
+    MODULES_INSTALLED.with(Cell::get).iter().filter(|installed| **installed).count()
+}
+
+pub fn lookup(name: &str) -> Option<RispExp> {
+    INSTALLED.with(|installed| {
+        if let Some(exp) = installed.borrow().get(name) {
+            return Some(exp.clone())
+        }
+
+        // a name no module defines installs nothing
+        let idx = INDEX.with(|index| index.get(name).copied())?;
+        let mut modules = MODULES_INSTALLED.with(Cell::get);
+        if modules[idx] {
+            return None
+        }
+        modules[idx] = true;
+        MODULES_INSTALLED.with(|installed| installed.set(modules));
+
+        let mut data = HashMap::new();
+        MODULES[idx](&mut data);
+        audit::register(&data);
+        let exp = data.get(name).cloned();
+        installed.borrow_mut().extend(data);
+        exp
+    })
+}
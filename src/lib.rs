@@ -0,0 +1,2291 @@
+
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::convert::TryFrom;
+use std::env;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::num::ParseFloatError;
+use std::rc::{Rc, Weak};
+use std::process;
+use std::fmt;
+
+mod access;
+pub mod args;
+mod audit;
+mod builtins;
+pub mod cli;
+#[cfg(feature = "audio")]
+mod audio;
+#[cfg(feature = "bignum")]
+mod bignum;
+mod console;
+mod diff;
+mod doctor;
+mod dot;
+mod edn;
+mod envs;
+mod html;
+mod explain;
+mod glob;
+mod graph;
+mod hashmap;
+mod i18n;
+mod interpreter;
+mod image;
+mod lazy;
+mod lists;
+mod macros;
+#[cfg(feature = "markdown")]
+mod markdown;
+mod meta;
+mod minify;
+mod namespace;
+mod numeric;
+mod objects;
+mod output;
+mod parse_cache;
+mod pkg;
+mod portable;
+mod project;
+mod queue;
+mod reader;
+mod remote;
+mod rename;
+mod replay;
+mod resource;
+mod sandbox;
+mod sorted;
+mod spec;
+mod stats;
+mod strings;
+mod svg;
+mod tagged;
+mod template;
+mod tui;
+mod turtle;
+mod types;
+mod vector;
+mod walk;
+mod watch;
+mod zipper;
+
+use queue::{Cons, RispQueue};
+use resource::Resource;
+use sorted::SortKey;
+use spec::Instrumented;
+use tagged::Tagged;
+use zipper::Zipper;
+
+pub use audit::AuditEntry;
+pub use interpreter::{EvalOutcome, Interpreter, Pooled, RispPool};
+pub use portable::Portable;
+pub use reader::{register as register_reader_macro, ReaderMacro};
+pub use stats::{set_stack_limit, CountingAllocator, Metrics};
+pub use tagged::register as register_tag_reader;
+
+
+#[derive(Clone, Debug)]
+pub enum RispExp {
+    Bool(bool),
+    Symbol(String),
+    // with its colon, like `:foo`
+    Keyword(String),
+    Number(f64),
+    Int(i64),
+    // big integers and rationals, never one that fits in an int
+    #[cfg(feature = "bignum")]
+    Exact(Rc<bignum::Rational>),
+    Str(String),
+    Char(char),
+    List(RispList),
+    Vector(Rc<RefCell<Vec<RispExp>>>),
+    Func(fn(&[RispExp]) -> Result<RispExp, RispErr>),
+    // a builtin that calls back into functions, like `map`, called with the
+    // environment it's called from
+    EnvFunc(fn(&[RispExp], &mut RispEnv) -> Result<RispExp, RispErr>),
+    Lambda(RispLambda),
+    Generator(Rc<Generator>),
+    Resource(Rc<RefCell<Resource>>),
+    Weak(Weak<RefCell<Resource>>),
+    Tuple(Rc<Vec<RispExp>>),
+    SortedMap(Rc<BTreeMap<SortKey, RispExp>>),
+    Map(Rc<hashmap::Entries>),
+    SortedSet(Rc<BTreeSet<SortKey>>),
+    Queue(Rc<RispQueue>),
+    Stack(Rc<Cons>),
+    Zipper(Rc<Zipper>),
+    Instrumented(Rc<Instrumented>),
+    Object(Rc<objects::Object>),
+    Generic(Rc<objects::Generic>),
+    Tagged(Rc<Tagged>),
+    // the bindings of each scope, the outermost first
+    Env(Rc<Vec<Frame>>),
+}
+
+// a list's items, used like the `Vec` of them, and the metadata it was given
+// with `with-meta`, which it keeps however it's copied
+#[derive(Clone, Debug, Default)]
+pub struct RispList {
+    items: Vec<RispExp>,
+    meta: Option<Rc<RispExp>>,
+}
+
+impl RispList {
+    pub fn into_vec(self) -> Vec<RispExp> {
+        self.items
+    }
+}
+
+impl From<Vec<RispExp>> for RispList {
+    fn from(items: Vec<RispExp>) -> RispList {
+        RispList { items, meta: None }
+    }
+}
+
+impl std::iter::FromIterator<RispExp> for RispList {
+    fn from_iter<I: IntoIterator<Item = RispExp>>(iter: I) -> RispList {
+        RispList::from(iter.into_iter().collect::<Vec<RispExp>>())
+    }
+}
+
+impl IntoIterator for RispList {
+    type Item = RispExp;
+    type IntoIter = std::vec::IntoIter<RispExp>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a RispList {
+    type Item = &'a RispExp;
+    type IntoIter = std::slice::Iter<'a, RispExp>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+impl std::ops::Deref for RispList {
+    type Target = Vec<RispExp>;
+
+    fn deref(&self) -> &Vec<RispExp> {
+        &self.items
+    }
+}
+
+impl std::ops::DerefMut for RispList {
+    fn deref_mut(&mut self) -> &mut Vec<RispExp> {
+        &mut self.items
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RispLambda {
+    params_exp:  Rc<RispExp>,
+    body_exps: Rc<Vec<RispExp>>,
+    // type annotations, only read by `risp check --types`
+    signature: Option<Rc<types::Signature>>,
+    // the scopes the fn was made in, the outermost first and without the
+    // global one, whose bindings are looked up when it's called. They're
+    // shared with the scopes themselves, so a `set!` on one of their bindings,
+    // by the fn or by anything else in scope, is seen by later calls
+    closure: Rc<Vec<Frame>>,
+}
+
+impl RispLambda {
+    // the bindings it closed over in one scope, the innermost of each name
+    fn closed_over(&self) -> HashMap<String, RispExp> {
+        let mut bindings = HashMap::new();
+        for frame in self.closure.iter() {
+            bindings.extend(frame.borrow().iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        bindings
+    }
+}
+
+#[derive(Debug)]
+pub struct Generator {
+    producer: RispLambda,
+    // what it's yielded so far, handed out again instead of rerunning it
+    yielded: RefCell<Vec<RispExp>>,
+    // whether the producer has returned, so `yielded` is all there is
+    finished: Cell<bool>,
+}
+
+#[derive(Debug)]
+pub enum RispErr {
+    Reason(String),
+    // a builtin called with arguments it doesn't take, from the `args` checks
+    Args(args::ArgErr),
+    // raised by `yield` once the consuming `take` has all the values it asked
+    // for. Only the generator's own run stops it, handlers never see it
+    GeneratorFull,
+    // raised by `invoke-restart`, unwinds to the `restart-case` frame with this id
+    Restart {
+        frame: usize,
+        name: String,
+        args: Vec<RispExp>,
+    },
+}
+
+// values collected from a running generator, innermost generator last
+struct YieldSink {
+    // how many yields are already in the generator's `yielded`
+    skip: usize,
+    // the scopes the producer closed over, which it mustn't `set!` in
+    closure: Rc<Vec<Frame>>,
+    values: Vec<RispExp>,
+    limit: Option<usize>,
+}
+
+// restarts established by a `restart-case`, innermost frame last
+struct RestartFrame {
+    id: usize,
+    names: Vec<String>,
+}
+
+thread_local! {
+    // risp finalizers of collected handles, run by the REPL between evaluations
+    static PENDING_FINALIZERS: RefCell<Vec<RispExp>> = const { RefCell::new(vec![]) };
+    static DEFERRED: RefCell<Vec<Vec<RispExp>>> = const { RefCell::new(vec![]) };
+    static YIELD_SINKS: RefCell<Vec<YieldSink>> = const { RefCell::new(vec![]) };
+    static HANDLERS: RefCell<Vec<RispExp>> = const { RefCell::new(vec![]) };
+    static RESTARTS: RefCell<Vec<RestartFrame>> = const { RefCell::new(vec![]) };
+    static NEXT_RESTART_FRAME: RefCell<usize> = const { RefCell::new(0) };
+}
+
+// optional builtin groups compiled into this build
+const COMPILED_FEATURES: &[&str] = &[
+    #[cfg(feature = "math")]
+    "math",
+    #[cfg(feature = "list")]
+    "list",
+    #[cfg(feature = "string")]
+    "string",
+    #[cfg(feature = "net")]
+    "net",
+    #[cfg(feature = "sys")]
+    "sys",
+    #[cfg(feature = "files")]
+    "files",
+    #[cfg(feature = "markdown")]
+    "markdown",
+    #[cfg(feature = "audio")]
+    "audio",
+    #[cfg(feature = "bignum")]
+    "bignum",
+];
+
+const STANDARD_RESTARTS: [&str; 2] = ["retry", "use-value"];
+
+// the bindings of one scope. Scopes nested in it only get a shared reference
+// to it, so it's behind a `RefCell` for `set!`, and a fn's closure is shared
+// by every call to it
+type Frame = Rc<RefCell<HashMap<String, RispExp>>>;
+
+pub struct RispEnv<'a> {
+    data: Frame,
+    outer: Option<&'a RispEnv<'a>>,
+}
+
+impl<'a> RispEnv<'a> {
+    fn new(data: HashMap<String, RispExp>, outer: Option<&'a RispEnv<'a>>) -> RispEnv<'a> {
+        RispEnv { data: Rc::new(RefCell::new(data)), outer }
+    }
+
+    fn insert(&self, k: String, v: RispExp) {
+        if watch::is_watched(&k) {
+            let old = self.data.borrow().get(&k).cloned();
+            watch::changed(&k, old, &v);
+        }
+        self.data.borrow_mut().insert(k, v);
+    }
+
+    fn contains(&self, k: &str) -> bool {
+        self.data.borrow().contains_key(k)
+    }
+}
+
+impl fmt::Display for RispExp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(literal) = tagged::literal(self) {
+            return write!(f, "{}", literal.display())
+        }
+
+        let str = match self {
+            RispExp::Bool(a) => a.to_string(),
+            RispExp::Symbol(s) => s.clone(),
+            RispExp::Keyword(k) => k.clone(),
+            // floats always have a `.` or an exponent, so they don't read back as ints
+            RispExp::Number(n) if n.is_finite() => format!("{:?}", n),
+            RispExp::Number(n) => n.to_string(),
+            RispExp::Int(n) => n.to_string(),
+            #[cfg(feature = "bignum")]
+            RispExp::Exact(r) => r.to_string(),
+            RispExp::Str(s) => edn::write_string(s),
+            RispExp::Char(c) => char_literal(*c),
+            RispExp::List(list) if list.is_empty() => "nil".to_string(),
+            RispExp::List(list) => {
+                let xs: Vec<String> = list
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect();
+                format!("({})", xs.join(","))
+            },
+            RispExp::Vector(xs) => {
+                let xs: Vec<String> = xs.borrow().iter().map(|x| x.to_string()).collect();
+                format!("[{}]", xs.join(","))
+            },
+            RispExp::Map(map) => hashmap::display(map),
+            RispExp::Func(_) | RispExp::EnvFunc(_) => "Function {}".to_string(),
+            RispExp::Lambda(_) => "Lambda {}".to_string(),
+            RispExp::Generator(_) => "Generator {}".to_string(),
+            RispExp::Resource(resource) => resource.borrow().to_string(),
+            RispExp::Weak(_) => "Weak {}".to_string(),
+            RispExp::Env(_) => "Env {}".to_string(),
+            RispExp::Tuple(values) => {
+                let xs: Vec<String> = values
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect();
+                format!("#({})", xs.join(","))
+            },
+            RispExp::SortedMap(map) => sorted::display_map(map),
+            RispExp::SortedSet(set) => sorted::display_set(set),
+            RispExp::Queue(queue) => queue::display("queue", &queue.values()),
+            RispExp::Stack(stack) => queue::display("stack", &queue::stack_values(stack)),
+            RispExp::Zipper(zipper) => format!("Zipper {{{}}}", zipper.focus()),
+            RispExp::Instrumented(_) => "Instrumented {}".to_string(),
+            RispExp::Object(obj) => obj.display(),
+            RispExp::Generic(_) => "Generic {}".to_string(),
+            RispExp::Tagged(tagged) => tagged.display(),
+        } ;
+
+        write!(f, "{}", str)
+    }
+}
+
+fn parse_single_float(exp: &RispExp) -> Result<f64, RispErr> {
+    as_float(exp).ok_or(RispErr::Reason("expected a number".to_string()))
+}
+
+// a number of either kind, ints as the nearest float
+fn as_float(exp: &RispExp) -> Option<f64> {
+    match exp {
+        RispExp::Number(num) => Some(*num),
+        RispExp::Int(num) => Some(*num as f64),
+        #[cfg(feature = "bignum")]
+        RispExp::Exact(r) => Some(r.to_f64()),
+        _ => None,
+    }
+}
+
+// a whole number of either kind that isn't negative, like a count or an index
+fn as_index(exp: &RispExp) -> Option<usize> {
+    match exp {
+        RispExp::Int(n) => usize::try_from(*n).ok(),
+        RispExp::Number(n) if n.fract() == 0.0 && *n >= 0.0 => Some(*n as usize),
+        _ => None,
+    }
+}
+
+// compiled features, minus any switched off through `RISP_DISABLED_FEATURES=a,b`
+fn enabled_features() -> Vec<&'static str> {
+    let disabled = env::var("RISP_DISABLED_FEATURES").unwrap_or_default();
+    let disabled: Vec<&str> = disabled.split(',').map(|x| x.trim()).collect();
+
+    COMPILED_FEATURES
+        .iter()
+        .filter(|feature| !disabled.contains(feature))
+        .copied()
+        .collect()
+}
+
+// the names each optional builtin group defines, whether or not it's compiled in
+fn feature_index() -> HashMap<String, &'static str> {
+    let mut index: HashMap<String, &'static str> = builtins::index().into_iter().collect();
+    for (name, feature) in [
+        ("with-lines", "files"),
+        ("process-files", "files"),
+        ("require", "files"),
+        ("md->html", "markdown"),
+        ("beep", "audio"),
+        ("play-tone", "audio"),
+    ].iter() {
+        index.insert(name.to_string(), feature);
+    }
+
+    index
+}
+
+thread_local! {
+    static FEATURE_INDEX: HashMap<String, &'static str> = feature_index();
+}
+
+// the error for a symbol that isn't bound, pointing at the feature that
+// would define it
+fn unbound_symbol(k: &str) -> RispErr {
+    let feature = FEATURE_INDEX.with(|index| index.get(k).copied());
+    match feature {
+        Some(feature) if !enabled_features().contains(&feature) =>
+            RispErr::Reason(format!("unexpected symbol k='{}', it needs the `{}` feature", k, feature)),
+        _ => RispErr::Reason(format!("unexpected symbol k='{}'", k)),
+    }
+}
+
+fn default_env<'a>() -> RispEnv<'a> {
+    env_with(&enabled_features())
+}
+
+// a global environment with the core builtins and the groups of `features`
+fn env_with<'a>(features: &[&str]) -> RispEnv<'a> {
+    reader::add_default_macros();
+
+    let mut data: HashMap<String, RispExp> = HashMap::new();
+    add_type_predicates(&mut data);
+    builtins::add_builtins(&mut data, features);
+    args::add_builtins(&mut data);
+    tagged::add_builtins(&mut data);
+    hashmap::add_builtins(&mut data);
+    namespace::add_builtins(&mut data);
+    objects::add_builtins(&mut data);
+    data.insert("read".to_string(), RispExp::Func(envs::read));
+    data.insert("invoke-restart".to_string(), RispExp::Func(invoke_restart));
+
+    data.insert(
+        "*features*".to_string(),
+        RispExp::List(
+            features
+                .iter()
+                .map(|feature| RispExp::Keyword(format!(":{}", feature)))
+                .collect()
+        )
+    );
+
+    audit::register(&data);
+    RispEnv::new(data, None)
+}
+
+// the builtins of the optional features that are enabled, installed lazily
+fn add_feature_builtins(data: &mut HashMap<String, RispExp>) {
+    let features = enabled_features();
+    if features.contains(&"files") {
+        builtins::add_file_builtins(data);
+    }
+
+    #[cfg(feature = "markdown")]
+    if features.contains(&"markdown") {
+        data.insert("md->html".to_string(), RispExp::Func(markdown::md_to_html));
+    }
+
+    #[cfg(feature = "audio")]
+    if features.contains(&"audio") {
+        data.insert("beep".to_string(), RispExp::Func(audio::beep));
+        data.insert("play-tone".to_string(), RispExp::Func(audio::play_tone));
+    }
+}
+
+// trailing `:name value` pairs of a builtin call
+fn parse_options(args: &[RispExp], allowed: &[&str]) -> Result<HashMap<String, RispExp>, RispErr> {
+    if !args.len().is_multiple_of(2) {
+        return Err(RispErr::Reason("expected options to come in `:name value` pairs".to_string()))
+    }
+
+    args
+        .chunks(2)
+        .map(|pair| match &pair[0] {
+            RispExp::Keyword(name) if allowed.contains(&name.as_ref()) => Ok((name.clone(), pair[1].clone())),
+            _ => Err(RispErr::Reason(format!("unknown option '{}', expected one of {}", pair[0], allowed.join(", ")))),
+        })
+        .collect()
+}
+
+fn option_symbol(options: &HashMap<String, RispExp>, name: &str, default: &str) -> Result<String, RispErr> {
+    match options.get(name) {
+        Some(RispExp::Keyword(k)) => Ok(k.clone()),
+        Some(_) => Err(RispErr::Reason(format!("expected {} to be a `:name`", name))),
+        None => Ok(default.to_string()),
+    }
+}
+
+fn add_type_predicates(data: &mut HashMap<String, RispExp>) {
+    data.insert(
+        "number?".to_string(),
+        RispExp::Func(|args: &[RispExp]| -> Result<RispExp, RispErr> {
+            Ok(RispExp::Bool(args.first().and_then(as_float).is_some()))
+        })
+    );
+
+    data.insert(
+        "int?".to_string(),
+        RispExp::Func(|args: &[RispExp]| -> Result<RispExp, RispErr> {
+            Ok(RispExp::Bool(matches!(args.first(), Some(RispExp::Int(_)))))
+        })
+    );
+
+    data.insert(
+        "string?".to_string(),
+        RispExp::Func(|args: &[RispExp]| -> Result<RispExp, RispErr> {
+            Ok(RispExp::Bool(matches!(args.first(), Some(RispExp::Str(_)))))
+        })
+    );
+
+    data.insert(
+        "char?".to_string(),
+        RispExp::Func(|args: &[RispExp]| -> Result<RispExp, RispErr> {
+            Ok(RispExp::Bool(matches!(args.first(), Some(RispExp::Char(_)))))
+        })
+    );
+
+    data.insert(
+        "symbol?".to_string(),
+        RispExp::Func(|args: &[RispExp]| -> Result<RispExp, RispErr> {
+            Ok(RispExp::Bool(matches!(args.first(), Some(RispExp::Symbol(_)))))
+        })
+    );
+
+    data.insert(
+        "keyword?".to_string(),
+        RispExp::Func(|args: &[RispExp]| -> Result<RispExp, RispErr> {
+            Ok(RispExp::Bool(matches!(args.first(), Some(RispExp::Keyword(_)))))
+        })
+    );
+
+    data.insert(
+        "bool?".to_string(),
+        RispExp::Func(|args: &[RispExp]| -> Result<RispExp, RispErr> {
+            Ok(RispExp::Bool(matches!(args.first(), Some(RispExp::Bool(_)))))
+        })
+    );
+
+    data.insert(
+        "list?".to_string(),
+        RispExp::Func(|args: &[RispExp]| -> Result<RispExp, RispErr> {
+            Ok(RispExp::Bool(matches!(args.first(), Some(RispExp::List(_)))))
+        })
+    );
+
+    data.insert(
+        "fn?".to_string(),
+        RispExp::Func(|args: &[RispExp]| -> Result<RispExp, RispErr> {
+            let f = matches!(args.first(), Some(RispExp::Func(_)) | Some(RispExp::EnvFunc(_)) | Some(RispExp::Lambda(_)) | Some(RispExp::Instrumented(_)) | Some(RispExp::Generic(_)));
+            Ok(RispExp::Bool(f))
+        })
+    );
+}
+
+fn parse_single_str(exp: &RispExp) -> Result<String, RispErr> {
+    match exp {
+        RispExp::Str(s) => Ok(s.clone()),
+        _ => Err(RispErr::Reason("expected a string".to_string()))
+    }
+}
+
+fn run_pending_finalizers(env: &mut RispEnv) -> Vec<RispErr> {
+    let pending = PENDING_FINALIZERS.with(|pending| std::mem::take(&mut *pending.borrow_mut()));
+    pending
+        .iter()
+        .filter_map(|finalizer| call_exp(finalizer, vec![], env).err())
+        .collect()
+}
+
+// where a token starts in the source text, both 1-based
+#[derive(Clone, Copy, Debug)]
+struct Pos {
+    line: usize,
+    col: usize,
+}
+
+impl fmt::Display for Pos {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.col)
+    }
+}
+
+fn tokenize(expr: String) -> Vec<String> {
+    tokenize_spanned(&expr).into_iter().map(|(token, _)| token).collect()
+}
+
+// string tokens keep their opening `"`, their closing one when terminated and
+// any escapes, as they're written
+fn tokenize_spanned(expr: &str) -> Vec<(String, Pos)> {
+    let mut tokens: Vec<(String, Pos)> = vec![];
+    let mut pos = Pos { line: 1, col: 1 };
+    let mut chars = expr
+        .chars()
+        .map(move |c| {
+            let at = pos;
+            if c == '\n' {
+                pos = Pos { line: pos.line + 1, col: 1 };
+            } else {
+                pos.col += 1;
+            }
+            (c, at)
+        })
+        .peekable();
+    // how deeply nested the current token is, and the depths of the lists
+    // quoted with a backquote, in which `,` unquotes
+    let mut depth = 0;
+    let mut quasiquoted: Vec<usize> = vec![];
+    let mut after_backquote = false;
+
+    while let Some((c, at)) = chars.next() {
+        match c {
+            '(' | '{' | '[' => {
+                if after_backquote {
+                    quasiquoted.push(depth);
+                }
+                depth += 1;
+                tokens.push((c.to_string(), at));
+            },
+            ')' | '}' | ']' => {
+                depth = depth.saturating_sub(1);
+                if quasiquoted.last() == Some(&depth) {
+                    quasiquoted.pop();
+                }
+                tokens.push((c.to_string(), at));
+            },
+            // `'x` reads as `(quote x)`, `` `x `` as `(quasiquote x)`
+            '\'' => {
+                tokens.push((c.to_string(), at));
+                continue
+            },
+            '`' => {
+                tokens.push((c.to_string(), at));
+                after_backquote = true;
+                continue
+            },
+            // `,x` is `(unquote x)` and `,@xs` is `(unquote-splicing xs)`
+            ',' if after_backquote || !quasiquoted.is_empty() => match chars.peek() {
+                Some(('@', _)) => {
+                    chars.next();
+                    tokens.push((",@".to_string(), at));
+                },
+                _ => tokens.push((",".to_string(), at)),
+            },
+            // `#\a` is a character, which can be a delimiter like `#\(`
+            '#' if matches!(chars.peek(), Some(('\\', _))) => {
+                let mut token = c.to_string();
+                token.extend(chars.next().map(|(c, _)| c));
+                token.extend(chars.next().map(|(c, _)| c));
+                while let Some(&(c, _)) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, ',' | '(' | ')' | '{' | '}' | '[' | ']' | '"' | ';') {
+                        break
+                    }
+                    token.push(c);
+                    chars.next();
+                }
+                tokens.push((token, at));
+            },
+            // `#{` opens a set, the `#` can't start a token of its own here
+            '#' if matches!(chars.peek(), Some(('{', _))) => {
+                chars.next();
+                if after_backquote {
+                    quasiquoted.push(depth);
+                }
+                depth += 1;
+                tokens.push(("#{".to_string(), at));
+            },
+            '"' => {
+                let mut token = c.to_string();
+                while let Some((c, _)) = chars.next() {
+                    token.push(c);
+                    match c {
+                        '"' => break,
+                        '\\' => token.extend(chars.next().map(|(c, _)| c)),
+                        _ => (),
+                    }
+                }
+                tokens.push((token, at));
+            },
+            // comments run to the end of the line
+            ';' => {
+                while !matches!(chars.next(), Some(('\n', _)) | None) {}
+                continue
+            },
+            // other commas are whitespace, so printed lists read back
+            _ if c.is_whitespace() || c == ',' => continue,
+            _ => {
+                let mut token = c.to_string();
+                while let Some(&(c, _)) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, ',' | '(' | ')' | '{' | '}' | '[' | ']' | '"' | ';') {
+                        break
+                    }
+                    token.push(c);
+                    chars.next();
+                }
+                tokens.push((token, at));
+            },
+        }
+        after_backquote = false;
+    }
+
+    tokens
+}
+
+fn parse(tokens: &[String]) -> Result<(RispExp, &[String]), RispErr> {
+    let (token, rest) = tokens.split_first()
+        .ok_or(
+            RispErr::Reason("could not get token".to_string())     
+        )?;
+
+    match &token[..] {
+        "(" => read_seq(rest),
+        ")" => Err(RispErr::Reason("unexpected `)`".to_string())),
+        // `{k v}` is a map, which evaluates to a new one of its keys' and values' values
+        "{" => {
+            let (xs, rest) = read_delimited(rest, "}")?;
+            match xs {
+                RispExp::List(xs) => Ok((hashmap::from_pairs(&xs)?, rest)),
+                _ => Err(RispErr::Reason("expected map entries".to_string())),
+            }
+        },
+        "}" => Err(RispErr::Reason("unexpected `}`".to_string())),
+        // `[a b]` is a vector, which evaluates to a new one of its elements' values
+        "[" => {
+            let (xs, rest) = read_delimited(rest, "]")?;
+            match xs {
+                RispExp::List(xs) => Ok((RispExp::Vector(Rc::new(RefCell::new(xs.into_vec()))), rest)),
+                _ => Err(RispErr::Reason("expected vector elements".to_string())),
+            }
+        },
+        "]" => Err(RispErr::Reason("unexpected `]`".to_string())),
+        _ if token.starts_with("#\\") => Ok((parse_char(token)?, rest)),
+        _ if token.starts_with('#') => reader::read_dispatch(&token[1..], rest),
+        _ if token.starts_with('"') => Ok((parse_string(token)?, rest)),
+        "'" | "`" | "," | ",@" => {
+            let (exp, rest) = parse(rest)?;
+            Ok((RispExp::List(vec![RispExp::Symbol(quote_name(token).to_string()), exp].into()), rest))
+        },
+        // `@xs` reads as `(spread xs)`
+        "@" => {
+            let (exp, rest) = parse(rest)?;
+            Ok((spread_form(exp), rest))
+        },
+        _ if token.starts_with('@') => Ok((spread_form(parse_atom(&token[1..])), rest)),
+        _ => Ok((parse_atom(token), rest)),
+    }
+}
+
+// what the quoting shorthand before a form stands for
+fn quote_name(token: &str) -> &'static str {
+    match token {
+        "`" => "quasiquote",
+        "," => "unquote",
+        ",@" => "unquote-splicing",
+        _ => "quote",
+    }
+}
+
+fn spread_form(exp: RispExp) -> RispExp {
+    RispExp::List(vec![RispExp::Symbol("spread".to_string()), exp].into())
+}
+
+fn read_seq(tokens: &[String]) -> Result<(RispExp, &[String]), RispErr> {
+    read_delimited(tokens, ")")
+}
+
+// the forms up to the `close` token, as a list
+fn read_delimited<'a>(tokens: &'a [String], close: &str) -> Result<(RispExp, &'a [String]), RispErr> {
+    let mut res: Vec<RispExp> = vec![];
+    let mut xs = tokens;
+
+    loop {
+        let (next_token, rest) = xs
+            .split_first()
+            .ok_or(RispErr::Reason(format!("could not find closing `{}`", close)))
+            ?;
+
+        if next_token == close {
+            return Ok((RispExp::List(res.into()), rest)) // skip `)`, head to token after
+        }
+
+        let (exp, new_xs) = parse(xs)?;
+        res.push(exp);
+        xs = new_xs;
+    }
+}
+
+// the string a `"...\n..."` token stands for, with its escapes replaced
+fn parse_string(token: &str) -> Result<RispExp, RispErr> {
+    let mut s = String::new();
+    let mut chars = token[1..].chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if chars.as_str().is_empty() => return Ok(RispExp::Str(s)),
+            '\\' => match chars.next() {
+                Some('n') => s.push('\n'),
+                Some('t') => s.push('\t'),
+                Some('r') => s.push('\r'),
+                Some(c @ ('"' | '\\')) => s.push(c),
+                Some(c) => return Err(RispErr::Reason(format!("unknown escape `\\{}` in string", c))),
+                None => break,
+            },
+            _ => s.push(c),
+        }
+    }
+
+    Err(RispErr::Reason("could not find closing `\"`".to_string()))
+}
+
+// the character a `#\a` or `#\space` token stands for
+fn parse_char(token: &str) -> Result<RispExp, RispErr> {
+    let name = &token[2..];
+    let mut chars = name.chars();
+    let c = match (chars.next(), chars.as_str()) {
+        (Some(c), "") => c,
+        _ => match name {
+            "space" => ' ',
+            "newline" => '\n',
+            "tab" => '\t',
+            "return" => '\r',
+            _ => return Err(RispErr::Reason(format!("unknown character `{}`", token))),
+        },
+    };
+
+    Ok(RispExp::Char(c))
+}
+
+fn char_literal(c: char) -> String {
+    match c {
+        ' ' => "#\\space".to_string(),
+        '\n' => "#\\newline".to_string(),
+        '\t' => "#\\tab".to_string(),
+        '\r' => "#\\return".to_string(),
+        _ => format!("#\\{}", c),
+    }
+}
+
+fn parse_atom(tokens: &str) -> RispExp {
+    match tokens {
+        "true" => RispExp::Bool(true),
+        "false" => RispExp::Bool(false),
+        "nil" => RispExp::List(vec![].into()),
+        _ if tokens.starts_with("::") && tokens.len() > 2 => namespace::qualify(&tokens[2..]),
+        // a lone `:` is the type annotation of a param
+        _ if tokens.starts_with(':') && tokens.len() > 1 => RispExp::Keyword(tokens.to_string()),
+        _ if tokens.starts_with('"') => RispExp::Str(tokens[1..tokens.len() - 1].to_string()),
+        _ => match numeric::parse(tokens) {
+            Some(n) => n,
+            None => {
+                let potential_float: Result<f64, ParseFloatError> = tokens.parse();
+                match potential_float {
+                    Ok(v) => RispExp::Number(v),
+                    Err(_) => RispExp::Symbol(tokens.to_string().clone())
+                }
+            }
+        }
+    }
+
+}
+
+// what a conditional makes of a test, nil and `false` being false and lists
+// that aren't empty true. Anything else isn't a test
+fn truthy(exp: &RispExp) -> Option<bool> {
+    match exp {
+        RispExp::Bool(b) => Some(*b),
+        RispExp::List(list) => Some(!list.is_empty()),
+        _ => None,
+    }
+}
+
+fn eval_if_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let test_form = arg_forms.first().ok_or(
+        RispErr::Reason(
+            "expected test form".to_string(),
+        )  
+    )?;
+
+    let test_eval = eval(test_form, env)?; match truthy(&test_eval) {
+        Some(b) => {
+            let form_idx = if b { 1 } else { 2 };
+            // without an else branch it's nil
+            if form_idx == 2 && arg_forms.len() == 2 {
+                return Ok(RispExp::List(vec![].into()))
+            }
+            let res_form = arg_forms.get(form_idx)
+                .ok_or(RispErr::Reason(
+                    format!("expected form idx={}", form_idx)     
+                ))?;
+
+            eval(res_form, env)
+        },
+
+        None => Err(
+            RispErr::Reason(format!("unexpected test form='{}'", test_form))     
+        )
+    }
+}
+
+// `(assert test [message])` fails unless the test is true
+fn eval_assert_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let test_form = arg_forms.first().ok_or(RispErr::Reason("expected test form".to_string()))?;
+    if let RispExp::Bool(true) = eval(test_form, env)? {
+        return Ok(RispExp::Bool(true))
+    }
+
+    match arg_forms.get(1) {
+        Some(message) => match eval(message, env)? {
+            RispExp::Str(message) => Err(RispErr::Reason(format!("assertion failed: {}", message))),
+            message => Err(RispErr::Reason(format!("assertion failed: {}", message))),
+        },
+        None => {
+            let source = edn::write_exp(test_form).unwrap_or_else(|_| test_form.to_string());
+            Err(RispErr::Reason(format!("assertion failed: {}", source)))
+        },
+    }
+}
+
+// `(and a b...)` stops at the first false value and `(or a b...)` at the
+// first true one, either returns the last value it evaluated. What's true is
+// the same as for `if`, so every value but the last has to be a bool or a list
+fn eval_and_or_args(arg_forms: &[RispExp], stop_at_false: bool, env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let op = if stop_at_false { "and" } else { "or" };
+    let mut value = RispExp::Bool(stop_at_false);
+    for (idx, form) in arg_forms.iter().enumerate() {
+        value = eval(form, env)?;
+        if idx == arg_forms.len() - 1 {
+            break
+        }
+        match truthy(&value) {
+            Some(b) if b != stop_at_false => break,
+            Some(_) => (),
+            None => return Err(RispErr::Reason(format!("`{}` expected a bool or a list, got '{}'", op, value))),
+        }
+    }
+
+    Ok(value)
+}
+
+// `(begin a b...)` evaluates each form in order, in the scope it's in, and
+// returns the last value, `nil` when there aren't any
+fn eval_begin_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let mut value = RispExp::List(vec![].into());
+    for form in arg_forms {
+        value = eval(form, env)?;
+    }
+
+    Ok(value)
+}
+
+// `(while test body...)` evaluates the body for as long as the test is true,
+// and returns the last value it got, `nil` when it never ran
+fn eval_while_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let (test_form, body) = arg_forms.split_first()
+        .ok_or(RispErr::Reason("expected test form".to_string()))?;
+
+    let mut value = RispExp::List(vec![].into());
+    loop {
+        match truthy(&eval(test_form, env)?) {
+            Some(true) => (),
+            Some(false) => return Ok(value),
+            None => return Err(RispErr::Reason(format!("unexpected test form='{}'", test_form))),
+        }
+        for form in body {
+            value = eval(form, env)?;
+        }
+    }
+}
+
+fn eval_def_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let first_form = arg_forms.first().ok_or(
+        RispErr::Reason(
+            "expected first form".to_string(),
+        )     
+    )?;
+
+    let first_str = match first_form {
+         RispExp::Symbol(s) => Ok(s.clone()),
+         _ => Err(RispErr::Reason(
+            "expected first form to be a symbol".to_string(),
+        ))
+    }?;
+
+    let second_form = arg_forms.get(1).ok_or(
+        RispErr::Reason(
+            "expected second form".to_string(),
+        )     
+    )?;
+
+    if arg_forms.len() > 2 {
+        return Err(
+            RispErr::Reason(
+                "def can only have two forms ".to_string(),
+            )     
+        ) 
+    }
+
+    let second_eval = eval(second_form, env)?;
+    env.insert(first_str, second_eval);
+
+    Ok(first_form.clone())
+}
+
+// `(set! name value)` changes the binding `name` already has, in whichever
+// scope it's in, and returns the new value
+fn eval_set_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let name = match arg_forms {
+        [RispExp::Symbol(name), _] => Ok(name.clone()),
+        _ => Err(RispErr::Reason("expected (set! name value)".to_string())),
+    }?;
+
+    let value = eval(&arg_forms[1], env)?;
+    let mut frame: &RispEnv = env;
+    loop {
+        if frame.contains(&name) {
+            if frame.outer.is_none() || closed_over_by_producer(&frame.data) {
+                effect(&format!("set! '{}'", name))?;
+            }
+            frame.insert(name, value.clone());
+            return Ok(value)
+        }
+        match frame.outer {
+            Some(outer) => frame = outer,
+            None => break,
+        }
+    }
+
+    // builtins are only in the global environment once they're used
+    if lazy::lookup(&name).is_some() {
+        effect(&format!("set! '{}'", name))?;
+        frame.insert(name, value.clone());
+        return Ok(value)
+    }
+
+    Err(RispErr::Reason(format!("can not set! '{}', it isn't bound", name)))
+}
+
+fn eval_defspec_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let name = match arg_forms {
+        [RispExp::Symbol(name), _] => Ok(name.clone()),
+        _ => Err(RispErr::Reason("expected (defspec name spec)".to_string())),
+    }?;
+
+    let spec = eval(&arg_forms[1], env)?;
+    spec::check_spec(&spec)?;
+    env.insert(name, spec);
+
+    Ok(arg_forms[0].clone())
+}
+
+// like `def`, but keeps an existing binding so reloading a file doesn't reset state
+fn eval_defonce_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    match arg_forms.first() {
+        Some(RispExp::Symbol(s)) if env.contains(s) => Ok(arg_forms[0].clone()),
+        _ => eval_def_args(arg_forms, env),
+    }
+}
+
+fn eval_lambda_args(arg_forms: &[RispExp], env: &RispEnv) -> Result<RispExp, RispErr> {
+    let params_exp = arg_forms.first().ok_or(
+        RispErr::Reason(
+            "expected args form".to_string(),
+        )       
+    )?;
+
+    let body_exps = &arg_forms[1..];
+    if body_exps.is_empty() {
+        return Err(
+            RispErr::Reason(
+                "expected second form".to_string(),
+            )
+        )
+    }
+
+    let annotated = types::strip_annotations(params_exp, body_exps)?;
+    Ok(
+        RispExp::Lambda(
+            RispLambda {
+                body_exps: Rc::new(body_exps[annotated.skip..].to_vec()),
+                params_exp: Rc::new(annotated.params),
+                signature: match annotated.annotated {
+                    true => Some(Rc::new(annotated.signature)),
+                    false => None,
+                },
+                closure: captured(env),
+            }     
+        )     
+    )
+}
+
+// `(defn name params body...)` is `(def name (fn params body...))`
+fn eval_defn_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let name = match arg_forms.first() {
+        Some(RispExp::Symbol(name)) => Ok(name.clone()),
+        _ => Err(RispErr::Reason("expected (defn name params body...)".to_string())),
+    }?;
+
+    let lambda = eval_lambda_args(&arg_forms[1..], env)?;
+    env.insert(name, lambda);
+
+    Ok(arg_forms[0].clone())
+}
+
+fn eval_generator_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let producer_form = arg_forms.first().ok_or(
+        RispErr::Reason(
+            "expected producer form".to_string(),
+        )
+    )?;
+
+    if arg_forms.len() > 1 {
+        return Err(
+            RispErr::Reason(
+                "generator can only have one form".to_string(),
+            )
+        )
+    }
+
+    match eval(producer_form, env)? {
+        RispExp::Lambda(producer) => Ok(RispExp::Generator(Rc::new(Generator {
+            producer,
+            yielded: RefCell::new(vec![]),
+            finished: Cell::new(false),
+        }))),
+        _ => Err(
+            RispErr::Reason("expected producer to be a fn".to_string())
+        ),
+    }
+}
+
+fn eval_yield_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let value_form = arg_forms.first().ok_or(
+        RispErr::Reason(
+            "expected value form".to_string(),
+        )
+    )?;
+
+    let value = eval(value_form, env)?;
+    YIELD_SINKS.with(|sinks| {
+        let mut sinks = sinks.borrow_mut();
+        let sink = sinks.last_mut().ok_or(
+            RispErr::Reason("yield outside of a generator".to_string())
+        )?;
+
+        if sink.skip > 0 {
+            sink.skip -= 1;
+            return Ok(value)
+        }
+
+        sink.values.push(value.clone());
+        match sink.limit {
+            Some(limit) if sink.values.len() >= limit => Err(RispErr::GeneratorFull),
+            _ => Ok(value),
+        }
+    })
+}
+
+// A generator keeps what it's yielded, so consumers asking for no more than
+// that get it without the producer running again. One wanting more replays
+// the producer from the start, stepping over the values already kept and
+// stopping once it has `limit` of them. Replaying is only the same as carrying
+// on when the producer does nothing but yield, so `effect` refuses the rest.
+fn run_generator(
+    generator: &Generator, limit: Option<usize>, env: &mut RispEnv
+) -> Result<Vec<RispExp>, RispErr> {
+    let kept = generator.yielded.borrow().len();
+    if generator.finished.get() || limit.is_some_and(|n| n <= kept) {
+        return Ok(truncated(generator.yielded.borrow().clone(), limit))
+    }
+
+    YIELD_SINKS.with(|sinks| sinks.borrow_mut().push(YieldSink {
+        skip: kept,
+        closure: generator.producer.closure.clone(),
+        values: vec![],
+        limit: limit.map(|n| n - kept),
+    }));
+    let res = apply_lambda(&generator.producer, vec![], env);
+    let sink = YIELD_SINKS.with(|sinks| sinks.borrow_mut().pop())
+        .expect("generator sink disappeared");
+
+    match res {
+        Ok(_) => generator.finished.set(true),
+        Err(RispErr::GeneratorFull) => (),
+        Err(e) => return Err(e),
+    }
+
+    generator.yielded.borrow_mut().extend(sink.values);
+    Ok(truncated(generator.yielded.borrow().clone(), limit))
+}
+
+// errors while a generator's producer is running, for `what` it's doing that
+// would be done again when it's replayed: printing, the builtins that touch
+// the world outside the interpreter and `set!`s of variables from outside it
+fn effect(what: &str) -> Result<(), RispErr> {
+    match producing() {
+        false => Ok(()),
+        true => Err(RispErr::Reason(format!(
+            "a generator's producer can't {}, it's replayed from the start for more values", what
+        ))),
+    }
+}
+
+fn producing() -> bool {
+    YIELD_SINKS.with(|sinks| !sinks.borrow().is_empty())
+}
+
+// whether `frame` is a scope some running producer closed over
+fn closed_over_by_producer(frame: &Frame) -> bool {
+    YIELD_SINKS.with(|sinks| {
+        sinks.borrow().iter().any(|sink| sink.closure.iter().any(|outer| Rc::ptr_eq(outer, frame)))
+    })
+}
+
+fn truncated(mut values: Vec<RispExp>, limit: Option<usize>) -> Vec<RispExp> {
+    values.truncate(limit.unwrap_or(values.len()));
+    values
+}
+
+fn seq_values(
+    seq: &RispExp, limit: Option<usize>, env: &mut RispEnv
+) -> Result<Vec<RispExp>, RispErr> {
+    match seq {
+        RispExp::List(list) => {
+            let n = limit.unwrap_or(list.len()).min(list.len());
+            Ok(list[..n].to_vec())
+        },
+        RispExp::Vector(xs) => Ok(truncated(xs.borrow().clone(), limit)),
+        RispExp::Generator(producer) => run_generator(producer, limit, env),
+        RispExp::Queue(queue) => Ok(truncated(queue.values(), limit)),
+        RispExp::Stack(stack) => Ok(truncated(queue::stack_values(stack), limit)),
+        RispExp::Map(map) => Ok(truncated(hashmap::entries(map), limit)),
+        _ => match sorted::entries(seq) {
+            Some(entries) => Ok(truncated(entries, limit)),
+            None => Err(RispErr::Reason("expected a list or generator".to_string())),
+        },
+    }
+}
+
+// `(for (x seq) body...)` evaluates its body with `x` bound to each value of
+// `seq`, so it's a special form where `take` can be a builtin
+fn eval_for_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let binding_form = arg_forms.first().ok_or(
+        RispErr::Reason(
+            "expected binding form".to_string(),
+        )
+    )?;
+
+    let (name, seq_form) = match binding_form {
+        RispExp::List(binding) => match &binding[..] {
+            [RispExp::Symbol(name), seq_form] => Ok((name.clone(), seq_form)),
+            _ => Err(RispErr::Reason("expected binding form to be (symbol sequence)".to_string())),
+        },
+        _ => Err(RispErr::Reason("expected binding form to be a list".to_string())),
+    }?;
+
+    let body_exps = &arg_forms[1..];
+    if body_exps.is_empty() {
+        return Err(RispErr::Reason("expected body form".to_string()))
+    }
+
+    let seq = eval(seq_form, env)?;
+    let mut res = vec![];
+    for value in seq_values(&seq, None, env)? {
+        let mut data: HashMap<String, RispExp> = HashMap::new();
+        data.insert(name.clone(), value);
+        let body_env = &mut RispEnv::new(data, Some(env));
+        res.push(eval_body(body_exps, body_env)?);
+    }
+
+    Ok(RispExp::List(res.into()))
+}
+
+// Handlers run before anything unwinds, innermost first. A handler either
+// transfers control with `invoke-restart` or returns normally to decline, in
+// which case the next outer handler is tried.
+fn signal_condition(condition: &RispExp, env: &mut RispEnv) -> Result<(), RispErr> {
+    let handlers = HANDLERS.with(|handlers| handlers.borrow().clone());
+    for (idx, handler) in handlers.iter().enumerate().rev() {
+        // a handler only sees the handlers that were established outside of it
+        HANDLERS.with(|active| active.borrow_mut().truncate(idx));
+        let res = call_exp(handler, vec![condition.clone()], env);
+        HANDLERS.with(|active| *active.borrow_mut() = handlers.clone());
+        res?;
+    }
+
+    Ok(())
+}
+
+fn eval_signal_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let condition_form = arg_forms.first().ok_or(
+        RispErr::Reason(
+            "expected condition form".to_string(),
+        )
+    )?;
+
+    if arg_forms.len() > 1 {
+        return Err(
+            RispErr::Reason(
+                "signal can only have one form".to_string(),
+            )
+        )
+    }
+
+    let condition = eval(condition_form, env)?;
+    signal_condition(&condition, env)?;
+
+    Err(RispErr::Reason(format!("unhandled condition '{}'", condition)))
+}
+
+fn eval_handler_bind_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let handler_form = arg_forms.first().ok_or(
+        RispErr::Reason(
+            "expected handler form".to_string(),
+        )
+    )?;
+
+    let body_exps = &arg_forms[1..];
+    if body_exps.is_empty() {
+        return Err(RispErr::Reason("expected body form".to_string()))
+    }
+
+    let handler = eval(handler_form, env)?;
+    match handler {
+        RispExp::Func(_) | RispExp::EnvFunc(_) | RispExp::Lambda(_) => (),
+        _ => return Err(RispErr::Reason("expected handler to be a function".to_string())),
+    }
+
+    HANDLERS.with(|handlers| handlers.borrow_mut().push(handler));
+    let res = eval_body(body_exps, env);
+    HANDLERS.with(|handlers| handlers.borrow_mut().pop());
+
+    res
+}
+
+// a user-defined `(name (params...) body...)` clause of a `restart-case`
+struct RestartClause<'a> {
+    name: String,
+    params_exp: &'a RispExp,
+    body_exps: &'a [RispExp],
+}
+
+fn parse_restart_clause(clause: &RispExp) -> Result<RestartClause<'_>, RispErr> {
+    match clause {
+        RispExp::List(list) => match &list[..] {
+            [RispExp::Symbol(name), params_exp, body_exps @ ..] if !body_exps.is_empty() =>
+                Ok(RestartClause { name: name.clone(), params_exp, body_exps }),
+            _ => Err(RispErr::Reason("expected restart clause to be (name (params) body)".to_string())),
+        },
+        _ => Err(RispErr::Reason("expected restart clause to be a list".to_string())),
+    }
+}
+
+fn run_restart_frame(form: &RispExp, frame: RestartFrame, env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    RESTARTS.with(|restarts| restarts.borrow_mut().push(frame));
+    let res = match eval(form, env) {
+        // native errors become conditions while this frame's restarts are still reachable
+        Err(e @ (RispErr::Reason(_) | RispErr::Args(_))) => {
+            let condition = RispExp::List(vec![
+                RispExp::Symbol("error".to_string()),
+                RispExp::Symbol(e.to_string()),
+            ].into());
+            signal_condition(&condition, env).and(Err(e))
+        },
+        res => res,
+    };
+    RESTARTS.with(|restarts| restarts.borrow_mut().pop());
+
+    res
+}
+
+// `restart-case` evaluates its form with `retry` (evaluate the form again),
+// `use-value` (return the given value instead) and any user clauses available
+// to handlers through `invoke-restart`.
+fn eval_restart_case_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let form = arg_forms.first().ok_or(
+        RispErr::Reason(
+            "expected restartable form".to_string(),
+        )
+    )?;
+
+    let clauses = arg_forms[1..]
+        .iter()
+        .map(parse_restart_clause)
+        .collect::<Result<Vec<RestartClause>, RispErr>>()?;
+
+    let id = NEXT_RESTART_FRAME.with(|next| {
+        let id = *next.borrow();
+        *next.borrow_mut() += 1;
+        id
+    });
+
+    let mut names: Vec<String> = clauses.iter().map(|c| c.name.clone()).collect();
+    names.extend(STANDARD_RESTARTS.iter().map(|name| name.to_string()));
+
+    loop {
+        let frame = RestartFrame { id, names: names.clone() };
+        match run_restart_frame(form, frame, env) {
+            Err(RispErr::Restart { frame, name, args }) if frame == id => {
+                if let Some(clause) = clauses.iter().find(|c| c.name == name) {
+                    let lambda = RispLambda {
+                        params_exp: Rc::new(clause.params_exp.clone()),
+                        body_exps: Rc::new(clause.body_exps.to_vec()),
+                        signature: None,
+                        closure: captured(env),
+                    };
+                    return apply_lambda(&lambda, args, env)
+                }
+
+                match name.as_ref() {
+                    "retry" => continue,
+                    _ => return args.into_iter().next().ok_or(
+                        RispErr::Reason("use-value expects a value".to_string())
+                    ),
+                }
+            },
+            res => return res,
+        }
+    }
+}
+
+// `(invoke-restart 'name args...)`, the name is a symbol or a keyword
+fn invoke_restart(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let name = match args.first() {
+        Some(RispExp::Symbol(name)) => Ok(name.clone()),
+        Some(RispExp::Keyword(name)) => Ok(name[1..].to_string()),
+        Some(name) => Err(RispErr::Reason(format!("expected restart name to be a symbol or keyword, got '{}'", name))),
+        None => Err(RispErr::Reason("expected restart name".to_string())),
+    }?;
+
+    let frame = RESTARTS.with(|restarts| {
+        restarts.borrow()
+            .iter()
+            .rev()
+            .find(|frame| frame.names.contains(&name))
+            .map(|frame| frame.id)
+    }).ok_or(RispErr::Reason(format!("no restart named '{}' is active", name)))?;
+
+    Err(RispErr::Restart { frame, name, args: args[1..].to_vec() })
+}
+
+fn eval_defer_args(arg_forms: &[RispExp]) -> Result<RispExp, RispErr> {
+    let deferred_form = arg_forms.first().ok_or(
+        RispErr::Reason(
+            "expected deferred form".to_string(),
+        )
+    )?;
+
+    if arg_forms.len() > 1 {
+        return Err(
+            RispErr::Reason(
+                "defer can only have one form".to_string(),
+            )
+        )
+    }
+
+    DEFERRED.with(|deferred| {
+        let mut deferred = deferred.borrow_mut();
+        let frame = deferred.last_mut().ok_or(
+            RispErr::Reason("defer outside of a body".to_string())
+        )?;
+        frame.push(deferred_form.clone());
+
+        Ok(RispExp::Bool(true))
+    })
+}
+
+// calls `f` with each line of the file at `path` in turn, without reading all
+// of it in, and returns how many lines there were
+fn each_line(path: &str, mut f: impl FnMut(RispExp) -> Result<(), RispErr>) -> Result<usize, RispErr> {
+    // opened on the first read, a replayed run doesn't need the file
+    let mut reader: Option<BufReader<File>> = None;
+    let mut count = 0;
+    loop {
+        let line = replay::recorded("with-lines", || {
+            let lines = match &mut reader {
+                Some(lines) => lines,
+                None => {
+                    let file = File::open(sandbox::resolve(path)?)
+                        .map_err(|e| RispErr::Reason(format!("could not read '{}': {}", path, e)))?;
+                    reader.insert(BufReader::new(file))
+                },
+            };
+
+            let mut line = String::new();
+            match lines.read_line(&mut line) {
+                Ok(0) => Ok(RispExp::Bool(false)),
+                Ok(_) => {
+                    let end = line.trim_end_matches(['\n', '\r']).len();
+                    line.truncate(end);
+                    Ok(RispExp::Str(line))
+                },
+                Err(e) => Err(RispErr::Reason(format!("could not read '{}': {}", path, e))),
+            }
+        })?;
+
+        match line {
+            RispExp::Str(_) => f(line)?,
+            _ => return Ok(count),
+        };
+        count += 1;
+    }
+}
+
+// `(with-lines path f)`
+fn with_lines(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    match args {
+        [RispExp::Str(path), f] => {
+            let count = each_line(path, |line| call_exp(f, vec![line], env).map(|_| ()))?;
+            Ok(RispExp::Int(count as i64))
+        },
+        _ => Err(RispErr::Reason("expected a path and a function to call with each line".to_string())),
+    }
+}
+
+// `(process-files pattern parse merge)`, or `(process-files pattern parse
+// merge init)`, passes every line of each file matching the pattern to
+// `parse` and folds what it returns into the result with `(merge result
+// parsed)`, starting from `init` or the first parsed line. Lines `parse`
+// returns `false` for are left out. Files are read one after another, risp
+// values can't be handed to other threads to work on them in parallel.
+fn process_files(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let (pattern, parse, merge, mut res) = match args {
+        [pattern @ RispExp::Str(_), parse, merge] => (pattern, parse, merge, None),
+        [pattern @ RispExp::Str(_), parse, merge, init] => (pattern, parse, merge, Some(init.clone())),
+        _ => return Err(RispErr::Reason("expected a pattern, a function to parse lines and one to merge them".to_string())),
+    };
+
+    let paths = match glob::glob_builtin(std::slice::from_ref(pattern))? {
+        RispExp::List(paths) => paths,
+        _ => vec![].into(),
+    };
+    for path in paths {
+        let path = parse_single_str(&path)?;
+        each_line(&path, |line| {
+            let parsed = call_exp(parse, vec![line], env)?;
+            if matches!(parsed, RispExp::Bool(false)) {
+                return Ok(())
+            }
+
+            res = Some(match res.take() {
+                Some(merged) => call_exp(merge, vec![merged, parsed], env)?,
+                None => parsed,
+            });
+            Ok(())
+        })?;
+    }
+
+    Ok(res.unwrap_or(RispExp::Bool(false)))
+}
+
+fn eval_with_open_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let bindings_form = arg_forms.first().ok_or(
+        RispErr::Reason(
+            "expected bindings form".to_string(),
+        )
+    )?;
+
+    let bindings = match bindings_form {
+        RispExp::List(bindings) => Ok(bindings),
+        _ => Err(RispErr::Reason("expected bindings form to be a list".to_string())),
+    }?;
+
+    let body_exps = &arg_forms[1..];
+    if body_exps.is_empty() {
+        return Err(RispErr::Reason("expected body form".to_string()))
+    }
+
+    let mut data: HashMap<String, RispExp> = HashMap::new();
+    let mut resources: Vec<Rc<RefCell<Resource>>> = vec![];
+    let mut res = Ok(RispExp::Bool(true));
+    for binding in bindings {
+        res = match binding {
+            RispExp::List(binding) => match &binding[..] {
+                [RispExp::Symbol(name), resource_form] => eval(resource_form, env)
+                    .and_then(|resource| {
+                        resources.push(resource::any(Some(&resource))?);
+                        data.insert(name.clone(), resource.clone());
+                        Ok(resource)
+                    }),
+                _ => Err(RispErr::Reason("expected binding to be (symbol resource)".to_string())),
+            },
+            _ => Err(RispErr::Reason("expected binding to be a list".to_string())),
+        };
+
+        if res.is_err() {
+            break
+        }
+    }
+
+    if res.is_ok() {
+        let body_env = &mut RispEnv::new(data, Some(env));
+        res = eval_body(body_exps, body_env);
+    }
+
+    // the body may have closed some already
+    for resource in resources.iter().rev() {
+        resource.borrow_mut().release();
+    }
+
+    res
+}
+
+fn eval_when_feature_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let feature_form = arg_forms.first().ok_or(
+        RispErr::Reason(
+            "expected feature form".to_string(),
+        )
+    )?;
+
+    let feature = match feature_form {
+        RispExp::Symbol(s) | RispExp::Keyword(s) => Ok(s.trim_start_matches(':')),
+        _ => Err(RispErr::Reason("expected feature to be a symbol".to_string())),
+    }?;
+
+    if builtins::has_feature(env, feature) {
+        eval_body(&arg_forms[1..], env)
+    } else {
+        Ok(RispExp::Bool(false))
+    }
+}
+
+// the form `(name x)` quotes, if it is one
+fn quoted<'f>(form: &'f RispExp, name: &str) -> Option<&'f RispExp> {
+    match form {
+        RispExp::List(list) => match &list[..] {
+            [RispExp::Symbol(head), x] if head == name => Some(x),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// fills in a quasiquoted template. `depth` counts the quasiquotes around
+// `form`, only unquotes at depth 1 are evaluated so nested templates are left
+// for when they're evaluated in turn
+fn quasiquote(form: &RispExp, depth: usize, env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    if let Some(x) = quoted(form, "unquote") {
+        return match depth {
+            1 => eval(x, env),
+            _ => Ok(RispExp::List(vec![RispExp::Symbol("unquote".to_string()), quasiquote(x, depth - 1, env)?].into())),
+        }
+    }
+    if let Some(x) = quoted(form, "quasiquote") {
+        return Ok(RispExp::List(vec![RispExp::Symbol("quasiquote".to_string()), quasiquote(x, depth + 1, env)?].into()))
+    }
+    if quoted(form, "unquote-splicing").is_some() && depth == 1 {
+        return Err(RispErr::Reason("`,@` can only splice into a list or vector".to_string()))
+    }
+
+    let list = match form {
+        RispExp::List(list) => list.clone(),
+        RispExp::Vector(xs) => xs.borrow().clone().into(),
+        RispExp::Map(map) => {
+            let mut pairs = vec![];
+            for (k, v) in map.iter() {
+                pairs.push(quasiquote(&k.exp(), depth, env)?);
+                pairs.push(quasiquote(v, depth, env)?);
+            }
+            return hashmap::from_pairs(&pairs)
+        },
+        _ => return Ok(form.clone()),
+    };
+
+    let mut res = vec![];
+    for x in &list {
+        match quoted(x, "unquote-splicing") {
+            Some(xs) if depth == 1 => {
+                let seq = eval(xs, env)?;
+                res.extend(seq_values(&seq, None, env)?);
+            },
+            Some(xs) => res.push(RispExp::List(vec![
+                RispExp::Symbol("unquote-splicing".to_string()),
+                quasiquote(xs, depth - 1, env)?,
+            ].into())),
+            None => res.push(quasiquote(x, depth, env)?),
+        }
+    }
+
+    match form {
+        RispExp::Vector(_) => Ok(RispExp::Vector(Rc::new(RefCell::new(res)))),
+        _ => Ok(RispExp::List(res.into())),
+    }
+}
+
+// `(let ((x 1) (y (+ x 1))) body...)`, each binding can refer to the ones
+// before it
+fn eval_let_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let bindings = match arg_forms.first() {
+        Some(RispExp::List(bindings)) => Ok(bindings),
+        Some(_) => Err(RispErr::Reason("expected bindings form to be a list".to_string())),
+        None => Err(RispErr::Reason("expected bindings form".to_string())),
+    }?;
+
+    let body_exps = &arg_forms[1..];
+    if body_exps.is_empty() {
+        return Err(RispErr::Reason("expected body form".to_string()))
+    }
+
+    let body_env = &mut RispEnv::new(HashMap::new(), Some(env));
+    for binding in bindings {
+        let (name, value_form) = match binding {
+            RispExp::List(binding) => match &binding[..] {
+                [RispExp::Symbol(name), value_form] => Ok((name, value_form)),
+                _ => Err(RispErr::Reason("expected binding to be (symbol form)".to_string())),
+            },
+            _ => Err(RispErr::Reason("expected binding to be a list".to_string())),
+        }?;
+
+        let value = eval(value_form, body_env)?;
+        body_env.insert(name.clone(), value);
+    }
+
+    eval_body(body_exps, body_env)
+}
+
+// `(let-values (((q r) (divmod 7 2)) ...) body...)` binds each tuple's parts
+fn eval_let_values_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let bindings_form = arg_forms.first().ok_or(
+        RispErr::Reason(
+            "expected bindings form".to_string(),
+        )
+    )?;
+
+    let bindings = match bindings_form {
+        RispExp::List(bindings) => Ok(bindings),
+        _ => Err(RispErr::Reason("expected bindings form to be a list".to_string())),
+    }?;
+
+    let body_exps = &arg_forms[1..];
+    if body_exps.is_empty() {
+        return Err(RispErr::Reason("expected body form".to_string()))
+    }
+
+    let mut data: HashMap<String, RispExp> = HashMap::new();
+    for binding in bindings {
+        let (names, value_form) = match binding {
+            RispExp::List(binding) => match &binding[..] {
+                [RispExp::List(names), value_form] => Ok((names, value_form)),
+                _ => Err(RispErr::Reason("expected binding to be ((symbols...) form)".to_string())),
+            },
+            _ => Err(RispErr::Reason("expected binding to be a list".to_string())),
+        }?;
+
+        let values = match eval(value_form, env)? {
+            RispExp::Tuple(values) => Ok(values),
+            other => Err(RispErr::Reason(format!("expected values, got '{}'", other))),
+        }?;
+
+        if names.len() != values.len() {
+            return Err(RispErr::Reason(format!("expected {} values, got {}", names.len(), values.len())))
+        }
+
+        for (name, value) in names.iter().zip(values.iter()) {
+            match name {
+                RispExp::Symbol(s) => data.insert(s.clone(), value.clone()),
+                _ => return Err(RispErr::Reason("expected symbols in the binding list".to_string())),
+            };
+        }
+    }
+
+    let body_env = &mut RispEnv::new(data, Some(env));
+    eval_body(body_exps, body_env)
+}
+
+fn eval_built_in_form(
+    exp: &RispExp, arg_forms: &[RispExp], env: &mut RispEnv
+) -> Option<Result<RispExp, RispErr>> {
+    match exp {
+        RispExp::Symbol(s) => 
+            match s.as_ref() {
+                "quote" => Some(match arg_forms {
+                    [form] => Ok(form.clone()),
+                    _ => Err(RispErr::Reason("expected one form to quote".to_string())),
+                }),
+                "quasiquote" => Some(match arg_forms {
+                    [form] => quasiquote(form, 1, env),
+                    _ => Err(RispErr::Reason("expected one form to quasiquote".to_string())),
+                }),
+                "unquote" | "unquote-splicing" =>
+                    Some(Err(RispErr::Reason(format!("`{}` can only be used inside a quasiquote", s)))),
+                "if" => Some(eval_if_args(arg_forms, env)),
+                "begin" | "do" => Some(eval_begin_args(arg_forms, env)),
+                "while" => Some(eval_while_args(arg_forms, env)),
+                "current-env" => Some(Ok(envs::current(env))),
+                "eval" => Some(eval_forms(arg_forms, env).and_then(|args| envs::eval_in(&args, env))),
+                "assert" => Some(eval_assert_args(arg_forms, env)),
+                "and" => Some(eval_and_or_args(arg_forms, true, env)),
+                "or" => Some(eval_and_or_args(arg_forms, false, env)),
+                "def" | "define" => Some(eval_def_args(arg_forms, env)),
+                "set!" => Some(eval_set_args(arg_forms, env)),
+                "defonce" => Some(eval_defonce_args(arg_forms, env)),
+                "fn" => Some(eval_lambda_args(arg_forms, env)),
+                "defn" => Some(eval_defn_args(arg_forms, env)),
+                "generator" => Some(eval_generator_args(arg_forms, env)),
+                "yield" => Some(eval_yield_args(arg_forms, env)),
+                "for" => Some(eval_for_args(arg_forms, env)),
+                "signal" => Some(eval_signal_args(arg_forms, env)),
+                "handler-bind" => Some(eval_handler_bind_args(arg_forms, env)),
+                "restart-case" => Some(eval_restart_case_args(arg_forms, env)),
+                "defer" => Some(eval_defer_args(arg_forms)),
+                "with-open" => Some(eval_with_open_args(arg_forms, env)),
+                "with-lines" if builtins::has_feature(env, "files") => Some(eval_forms(arg_forms, env).and_then(|args| {
+                    audit::record("with-lines", &args);
+                    with_lines(&args, env)
+                })),
+                "process-files" if builtins::has_feature(env, "files") => Some(eval_forms(arg_forms, env).and_then(|args| {
+                    audit::record("process-files", &args);
+                    process_files(&args, env)
+                })),
+                "require" if builtins::has_feature(env, "files") => Some(eval_forms(arg_forms, env).and_then(|args| {
+                    audit::record("require", &args);
+                    pkg::require(&args, env)
+                })),
+                "when-feature" => Some(eval_when_feature_args(arg_forms, env)),
+                "let" => Some(eval_let_args(arg_forms, env)),
+                "let-values" => Some(eval_let_values_args(arg_forms, env)),
+                // builtins that call back into functions get their arguments evaluated here
+                "defspec" => Some(eval_defspec_args(arg_forms, env)),
+                "defmacro" => Some(macros::eval_defmacro_args(arg_forms, env)),
+                "macroexpand-1" => Some(eval_forms(arg_forms, env).and_then(|args| macros::macroexpand_1(&args, env))),
+                "macroexpand" => Some(eval_forms(arg_forms, env).and_then(|args| macros::macroexpand(&args, env))),
+                "who-calls" => Some(eval_forms(arg_forms, env).and_then(|args| graph::who_calls(&args, env))),
+                "uses" => Some(eval_forms(arg_forms, env).and_then(|args| graph::uses(&args, env))),
+                "deftag" => Some(tagged::eval_deftag_args(arg_forms, env)),
+                "defclass" => Some(objects::eval_defclass_args(arg_forms, env)),
+                "defmethod" => Some(objects::eval_defmethod_args(arg_forms, env)),
+                "edn-read" => Some(eval_forms(arg_forms, env).and_then(|args| edn::read(&args, env))),
+                "write-ppm" => Some(eval_forms(arg_forms, env).and_then(|args| {
+                    audit::record("write-ppm", &args);
+                    image::write_ppm(&args, env)
+                })),
+                "spread" => Some(Err(RispErr::Reason("`@` can only spread into function call arguments".to_string()))),
+                "exit" => process::exit(0),
+                _ => None,
+            },
+        _ => None,
+    }
+}
+
+// the environment of the whole program, that `env` is nested in
+fn global_env<'e>(env: &'e RispEnv<'e>) -> &'e RispEnv<'e> {
+    match env.outer {
+        Some(outer) => global_env(outer),
+        None => env,
+    }
+}
+
+// the scopes a fn made in `env` can see, other than the global one
+fn captured(env: &RispEnv) -> Rc<Vec<Frame>> {
+    let mut frames = vec![];
+    let mut frame = env;
+    while let Some(outer) = frame.outer {
+        frames.push(frame.data.clone());
+        frame = outer;
+    }
+    frames.reverse();
+
+    Rc::new(frames)
+}
+
+fn env_get(k: &str, env: &RispEnv) -> Option<RispExp> {
+    let local = env.data.borrow().get(k).cloned();
+    match local {
+        Some(exp) => Some(exp),
+        None => {
+            match &env.outer {
+                Some(outer_env) => env_get(k, outer_env),
+                // shared by the thread, but only seen with the feature that defines them
+                None => lazy::lookup(k).filter(|_| {
+                    FEATURE_INDEX.with(|index| index.get(k).copied()).is_none_or(|feature| builtins::has_feature(env, feature))
+                }),
+            }
+        }
+    }
+}
+
+fn eval_forms(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<Vec<RispExp>, RispErr> {
+    arg_forms 
+        .iter()
+        .map(|x| eval(x, env))
+        .collect()
+}
+
+// evaluates the arguments of a function call, splicing in `(spread xs)` ones
+fn eval_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<Vec<RispExp>, RispErr> {
+    let mut args: Vec<RispExp> = vec![];
+    for form in arg_forms {
+        match form {
+            RispExp::List(list) if is_spread_form(list) => {
+                let seq = eval(&list[1], env)?;
+                args.extend(seq_values(&seq, None, env)?);
+            },
+            _ => args.push(eval(form, env)?),
+        }
+    }
+
+    Ok(args)
+}
+
+fn is_spread_form(list: &[RispExp]) -> bool {
+    match list {
+        [RispExp::Symbol(s), _] => s == "spread",
+        _ => false,
+    }
+}
+
+// `(a b &key c (d default))`: positional names, then keyword names with
+// optional default forms, or `(a b & rest)` with the rest of the arguments
+// as a list
+struct LambdaParams {
+    positional: Vec<String>,
+    keys: Vec<(String, Option<RispExp>)>,
+    rest: Option<String>,
+}
+
+// `&rest` is the same as `&`
+fn is_rest_marker(s: &str) -> bool {
+    s == "&" || s == "&rest"
+}
+
+fn parse_lambda_params(form: Rc<RispExp>) -> Result<LambdaParams, RispErr> {
+    let list = match form.as_ref() {
+        RispExp::List(s) => Ok(s.clone()),
+        _ => Err(RispErr::Reason(
+            "expected args form to be a list".to_string(),
+        ))
+    }?;
+
+    let mut params = LambdaParams { positional: vec![], keys: vec![], rest: None };
+    let mut in_keys = false;
+    let mut list = list.into_iter();
+    while let Some(x) = list.next() {
+        match (x, in_keys) {
+            (RispExp::Symbol(s), _) if is_rest_marker(&s) => params.rest = match (in_keys, list.next(), list.next()) {
+                (false, Some(RispExp::Symbol(rest)), None) => Some(rest),
+                (true, _, _) => return Err(RispErr::Reason(
+                    "a fn can take the rest of its arguments or keyword arguments, not both".to_string(),
+                )),
+                _ => return Err(RispErr::Reason(
+                    format!("expected one name after `{}`, for the rest of the arguments", s),
+                )),
+            },
+            (RispExp::Symbol(s), false) if s == "&key" => in_keys = true,
+            (RispExp::Symbol(s), false) => params.positional.push(s),
+            (RispExp::Symbol(s), true) => params.keys.push((s, None)),
+            (RispExp::List(key), true) => match &key[..] {
+                [RispExp::Symbol(s), default] => params.keys.push((s.clone(), Some(default.clone()))),
+                _ => return Err(RispErr::Reason(
+                    "expected keyword params to be a symbol or (symbol default)".to_string(),
+                )),
+            },
+            _ => return Err(RispErr::Reason(
+                "expected symbols in the argumen list".to_string(),
+            )),
+        }
+    }
+
+    Ok(params)
+}
+
+fn env_for_lambdas<'a>(
+    params: Rc<RispExp>,
+    mut args: Vec<RispExp>,
+    outer_env: &'a RispEnv<'a>,
+) -> Result<RispEnv<'a>, RispErr> {
+    let params = parse_lambda_params(params)?;
+    let n = params.positional.len();
+    if args.len() < n && params.rest.is_some() {
+        return Err(RispErr::Reason(format!("expected at least {} arguments, got {}", n, args.len())))
+    }
+    if args.len() < n || (params.keys.is_empty() && params.rest.is_none() && args.len() != n) {
+        return Err(
+            RispErr::Reason(
+                format!("expected {} arguments, got {}", n, args.len())     
+            )
+        );
+    }
+
+    let extra = args.split_off(n);
+    let mut data: HashMap<String, RispExp> = HashMap::new();
+    for (k, v) in params.positional.into_iter().zip(args) {
+        data.insert(k, v);
+    }
+
+    match params.rest {
+        Some(rest) => {
+            data.insert(rest, RispExp::List(extra.into()));
+        },
+        None => add_key_args(&params.keys, &extra, &mut data)?,
+    }
+
+    let mut new_env = RispEnv::new(data, Some(outer_env));
+
+    // defaults can refer to the params before them
+    for (k, default) in params.keys {
+        if !new_env.contains(&k) {
+            let v = match default {
+                Some(default) => eval(&default, &mut new_env)?,
+                None => RispExp::Bool(false),
+            };
+            new_env.insert(k, v);
+        }
+    }
+
+    Ok(new_env)
+}
+
+// `:name value` pairs for the keyword params
+fn add_key_args(keys: &[(String, Option<RispExp>)], key_args: &[RispExp], data: &mut HashMap<String, RispExp>) -> Result<(), RispErr> {
+    if !key_args.len().is_multiple_of(2) {
+        return Err(RispErr::Reason("expected keyword arguments to come in `:name value` pairs".to_string()))
+    }
+
+    for pair in key_args.chunks(2) {
+        let name = match &pair[0] {
+            RispExp::Keyword(k) => &k[1..],
+            _ => return Err(RispErr::Reason(format!("expected a keyword argument, got '{}'", pair[0]))),
+        };
+
+        if !keys.iter().any(|(k, _)| k == name) {
+            return Err(RispErr::Reason(format!("unknown keyword argument ':{}'", name)))
+        }
+        data.insert(name.to_string(), pair[1].clone());
+    }
+
+    Ok(())
+}
+
+fn eval(exp: &RispExp, env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let _depth = stats::count_form()?;
+    match exp {
+        RispExp::Bool(_a) => Ok(exp.clone()),
+        // keywords are markers for options and map keys, so they stand for themselves
+        RispExp::Keyword(_) => Ok(exp.clone()),
+        RispExp::Symbol(k) => env_get(k, env).ok_or_else(|| unbound_symbol(k)),
+        RispExp::Number(_a) => Ok(exp.clone()),
+        RispExp::Int(_) => Ok(exp.clone()),
+        #[cfg(feature = "bignum")]
+        RispExp::Exact(_) => Ok(exp.clone()),
+        RispExp::Str(_a) => Ok(exp.clone()),
+        RispExp::Char(_) => Ok(exp.clone()),
+        RispExp::List(list) => {
+            // `()` is nil, which evaluates to itself
+            let first_form = match list.first() {
+                Some(first_form) => first_form,
+                None => return Ok(exp.clone()),
+            };
+            let arg_forms = &list[1..];
+
+            match eval_built_in_form(first_form, arg_forms, env) {
+                // a form that changed watched bindings reports them once it's done
+                Some(res) => {
+                    watch::fire(env)?;
+                    res
+                },
+                None => {
+                    if let Some(expansion) = macros::expand_1(exp, env)? {
+                        return eval(&expansion, env)
+                    }
+
+                    let first_eval = eval(first_form, env)?;
+                    match first_eval {
+                        RispExp::Func(f) => call_builtin(f, &eval_args(arg_forms, env)?),
+
+                        RispExp::EnvFunc(f) => f(&eval_args(arg_forms, env)?, env),
+
+                        RispExp::Lambda(lambda) => eval_lambda(&lambda, arg_forms, env),
+
+                        RispExp::Instrumented(inst) => spec::call_instrumented(&inst, eval_args(arg_forms, env)?, env),
+
+                        RispExp::Generic(generic) => objects::call_generic(&generic, eval_args(arg_forms, env)?, env),
+
+                        _ => Err(
+                            RispErr::Reason("first form must be a function".to_string()) 
+                        ),
+                    }
+                }
+            }
+
+        },
+        RispExp::Func(_) => Err(
+            RispErr::Reason("unexpected form".to_string())   
+        ),
+        RispExp::EnvFunc(_) => Err(RispErr::Reason("unexpected form".to_string())),
+        RispExp::Lambda(_) => Err(RispErr::Reason("unexpected form".to_string())),
+        RispExp::Generator(_) => Err(RispErr::Reason("unexpected form".to_string())),
+        RispExp::Resource(_) => Err(RispErr::Reason("unexpected form".to_string())),
+        RispExp::Weak(_) => Err(RispErr::Reason("unexpected form".to_string())),
+        RispExp::Tuple(_) => Err(RispErr::Reason("unexpected form".to_string())),
+        RispExp::SortedMap(_) => Err(RispErr::Reason("unexpected form".to_string())),
+        RispExp::SortedSet(_) => Err(RispErr::Reason("unexpected form".to_string())),
+        RispExp::Queue(_) => Err(RispErr::Reason("unexpected form".to_string())),
+        RispExp::Stack(_) => Err(RispErr::Reason("unexpected form".to_string())),
+        RispExp::Zipper(_) => Err(RispErr::Reason("unexpected form".to_string())),
+        RispExp::Instrumented(_) => Err(RispErr::Reason("unexpected form".to_string())),
+        RispExp::Object(_) => Err(RispErr::Reason("unexpected form".to_string())),
+        RispExp::Generic(_) => Err(RispErr::Reason("unexpected form".to_string())),
+        RispExp::Env(_) => Err(RispErr::Reason("unexpected form".to_string())),
+        RispExp::Map(map) => {
+            let mut pairs = vec![];
+            for (k, v) in map.iter() {
+                pairs.push(eval(&k.exp(), env)?);
+                pairs.push(eval(v, env)?);
+            }
+            hashmap::from_pairs(&pairs)
+        },
+        // a fresh vector each time, so `vec-set!` can't change the literal
+        RispExp::Vector(xs) => {
+            let xs = xs.borrow().clone();
+            Ok(RispExp::Vector(Rc::new(RefCell::new(eval_forms(&xs, env)?))))
+        },
+        RispExp::Tagged(tagged) => tagged::eval_tagged(tagged, env),
+    }
+}
+
+// Every body gets its own frame of `defer`ed forms, which run in reverse order
+// once the body is done, whether it finished or failed. The body's own error
+// wins over any error from a deferred form.
+fn eval_body(body_exps: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let (last, init) = body_exps.split_last()
+        .ok_or(RispErr::Reason("expected body form".to_string()))?;
+
+    DEFERRED.with(|deferred| deferred.borrow_mut().push(vec![]));
+    let res = init
+        .iter()
+        .try_for_each(|exp| eval(exp, env).map(|_| ()))
+        .and_then(|_| eval(last, env));
+
+    let deferred_exps = DEFERRED.with(|deferred| deferred.borrow_mut().pop())
+        .expect("defer frame disappeared");
+    let mut deferred_res = Ok(());
+    for exp in deferred_exps.iter().rev() {
+        let exp_res = eval(exp, env);
+        if deferred_res.is_ok() {
+            deferred_res = exp_res.map(|_| ());
+        }
+    }
+
+    let value = res?;
+    deferred_res?;
+
+    Ok(value)
+}
+
+fn eval_lambda(lambda: &RispLambda, arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let args = eval_args(arg_forms, env)?;
+    apply_lambda(lambda, args, env)
+}
+
+// the body sees the params, then what was in scope where the fn was made,
+// then the globals, not the bindings of whoever called it
+fn apply_lambda(lambda: &RispLambda, args: Vec<RispExp>, env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let globals = global_env(env);
+    if lambda.closure.is_empty() {
+        let new_env = &mut env_for_lambdas(lambda.params_exp.clone(), args, globals)?;
+        return eval_body(&lambda.body_exps, new_env)
+    }
+
+    envs::within(&lambda.closure, Some(globals), |closure| {
+        let new_env = &mut env_for_lambdas(lambda.params_exp.clone(), args, closure)?;
+        eval_body(&lambda.body_exps, new_env)
+    })
+}
+
+// calls an already-evaluated function value with already-evaluated arguments
+fn call_exp(f: &RispExp, args: Vec<RispExp>, env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    match f {
+        RispExp::Func(f) => call_builtin(*f, &args),
+        RispExp::EnvFunc(f) => f(&args, env),
+        RispExp::Lambda(lambda) => apply_lambda(lambda, args, env),
+        RispExp::Instrumented(inst) => spec::call_instrumented(inst, args, env),
+        RispExp::Generic(generic) => objects::call_generic(generic, args, env),
+        _ => Err(
+            RispErr::Reason("first form must be a function".to_string())
+        ),
+    }
+}
+
+fn call_builtin(f: fn(&[RispExp]) -> Result<RispExp, RispErr>, args: &[RispExp]) -> Result<RispExp, RispErr> {
+    stats::builtin(|| audit::call(f, args))
+}
+
+fn parse_eval(expr: String, env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    stats::timed(|| {
+        let (parsed_exp, _) = parse(&tokenize(expr))?;
+        eval(&parsed_exp, env)
+    })
+}
+
+// every top-level form in the source text, in order
+fn parse_forms(expr: String) -> Result<Vec<RispExp>, RispErr> {
+    Ok(parse_forms_spanned(&expr)?.into_iter().map(|(form, _)| form).collect())
+}
+
+// the top-level forms with where each starts in the source, so errors while
+// evaluating one can point back at it
+fn parse_forms_spanned(expr: &str) -> Result<Vec<(RispExp, Pos)>, RispErr> {
+    if let Some(forms) = parse_cache::get(expr) {
+        return Ok(forms.as_ref().clone())
+    }
+
+    let forms = Rc::new(parse_uncached(expr)?);
+    parse_cache::insert(expr, forms.clone());
+    Ok(forms.as_ref().clone())
+}
+
+// the source text of each top-level form, as it's written, for sending or
+// evaluating them one at a time. The reader finds where they end, so strings,
+// characters, comments and every kind of brackets are split the same way
+// they're read
+pub fn split_forms(src: &str) -> Result<Vec<String>, RispErr> {
+    // `#{` needs its macro even on a thread without an environment
+    reader::add_default_macros();
+    let (tokens, positions): (Vec<String>, Vec<Pos>) = tokenize_spanned(src).into_iter().unzip();
+    let line_starts: Vec<usize> = std::iter::once(0).chain(src.match_indices('\n').map(|(at, _)| at + 1)).collect();
+    let offset = |pos: Pos| {
+        let start = line_starts[pos.line - 1];
+        let line = &src[start..];
+        start + line.char_indices().nth(pos.col - 1).map_or(line.len(), |(at, _)| at)
+    };
+
+    let mut forms = vec![];
+    let mut rest = &tokens[..];
+    while !rest.is_empty() {
+        let first = tokens.len() - rest.len();
+        let (_, new_rest) = parse(rest)?;
+        let last = tokens.len() - new_rest.len() - 1;
+        // tokens are as long as they're written
+        let end = (offset(positions[last]) + tokens[last].len()).min(src.len());
+        forms.push(src[offset(positions[first])..end].to_string());
+        rest = new_rest;
+    }
+
+    Ok(forms)
+}
+
+fn parse_uncached(expr: &str) -> Result<Vec<(RispExp, Pos)>, RispErr> {
+    let (tokens, positions): (Vec<String>, Vec<Pos>) = tokenize_spanned(expr).into_iter().unzip();
+    let mut forms = vec![];
+    let mut rest = &tokens[..];
+
+    while !rest.is_empty() {
+        let pos = positions[tokens.len() - rest.len()];
+        let (form, new_rest) = parse(rest)?;
+        forms.push((form, pos));
+        rest = new_rest;
+    }
+
+    Ok(forms)
+}
+
+// the message in English, `err_message` translates it
+impl fmt::Display for RispErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RispErr::Reason(msg) => write!(f, "{}", msg),
+            RispErr::Args(e) => write!(f, "{}", e),
+            RispErr::GeneratorFull => write!(f, "a generator's `yield` escaped the `take` consuming it"),
+            RispErr::Restart { name, .. } => write!(f, "no restart named '{}' is active", name),
+        }
+    }
+}
+
+fn err_message(e: RispErr) -> String {
+    match e {
+        RispErr::Args(e) => i18n::translate_args(&e),
+        e => i18n::translate(e.to_string()),
+    }
+}
@@ -0,0 +1,190 @@
+// Taking lists apart and putting them together at runtime. Lists are vectors
+// underneath, so there are no dotted pairs: `cons` only puts a value in front
+// of another list. `car` and `cdr` are the same as `first` and `rest`, and
+// like them are an error on an empty list rather than nil.
+//
+// `map`, `filter`, `reduce` and `for-each` call a function on each element of
+// any sequence, lists, vectors, generators or the entries of a map, and give
+// back a list, `apply` calls one with a sequence as its arguments and `take`
+// gets the first values of one, running no more of a generator than it needs
+// to. They're
+// builtins given the environment they're called from, for calling lambdas in,
+// so they can be passed around like any other function.
+
+use std::collections::HashMap;
+
+use super::{args, call_exp, seq_values, stats, truthy, RispEnv, RispErr, RispExp};
+
+fn list(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    Ok(RispExp::List(args.to_vec().into()))
+}
+
+// `(cons x xs)`
+fn cons(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let x = args::get("cons", args, 0, "a value")?;
+    let rest = args::list("cons", args, 1)?;
+    stats::reserve_values(rest.len() + 1)?;
+    let mut xs = vec![x.clone()];
+    xs.extend_from_slice(rest);
+
+    Ok(RispExp::List(xs.into()))
+}
+
+fn first(name: &str, args: &[RispExp]) -> Result<RispExp, RispErr> {
+    match args::list(name, args, 0)?.first() {
+        Some(x) => Ok(x.clone()),
+        None => Err(args::wrong(name, 0, "a list that isn't empty", &args[0])),
+    }
+}
+
+fn rest(name: &str, args: &[RispExp]) -> Result<RispExp, RispErr> {
+    match args::list(name, args, 0)?.split_first() {
+        Some((_, rest)) => Ok(RispExp::List(rest.to_vec().into())),
+        None => Err(args::wrong(name, 0, "a list that isn't empty", &args[0])),
+    }
+}
+
+fn len(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    Ok(RispExp::Int(args::list("len", args, 0)?.len() as i64))
+}
+
+// `(nth xs idx)`, counting from 0
+fn nth(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let xs = args::list("nth", args, 0)?;
+    match args::index("nth", args, 1)? {
+        idx if idx < xs.len() => Ok(xs[idx].clone()),
+        idx => Err(RispErr::Reason(format!("`nth` index {} is out of range for a list of length {}", idx, xs.len()))),
+    }
+}
+
+// `(append xs...)`
+fn append(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let lists = (0..args.len()).map(|idx| args::list("append", args, idx)).collect::<Result<Vec<_>, RispErr>>()?;
+    stats::reserve_values(lists.iter().map(|xs| xs.len()).sum())?;
+    let mut all = vec![];
+    for xs in lists {
+        all.extend_from_slice(xs);
+    }
+
+    Ok(RispExp::List(all.into()))
+}
+
+fn reverse(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let xs = args::list("reverse", args, 0)?;
+    stats::reserve_values(xs.len())?;
+    Ok(RispExp::List(xs.iter().rev().cloned().collect()))
+}
+
+fn fn_and_seqs<'a>(name: &str, args: &'a [RispExp], env: &mut RispEnv) -> Result<(&'a RispExp, Vec<Vec<RispExp>>), RispErr> {
+    match args {
+        [] => Err(args::missing(name, 0, "a fn")),
+        [_] => Err(args::missing(name, 1, "a sequence")),
+        [f, seqs @ ..] => {
+            let seqs = seqs.iter().map(|seq| seq_values(seq, None, env)).collect::<Result<_, _>>()?;
+            Ok((f, seqs))
+        },
+    }
+}
+
+// `(map f xs ys...)`, calling `f` with an element of each, up to the shortest
+fn map(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let (f, seqs) = fn_and_seqs("map", args, env)?;
+    let len = seqs.iter().map(Vec::len).min().unwrap_or(0);
+
+    let mut results = vec![];
+    for idx in 0..len {
+        results.push(call_exp(f, seqs.iter().map(|xs| xs[idx].clone()).collect(), env)?);
+    }
+
+    Ok(RispExp::List(results.into()))
+}
+
+// `(filter pred xs)`
+fn filter(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let (pred, seqs) = match fn_and_seqs("filter", args, env)? {
+        (pred, seqs) if seqs.len() == 1 => (pred, seqs),
+        _ => return Err(RispErr::Reason("`filter` expected a predicate and one sequence".to_string())),
+    };
+
+    let mut kept = vec![];
+    for x in seqs.into_iter().flatten() {
+        let res = call_exp(pred, vec![x.clone()], env)?;
+        match truthy(&res) {
+            Some(true) => kept.push(x),
+            Some(false) => (),
+            None => return Err(RispErr::Reason(format!("`filter` expected the predicate to give a bool, got '{}'", res))),
+        }
+    }
+
+    Ok(RispExp::List(kept.into()))
+}
+
+// `(reduce f [init] xs)`, starting from the first element without `init`
+fn reduce(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let (f, init, seq) = match args {
+        [f, seq] => (f, None, seq),
+        [f, init, seq] => (f, Some(init.clone()), seq),
+        _ => return Err(RispErr::Reason("`reduce` expected a fn, maybe an initial value, and a sequence".to_string())),
+    };
+
+    let mut xs = seq_values(seq, None, env)?.into_iter();
+    let mut acc = match init.or_else(|| xs.next()) {
+        Some(acc) => acc,
+        None => return Err(RispErr::Reason("`reduce` of an empty sequence needs an initial value".to_string())),
+    };
+    for x in xs {
+        acc = call_exp(f, vec![acc, x], env)?;
+    }
+
+    Ok(acc)
+}
+
+// `(for-each f xs ys...)`, for the side effects, giving nil
+fn for_each(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let (f, seqs) = fn_and_seqs("for-each", args, env)?;
+    let len = seqs.iter().map(Vec::len).min().unwrap_or(0);
+    for idx in 0..len {
+        call_exp(f, seqs.iter().map(|xs| xs[idx].clone()).collect(), env)?;
+    }
+
+    Ok(RispExp::List(vec![].into()))
+}
+
+// `(apply f x... xs)`, calling `f` with the `x`s and then each element of `xs`
+fn apply(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    args::at_least("apply", args, 2)?;
+    let f = &args[0];
+    let last = args.len() - 1;
+    let mut call_args = args[1..last].to_vec();
+    call_args.extend(seq_values(&args[last], None, env)?);
+
+    call_exp(f, call_args, env)
+}
+
+// `(take n seq)`
+fn take(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    args::exactly("take", args, 2)?;
+    let n = args::index("take", args, 0)?;
+    let seq = args::get("take", args, 1, "a sequence")?;
+
+    Ok(RispExp::List(seq_values(seq, Some(n), env)?.into()))
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert("list".to_string(), RispExp::Func(list));
+    data.insert("cons".to_string(), RispExp::Func(cons));
+    data.insert("car".to_string(), RispExp::Func(|args| first("car", args)));
+    data.insert("first".to_string(), RispExp::Func(|args| first("first", args)));
+    data.insert("cdr".to_string(), RispExp::Func(|args| rest("cdr", args)));
+    data.insert("rest".to_string(), RispExp::Func(|args| rest("rest", args)));
+    data.insert("len".to_string(), RispExp::Func(len));
+    data.insert("nth".to_string(), RispExp::Func(nth));
+    data.insert("append".to_string(), RispExp::Func(append));
+    data.insert("reverse".to_string(), RispExp::Func(reverse));
+    data.insert("map".to_string(), RispExp::EnvFunc(map));
+    data.insert("filter".to_string(), RispExp::EnvFunc(filter));
+    data.insert("reduce".to_string(), RispExp::EnvFunc(reduce));
+    data.insert("for-each".to_string(), RispExp::EnvFunc(for_each));
+    data.insert("apply".to_string(), RispExp::EnvFunc(apply));
+    data.insert("take".to_string(), RispExp::EnvFunc(take));
+}
@@ -0,0 +1,85 @@
+// User-defined macros. `(defmacro name params body...)` registers a function
+// that's called with the unevaluated argument forms of every `(name ...)`
+// call, and the form it returns is evaluated in place of the call:
+//
+//     (defmacro unless (test then else) `(if ,test ,else ,then))
+//
+// `(macroexpand-1 'form)` shows what a macro call turns into and
+// `(macroexpand 'form)` keeps going until the form isn't a macro call
+// anymore. Like reader functions, macros are shared by every environment on
+// the thread, and the built-in special forms can't be redefined.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use super::{call_exp, eval_lambda_args, RispEnv, RispErr, RispExp};
+
+thread_local! {
+    static MACROS: RefCell<HashMap<String, RispExp>> = RefCell::new(HashMap::new());
+}
+
+// the macros defined so far, for `restore` to put back
+pub fn saved() -> HashMap<String, RispExp> {
+    MACROS.with(|macros| macros.borrow().clone())
+}
+
+pub fn restore(saved: &HashMap<String, RispExp>) {
+    MACROS.with(|macros| macros.borrow_mut().clone_from(saved));
+}
+
+// `(defmacro name params body...)`
+pub fn eval_defmacro_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let name = match arg_forms.first() {
+        Some(RispExp::Symbol(name)) => Ok(name.clone()),
+        _ => Err(RispErr::Reason("expected (defmacro name params body...)".to_string())),
+    }?;
+
+    let transformer = eval_lambda_args(&arg_forms[1..], env)?;
+    MACROS.with(|macros| macros.borrow_mut().insert(name, transformer));
+
+    Ok(arg_forms[0].clone())
+}
+
+// what `form` expands to, if it's a call to a macro
+pub fn expand_1(form: &RispExp, env: &mut RispEnv) -> Result<Option<RispExp>, RispErr> {
+    let (name, arg_forms) = match form {
+        RispExp::List(list) => match list.split_first() {
+            Some((RispExp::Symbol(name), arg_forms)) => (name, arg_forms),
+            _ => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+
+    let transformer = MACROS.with(|macros| macros.borrow().get(name).cloned());
+    match transformer {
+        Some(transformer) => Ok(Some(call_exp(&transformer, arg_forms.to_vec(), env)?)),
+        None => Ok(None),
+    }
+}
+
+pub fn expand(form: &RispExp, env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let mut form = form.clone();
+    while let Some(expansion) = expand_1(&form, env)? {
+        form = expansion;
+    }
+
+    Ok(form)
+}
+
+fn form_arg(args: &[RispExp]) -> Result<&RispExp, RispErr> {
+    match args {
+        [form] => Ok(form),
+        _ => Err(RispErr::Reason("expected one form to expand".to_string())),
+    }
+}
+
+// `(macroexpand-1 form)`
+pub fn macroexpand_1(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let form = form_arg(args)?;
+    Ok(expand_1(form, env)?.unwrap_or_else(|| form.clone()))
+}
+
+// `(macroexpand form)`
+pub fn macroexpand(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    expand(form_arg(args)?, env)
+}
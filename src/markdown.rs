@@ -0,0 +1,165 @@
+// `(md->html text)` for reports and docs written in risp, behind the
+// `markdown` feature. It covers the commonly used part of Markdown: ATX
+// headings, paragraphs, flat bullet and numbered lists, fenced code blocks,
+// block quotes and rules, and inline code, emphasis, links and images. Any
+// HTML in the text is escaped rather than passed through.
+
+use super::template::escape_html;
+use super::{args, RispErr, RispExp};
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    match (level, &line[level..]) {
+        (1..=6, "") => Some((level, "")),
+        (1..=6, rest) if rest.starts_with(' ') => Some((level, rest.trim().trim_end_matches('#').trim_end())),
+        _ => None,
+    }
+}
+
+fn is_rule(line: &str) -> bool {
+    let line: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    line.len() >= 3 && (line.chars().all(|c| c == '-') || line.chars().all(|c| c == '*') || line.chars().all(|c| c == '_'))
+}
+
+fn bullet(line: &str) -> Option<&str> {
+    ["- ", "* ", "+ "].iter().find_map(|marker| line.strip_prefix(marker))
+}
+
+fn numbered(line: &str) -> Option<&str> {
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    match digits {
+        0 => None,
+        _ => line[digits..].strip_prefix(". "),
+    }
+}
+
+// text up to the next `close`, with the index just past it
+fn until(chars: &[char], from: usize, close: &str) -> Option<(String, usize)> {
+    let close: Vec<char> = close.chars().collect();
+    (from..chars.len())
+        .find(|idx| chars[*idx..].starts_with(&close))
+        .filter(|idx| *idx > from)
+        .map(|idx| (chars[from..idx].iter().collect(), idx + close.len()))
+}
+
+// `[text](url)` from `from`, which is just past the `[`
+fn link(chars: &[char], from: usize) -> Option<(String, String, usize)> {
+    let (text, after) = until(chars, from, "](")?;
+    let (url, end) = until(chars, after, ")")?;
+    Some((text, url, end))
+}
+
+fn inline(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::new();
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        let rest = &chars[idx..];
+        let span = match rest {
+            ['`', ..] => until(&chars, idx + 1, "`")
+                .map(|(code, end)| (format!("<code>{}</code>", escape_html(&code)), end)),
+            ['*', '*', ..] | ['_', '_', ..] => until(&chars, idx + 2, if rest[0] == '*' { "**" } else { "__" })
+                .map(|(strong, end)| (format!("<strong>{}</strong>", inline(&strong)), end)),
+            ['*', ..] | ['_', ..] => until(&chars, idx + 1, if rest[0] == '*' { "*" } else { "_" })
+                .map(|(em, end)| (format!("<em>{}</em>", inline(&em)), end)),
+            ['!', '[', ..] => link(&chars, idx + 2).map(|(alt, src, end)| (
+                format!("<img src=\"{}\" alt=\"{}\">", escape_html(&src), escape_html(&alt)),
+                end,
+            )),
+            ['[', ..] => link(&chars, idx + 1).map(|(text, url, end)| (
+                format!("<a href=\"{}\">{}</a>", escape_html(&url), inline(&text)),
+                end,
+            )),
+            ['\\', c, ..] if c.is_ascii_punctuation() => Some((escape_html(&c.to_string()), idx + 2)),
+            _ => None,
+        };
+
+        match span {
+            Some((html, end)) => {
+                out.push_str(&html);
+                idx = end;
+            },
+            None => {
+                out.push_str(&escape_html(&chars[idx].to_string()));
+                idx += 1;
+            },
+        }
+    }
+
+    out
+}
+
+fn render(lines: &[&str]) -> String {
+    let mut out = String::new();
+    let mut idx = 0;
+
+    while idx < lines.len() {
+        let line = lines[idx].trim_end();
+        let trimmed = line.trim_start();
+
+        if trimmed.is_empty() {
+            idx += 1;
+        } else if let Some(fence) = trimmed.strip_prefix("```") {
+            let lang = fence.trim();
+            let end = (idx + 1..lines.len())
+                .find(|end| lines[*end].trim_start().starts_with("```"))
+                .unwrap_or(lines.len());
+            let code = lines[idx + 1..end].join("\n");
+            match lang {
+                "" => out.push_str("<pre><code>"),
+                _ => out.push_str(&format!("<pre><code class=\"language-{}\">", escape_html(lang))),
+            }
+            out.push_str(&escape_html(&code));
+            out.push_str("</code></pre>\n");
+            idx = end + 1;
+        } else if let Some((level, text)) = heading(trimmed) {
+            out.push_str(&format!("<h{}>{}</h{}>\n", level, inline(text), level));
+            idx += 1;
+        } else if is_rule(trimmed) {
+            out.push_str("<hr>\n");
+            idx += 1;
+        } else if trimmed.starts_with('>') {
+            let quoted: Vec<&str> = lines[idx..]
+                .iter()
+                .map(|line| line.trim_start())
+                .take_while(|line| line.starts_with('>'))
+                .map(|line| line[1..].strip_prefix(' ').unwrap_or(&line[1..]))
+                .collect();
+            idx += quoted.len();
+            out.push_str(&format!("<blockquote>\n{}</blockquote>\n", render(&quoted)));
+        } else if bullet(trimmed).is_some() || numbered(trimmed).is_some() {
+            let ordered = numbered(trimmed).is_some();
+            let item: fn(&str) -> Option<&str> = if ordered { numbered } else { bullet };
+            let tag = if ordered { "ol" } else { "ul" };
+
+            out.push_str(&format!("<{}>\n", tag));
+            while let Some(text) = lines.get(idx).and_then(|line| item(line.trim_start())) {
+                out.push_str(&format!("<li>{}</li>\n", inline(text.trim())));
+                idx += 1;
+            }
+            out.push_str(&format!("</{}>\n", tag));
+        } else {
+            // a paragraph runs until a blank line or the start of another block
+            let mut text = vec![trimmed];
+            idx += 1;
+            while let Some(line) = lines.get(idx).map(|line| line.trim()) {
+                let starts_block = line.is_empty() || line.starts_with("```") || line.starts_with('>')
+                    || heading(line).is_some() || bullet(line).is_some() || numbered(line).is_some();
+                if starts_block {
+                    break
+                }
+                text.push(line);
+                idx += 1;
+            }
+            out.push_str(&format!("<p>{}</p>\n", inline(&text.join("\n"))));
+        }
+    }
+
+    out
+}
+
+pub fn md_to_html(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let text = args::string("md->html", args, 0)?;
+    Ok(RispExp::Str(render(&text.lines().collect::<Vec<&str>>())))
+}
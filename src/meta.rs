@@ -0,0 +1,132 @@
+// Metadata, a map carried alongside a value, like where it was read from.
+// `(with-meta x {:source "config"})` is a copy of `x` with it, `(meta x)` reads
+// it back, or nil. It's not part of the value, so comparing values, like
+// `sexp-diff` does, never sees it, and `assoc`, `dissoc`, `conj` and `disj`
+// keep the metadata of the collection they change.
+//
+// A list holds its metadata itself, so every copy of it has it. Vectors,
+// tuples, maps, sets and lambdas have it kept by their identity instead, and
+// it goes wherever that value does. Numbers, strings and the rest can't carry
+// any.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+use super::{args, RispErr, RispExp, RispLambda};
+
+struct Entry {
+    // whether the value is still around, holding on to its allocation so the
+    // address isn't given to a new one while it's in here
+    alive: Box<dyn Fn() -> bool>,
+    meta: RispExp,
+}
+
+thread_local! {
+    // by the address of the value it's attached to
+    static METADATA: RefCell<HashMap<usize, Entry>> = RefCell::new(HashMap::new());
+}
+
+fn entry<T: 'static>(rc: &Rc<T>) -> (usize, Box<dyn Fn() -> bool>) {
+    let weak: Weak<T> = Rc::downgrade(rc);
+    (Rc::as_ptr(rc) as *const () as usize, Box::new(move || weak.strong_count() > 0))
+}
+
+// the address of the value `exp` is, and whether it's still around
+pub fn identity(exp: &RispExp) -> Option<(usize, Box<dyn Fn() -> bool>)> {
+    match exp {
+        RispExp::Vector(xs) => Some(entry(xs)),
+        RispExp::Tuple(xs) => Some(entry(xs)),
+        RispExp::Map(map) => Some(entry(map)),
+        RispExp::SortedMap(map) => Some(entry(map)),
+        RispExp::SortedSet(set) => Some(entry(set)),
+        RispExp::Lambda(lambda) => Some(entry(&lambda.body_exps)),
+        _ => None,
+    }
+}
+
+// the same value under a new identity
+fn copy(exp: &RispExp) -> RispExp {
+    match exp {
+        RispExp::Vector(xs) => RispExp::Vector(Rc::new(RefCell::new(xs.borrow().clone()))),
+        RispExp::Tuple(xs) => RispExp::Tuple(Rc::new(xs.as_ref().clone())),
+        RispExp::Map(map) => RispExp::Map(Rc::new(map.as_ref().clone())),
+        RispExp::SortedMap(map) => RispExp::SortedMap(Rc::new(map.as_ref().clone())),
+        RispExp::SortedSet(set) => RispExp::SortedSet(Rc::new(set.as_ref().clone())),
+        RispExp::Lambda(lambda) => RispExp::Lambda(RispLambda {
+            body_exps: Rc::new(lambda.body_exps.as_ref().clone()),
+            ..lambda.clone()
+        }),
+        _ => exp.clone(),
+    }
+}
+
+fn can_carry(exp: &RispExp) -> bool {
+    matches!(exp, RispExp::List(_)) || identity(exp).is_some()
+}
+
+pub fn get(exp: &RispExp) -> Option<RispExp> {
+    if let RispExp::List(list) = exp {
+        return list.meta.as_deref().cloned()
+    }
+
+    let (key, _) = identity(exp)?;
+    METADATA.with(|metadata| match metadata.borrow().get(&key) {
+        Some(entry) if (entry.alive)() => Some(entry.meta.clone()),
+        _ => None,
+    })
+}
+
+fn set(exp: &mut RispExp, meta: RispExp) {
+    if let RispExp::List(list) = exp {
+        list.meta = Some(Rc::new(meta));
+        return
+    }
+
+    if let Some((key, alive)) = identity(exp) {
+        METADATA.with(|metadata| {
+            let mut metadata = metadata.borrow_mut();
+            // forget the values that are gone before there's a need to grow
+            if metadata.len() == metadata.capacity() {
+                metadata.retain(|_, entry| (entry.alive)());
+            }
+            metadata.insert(key, Entry { alive, meta });
+        });
+    }
+}
+
+// `to` with the metadata of `from`, for a changed copy of a collection
+pub fn carry(from: &RispExp, mut to: RispExp) -> RispExp {
+    if let Some(meta) = get(from) {
+        set(&mut to, meta);
+    }
+    to
+}
+
+// `(with-meta x map)`
+fn with_meta(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let (exp, meta) = match args {
+        [exp, meta @ (RispExp::Map(_) | RispExp::SortedMap(_))] => (exp, meta),
+        [_, meta] => return Err(RispErr::Reason(format!("expected the metadata to be a map, got '{}'", meta))),
+        _ => return Err(RispErr::Reason("expected a value and a map of metadata".to_string())),
+    };
+    if !can_carry(exp) {
+        return Err(RispErr::Reason(format!("'{}' can not carry metadata", exp)))
+    }
+
+    let mut copy = copy(exp);
+    set(&mut copy, meta.clone());
+
+    Ok(copy)
+}
+
+// `(meta x)`, nil without any
+fn meta(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let exp = args::get("meta", args, 0, "a value")?;
+    Ok(get(exp).unwrap_or(RispExp::List(vec![].into())))
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert("with-meta".to_string(), RispExp::Func(with_meta));
+    data.insert("meta".to_string(), RispExp::Func(meta));
+}
@@ -0,0 +1,89 @@
+// `risp minify path` prints a file with its comments and all the whitespace
+// the reader doesn't need taken out, one top level form per line, and without
+// the definitions nothing refers to. Only definitions that can't do anything
+// when they're evaluated are taken out: `defn`s and `defmacro`s, and `def`s of
+// a lambda or a literal. Removing one can leave others unused, so it repeats
+// until none are.
+
+use super::graph;
+use super::{parse, tokenize, RispErr, RispExp};
+
+struct Form {
+    tokens: Vec<String>,
+    exp: RispExp,
+}
+
+fn is_delimiter(token: &str) -> bool {
+    matches!(token, "(" | ")" | "{" | "}" | "#{" | "[" | "]") || token.starts_with('"')
+}
+
+fn is_quote(token: &str) -> bool {
+    matches!(token, "'" | "`" | "," | ",@")
+}
+
+// atoms need a space between them, everything else is delimited already.
+// Quotes and unquotes stick to what they quote
+fn join(tokens: &[String]) -> String {
+    let mut out = String::new();
+    for (idx, token) in tokens.iter().enumerate() {
+        let after_atom = idx > 0 && !is_delimiter(&tokens[idx - 1]) && !is_quote(&tokens[idx - 1]);
+        if after_atom && (!is_delimiter(token) || token == "#{" || is_quote(token)) {
+            out.push(' ');
+        }
+        out.push_str(token);
+    }
+
+    out
+}
+
+fn removable(form: &RispExp) -> bool {
+    let list = match form {
+        RispExp::List(list) => list,
+        _ => return false,
+    };
+
+    match &list[..] {
+        [RispExp::Symbol(head), RispExp::Symbol(_), ..] if matches!(head.as_str(), "defn" | "defmacro") => true,
+        [RispExp::Symbol(head), RispExp::Symbol(_), value] if matches!(head.as_str(), "def" | "define" | "defonce") => match value {
+            RispExp::Number(_) | RispExp::Int(_) | RispExp::Str(_) | RispExp::Char(_) | RispExp::Bool(_) => true,
+            RispExp::Keyword(_) => true,
+            RispExp::List(lambda) => matches!(lambda.first(), Some(RispExp::Symbol(head)) if head == "fn"),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn name(form: &RispExp) -> Option<&str> {
+    match form {
+        RispExp::List(list) => match list.get(1) {
+            Some(RispExp::Symbol(name)) => Some(name),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+pub fn minify(src: String) -> Result<String, RispErr> {
+    let tokens = tokenize(src);
+    let mut forms = vec![];
+    let mut rest = &tokens[..];
+    while !rest.is_empty() {
+        let (exp, next) = parse(rest)?;
+        forms.push(Form { tokens: rest[..rest.len() - next.len()].to_vec(), exp });
+        rest = next;
+    }
+
+    loop {
+        let exps: Vec<RispExp> = forms.iter().map(|form| form.exp.clone()).collect();
+        let unused = graph::graph(&exps).unused;
+        let before = forms.len();
+        forms.retain(|form| !(removable(&form.exp) && name(&form.exp).is_some_and(|name| unused.iter().any(|u| u == name))));
+        if forms.len() == before {
+            break
+        }
+    }
+
+    let lines: Vec<String> = forms.iter().map(|form| join(&form.tokens)).collect();
+    Ok(lines.join("\n"))
+}
@@ -0,0 +1,89 @@
+// Namespaced names, so what modules define doesn't collide. A package loaded
+// with `(require 'strings)` is read and evaluated in the `strings` namespace,
+// and everything it defines is also bound as `strings/name`, which still means
+// the package's own even after something else is bound to plain `name`.
+//
+// `::kw` reads as a keyword qualified with the namespace it's read in, like
+// `:strings/kw` in the package or `:user/kw` anywhere else, for map keys that
+// can't collide with another module's. `(name x)` and `(namespace x)` take the
+// parts of a symbol or keyword apart.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use super::{parse_cache, RispErr, RispExp};
+
+pub const DEFAULT: &str = "user";
+
+thread_local! {
+    static CURRENT: RefCell<String> = RefCell::new(DEFAULT.to_string());
+}
+
+pub fn current() -> String {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+pub fn restore(ns: &str) {
+    set(ns.to_string());
+}
+
+fn set(ns: String) {
+    // `::kw` reads differently in another namespace
+    if CURRENT.with(|current| current.replace(ns.clone())) != ns {
+        parse_cache::clear();
+    }
+}
+
+// runs `f` in the namespace `ns`, back in the current one after
+pub fn within<T>(ns: &str, f: impl FnOnce() -> T) -> T {
+    let outer = current();
+    set(ns.to_string());
+    let res = f();
+    set(outer);
+    res
+}
+
+// `::kw`, without its colons, as the keyword in the current namespace
+pub fn qualify(name: &str) -> RispExp {
+    RispExp::Keyword(format!(":{}/{}", current(), name))
+}
+
+// the namespace and name of `a/b`, a lone `/` being a name of its own
+fn split(s: &str) -> (Option<&str>, &str) {
+    match s.find('/') {
+        Some(at) if at > 0 && at + 1 < s.len() => (Some(&s[..at]), &s[at + 1..]),
+        _ => (None, s),
+    }
+}
+
+fn name_arg(args: &[RispExp]) -> Result<(Option<&str>, &str), RispErr> {
+    match args.first() {
+        Some(RispExp::Symbol(s)) => Ok(split(s)),
+        Some(RispExp::Keyword(k)) => Ok(split(&k[1..])),
+        Some(exp) => Err(RispErr::Reason(format!("expected a symbol or keyword, got '{}'", exp))),
+        None => Err(RispErr::Reason("expected a symbol or keyword".to_string())),
+    }
+}
+
+// `(name :a/b)` is "b"
+fn name(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    Ok(RispExp::Str(name_arg(args)?.1.to_string()))
+}
+
+// `(namespace :a/b)` is "a", nil without one
+fn namespace(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    match name_arg(args)?.0 {
+        Some(ns) => Ok(RispExp::Str(ns.to_string())),
+        None => Ok(RispExp::List(vec![].into())),
+    }
+}
+
+fn current_ns(_args: &[RispExp]) -> Result<RispExp, RispErr> {
+    Ok(RispExp::Symbol(current()))
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert("name".to_string(), RispExp::Func(name));
+    data.insert("namespace".to_string(), RispExp::Func(namespace));
+    data.insert("current-ns".to_string(), RispExp::Func(current_ns));
+}
@@ -0,0 +1,176 @@
+// The numeric tower behind `+ - * /` and the comparisons. Ints stay ints while
+// the result fits in one, and a float anywhere makes the result a float. Ints
+// that don't divide evenly give a float.
+//
+// With the `bignum` feature there's no limit on integers: ints that would
+// overflow grow into big ones, and dividing them gives an exact rational, so
+// `(/ 1 3)` is `1/3` and `(+ (/ 1 3) (/ 2 3))` is `1`. Literals like `1/3`
+// read as rationals. A result that fits in an int is always an int again.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+#[cfg(feature = "bignum")]
+use std::rc::Rc;
+
+#[cfg(feature = "bignum")]
+use super::bignum::Rational;
+use super::{args, as_float, parse_single_float, RispErr, RispExp};
+
+#[derive(Clone, Copy)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Op {
+    fn name(self) -> &'static str {
+        match self {
+            Op::Add => "+",
+            Op::Sub => "-",
+            Op::Mul => "*",
+            Op::Div => "/",
+        }
+    }
+}
+
+fn int_op(op: Op, a: i64, b: i64) -> Option<i64> {
+    match op {
+        Op::Add => a.checked_add(b),
+        Op::Sub => a.checked_sub(b),
+        Op::Mul => a.checked_mul(b),
+        Op::Div => a.checked_rem(b).filter(|rem| *rem == 0).and_then(|_| a.checked_div(b)),
+    }
+}
+
+fn float_op(op: Op, a: f64, b: f64) -> f64 {
+    match op {
+        Op::Add => a + b,
+        Op::Sub => a - b,
+        Op::Mul => a * b,
+        Op::Div => a / b,
+    }
+}
+
+#[cfg(feature = "bignum")]
+fn exact(exp: &RispExp) -> Option<Rational> {
+    match exp {
+        RispExp::Int(n) => Some(Rational::from_i64(*n)),
+        RispExp::Exact(r) => Some((**r).clone()),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "bignum")]
+fn from_rational(r: Rational) -> RispExp {
+    match r.to_i64() {
+        Some(n) => RispExp::Int(n),
+        None => RispExp::Exact(Rc::new(r)),
+    }
+}
+
+// exact numbers the ints couldn't hold
+#[cfg(feature = "bignum")]
+fn exact_op(op: Op, a: &RispExp, b: &RispExp) -> Option<Result<RispExp, RispErr>> {
+    let (a, b) = (exact(a)?, exact(b)?);
+    let r = match op {
+        Op::Add => a.add(&b),
+        Op::Sub => a.sub(&b),
+        Op::Mul => a.mul(&b),
+        Op::Div => a.div(&b)?,
+    };
+
+    Some(Ok(from_rational(r)))
+}
+
+#[cfg(not(feature = "bignum"))]
+fn exact_op(op: Op, a: &RispExp, b: &RispExp) -> Option<Result<RispExp, RispErr>> {
+    match (op, a, b) {
+        (Op::Div, _, _) => None,
+        (_, RispExp::Int(_), RispExp::Int(_)) => Some(Err(RispErr::Reason(
+            "integer overflow, the `bignum` feature has integers without a limit".to_string(),
+        ))),
+        _ => None,
+    }
+}
+
+fn is_exact_zero(exp: &RispExp) -> bool {
+    match exp {
+        RispExp::Int(n) => *n == 0,
+        _ => false,
+    }
+}
+
+fn apply(op: Op, a: &RispExp, b: &RispExp) -> Result<RispExp, RispErr> {
+    if let Op::Div = op {
+        if is_exact_zero(b) && !matches!(a, RispExp::Number(_)) {
+            return Err(RispErr::Reason("division by zero".to_string()))
+        }
+    }
+    if let (RispExp::Int(a), RispExp::Int(b)) = (a, b) {
+        if let Some(n) = int_op(op, *a, *b) {
+            return Ok(RispExp::Int(n))
+        }
+    }
+    if let Some(res) = exact_op(op, a, b) {
+        return res
+    }
+
+    Ok(RispExp::Number(float_op(op, parse_single_float(a)?, parse_single_float(b)?)))
+}
+
+// `(+ x...)` and `(* x...)`
+fn fold(op: Op, identity: i64, xs: &[RispExp]) -> Result<RispExp, RispErr> {
+    for idx in 0..xs.len() {
+        args::number(op.name(), xs, idx)?;
+    }
+    xs.iter().try_fold(RispExp::Int(identity), |acc, x| apply(op, &acc, x))
+}
+
+// `(- x y...)` and `(/ x y...)`, the first number on its own without the rest
+fn fold_rest(op: Op, xs: &[RispExp]) -> Result<RispExp, RispErr> {
+    let first = args::number(op.name(), xs, 0)?;
+    for idx in 1..xs.len() {
+        args::number(op.name(), xs, idx)?;
+    }
+
+    xs[1..].iter().try_fold(first.clone(), |acc, x| apply(op, &acc, x))
+}
+
+#[cfg(feature = "bignum")]
+fn compare_exact(a: &RispExp, b: &RispExp) -> Option<Ordering> {
+    Some(exact(a)?.cmp(&exact(b)?))
+}
+
+#[cfg(not(feature = "bignum"))]
+fn compare_exact(_a: &RispExp, _b: &RispExp) -> Option<Ordering> {
+    None
+}
+
+// exact numbers compare exactly with each other, anything else as floats
+pub fn compare(a: &RispExp, b: &RispExp) -> Option<Ordering> {
+    match (a, b) {
+        (RispExp::Int(a), RispExp::Int(b)) => Some(a.cmp(b)),
+        _ => compare_exact(a, b).or_else(|| as_float(a)?.partial_cmp(&as_float(b)?)),
+    }
+}
+
+// an integer literal, or a rational one with the `bignum` feature
+pub fn parse(token: &str) -> Option<RispExp> {
+    if let Ok(n) = token.parse() {
+        return Some(RispExp::Int(n))
+    }
+
+    #[cfg(feature = "bignum")]
+    return Rational::parse(token).map(from_rational);
+    #[cfg(not(feature = "bignum"))]
+    None
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert("+".to_string(), RispExp::Func(|args| fold(Op::Add, 0, args)));
+    data.insert("*".to_string(), RispExp::Func(|args| fold(Op::Mul, 1, args)));
+    data.insert("-".to_string(), RispExp::Func(|args| fold_rest(Op::Sub, args)));
+    data.insert("/".to_string(), RispExp::Func(|args| fold_rest(Op::Div, args)));
+}
@@ -0,0 +1,278 @@
+// Classes and objects, for teaching object-oriented programming on top of the
+// Lisp core. A class has slots, each with an optional default, and at most one
+// parent, whose slots it has too:
+//
+//     (defclass animal () (name (sound "...")))
+//     (defclass dog (animal) (breed))
+//
+// `(make dog :name "Rex")` makes an object, and the class defines an accessor
+// for each of its own slots, so `(animal-name rex)` is `"Rex"`. A method is
+// defined for the class of its first parameter:
+//
+//     (defmethod speak ((self animal)) (animal-sound self))
+//     (defmethod speak ((self dog)) "woof")
+//
+// `speak` is then a generic function, calling the method of the object's own
+// class or else the nearest one it inherits from.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{args, call_exp, env_get, eval, eval_lambda_args, RispEnv, RispErr, RispExp};
+
+#[derive(Debug)]
+pub struct Class {
+    name: String,
+    parent: Option<Rc<Class>>,
+    // only the slots it adds, with their defaults
+    slots: Vec<(String, RispExp)>,
+}
+
+impl Class {
+    // the class itself, then its parent and so on
+    fn lineage(self: &Rc<Class>) -> Vec<Rc<Class>> {
+        let mut lineage = vec![self.clone()];
+        while let Some(parent) = lineage.last().and_then(|class| class.parent.clone()) {
+            lineage.push(parent);
+        }
+        lineage
+    }
+
+    // the inherited slots first
+    fn all_slots(self: &Rc<Class>) -> Vec<(String, RispExp)> {
+        let mut slots: Vec<(String, RispExp)> = vec![];
+        for class in self.lineage().iter().rev() {
+            for (name, default) in &class.slots {
+                match slots.iter_mut().find(|(slot, _)| slot == name) {
+                    Some(slot) => slot.1 = default.clone(),
+                    None => slots.push((name.clone(), default.clone())),
+                }
+            }
+        }
+        slots
+    }
+}
+
+#[derive(Debug)]
+pub struct Object {
+    class: Rc<Class>,
+    slots: RefCell<Vec<(String, RispExp)>>,
+}
+
+impl Object {
+    pub fn display(&self) -> String {
+        let slots: Vec<String> = self.slots.borrow().iter().map(|(name, value)| format!("{} {}", name, value)).collect();
+        match slots.is_empty() {
+            true => format!("#<{}>", self.class.name),
+            false => format!("#<{} {}>", self.class.name, slots.join(", ")),
+        }
+    }
+}
+
+// the methods of a generic function by the name of their class
+#[derive(Debug)]
+pub struct Generic {
+    name: String,
+    methods: RefCell<HashMap<String, RispExp>>,
+}
+
+thread_local! {
+    static CLASSES: RefCell<HashMap<String, Rc<Class>>> = RefCell::new(HashMap::new());
+}
+
+// the classes defined so far, for `restore` to put back
+pub fn saved() -> HashMap<String, Rc<Class>> {
+    CLASSES.with(|classes| classes.borrow().clone())
+}
+
+pub fn restore(saved: &HashMap<String, Rc<Class>>) {
+    CLASSES.with(|classes| classes.borrow_mut().clone_from(saved));
+}
+
+fn class_named(name: &str) -> Result<Rc<Class>, RispErr> {
+    CLASSES.with(|classes| classes.borrow().get(name).cloned())
+        .ok_or_else(|| RispErr::Reason(format!("no class named '{}'", name)))
+}
+
+// `(defclass name (parent) (slot (slot default)...))`
+pub fn eval_defclass_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let (name, parents, slot_forms) = match arg_forms {
+        [RispExp::Symbol(name), RispExp::List(parents), RispExp::List(slots)] => (name, parents, slots),
+        _ => return Err(RispErr::Reason("expected (defclass name (parent) (slots...))".to_string())),
+    };
+
+    let parent = match &parents[..] {
+        [] => None,
+        [RispExp::Symbol(parent)] => Some(class_named(parent)?),
+        [_] => return Err(RispErr::Reason("expected the parent to be a class name".to_string())),
+        _ => return Err(RispErr::Reason("a class can only have one parent".to_string())),
+    };
+
+    let mut slots = vec![];
+    for form in slot_forms {
+        match form {
+            RispExp::Symbol(slot) => slots.push((slot.clone(), RispExp::List(vec![].into()))),
+            RispExp::List(pair) => match &pair[..] {
+                [RispExp::Symbol(slot), default] => slots.push((slot.clone(), eval(default, env)?)),
+                _ => return Err(RispErr::Reason(format!("expected a slot name and its default, got '{}'", form))),
+            },
+            _ => return Err(RispErr::Reason(format!("expected a slot, got '{}'", form))),
+        }
+    }
+
+    // `(animal-name x)` for each slot it adds
+    for (slot, _) in &slots {
+        let accessor = eval_lambda_args(&[
+            RispExp::List(vec![RispExp::Symbol("obj".to_string())].into()),
+            RispExp::List(vec![
+                RispExp::Symbol("slot-value".to_string()),
+                RispExp::Symbol("obj".to_string()),
+                RispExp::List(vec![RispExp::Symbol("quote".to_string()), RispExp::Symbol(slot.clone())].into()),
+            ].into()),
+        ], env)?;
+        env.insert(format!("{}-{}", name, slot), accessor);
+    }
+
+    let class = Rc::new(Class { name: name.clone(), parent, slots });
+    CLASSES.with(|classes| classes.borrow_mut().insert(name.clone(), class));
+    // the name evaluates to itself, so `(make animal ...)` needs no quote
+    env.insert(name.clone(), RispExp::Symbol(name.clone()));
+
+    Ok(arg_forms[0].clone())
+}
+
+// `(defmethod name ((self class) params...) body...)`
+pub fn eval_defmethod_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let usage = || RispErr::Reason("expected (defmethod name ((self class) params...) body...)".to_string());
+    let (name, params, body) = match arg_forms {
+        [RispExp::Symbol(name), RispExp::List(params), body @ ..] if !body.is_empty() => (name, params, body),
+        _ => return Err(usage()),
+    };
+    let (param, class, rest) = match &params[..] {
+        [RispExp::List(first), rest @ ..] => match &first[..] {
+            [param @ RispExp::Symbol(_), RispExp::Symbol(class)] => (param, class, rest),
+            _ => return Err(usage()),
+        },
+        _ => return Err(usage()),
+    };
+    class_named(class)?;
+
+    let mut lambda_params = vec![param.clone()];
+    lambda_params.extend_from_slice(rest);
+    let mut lambda_forms = vec![RispExp::List(lambda_params.into())];
+    lambda_forms.extend_from_slice(body);
+    let method = eval_lambda_args(&lambda_forms, env)?;
+
+    let generic = match env_get(name, env) {
+        Some(RispExp::Generic(generic)) => generic,
+        None => {
+            let generic = Rc::new(Generic { name: name.clone(), methods: RefCell::new(HashMap::new()) });
+            env.insert(name.clone(), RispExp::Generic(generic.clone()));
+            generic
+        },
+        Some(_) => return Err(RispErr::Reason(format!("'{}' is already bound to something other than a generic fn", name))),
+    };
+    generic.methods.borrow_mut().insert(class.clone(), method);
+
+    Ok(arg_forms[0].clone())
+}
+
+// the method for the class of the first argument, or the nearest one it inherits
+pub fn call_generic(generic: &Generic, args: Vec<RispExp>, env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let method = match args.first() {
+        Some(RispExp::Object(obj)) => obj.class.lineage().iter().find_map(|class| {
+            generic.methods.borrow().get(&class.name).cloned()
+        }),
+        _ => None,
+    };
+
+    match (method, args.first()) {
+        (Some(method), _) => call_exp(&method, args, env),
+        (None, Some(arg)) => Err(RispErr::Reason(format!("no `{}` method for '{}'", generic.name, arg))),
+        (None, None) => Err(RispErr::Reason(format!("`{}` expects an object to dispatch on", generic.name))),
+    }
+}
+
+fn object_arg<'a>(op: &str, args: &'a [RispExp]) -> Result<&'a Rc<Object>, RispErr> {
+    match args::get(op, args, 0, "an object")? {
+        RispExp::Object(obj) => Ok(obj),
+        exp => Err(args::wrong(op, 0, "an object", exp)),
+    }
+}
+
+// a slot written as `'name` or `:name`
+fn slot_arg(op: &str, args: &[RispExp], idx: usize) -> Result<String, RispErr> {
+    match args::get(op, args, idx, "a slot name")? {
+        RispExp::Symbol(slot) => Ok(slot.clone()),
+        RispExp::Keyword(slot) => Ok(slot[1..].to_string()),
+        exp => Err(args::wrong(op, idx, "a slot name", exp)),
+    }
+}
+
+fn slot_index(obj: &Object, slot: &str) -> Result<usize, RispErr> {
+    obj.slots.borrow().iter().position(|(name, _)| name == slot)
+        .ok_or_else(|| RispErr::Reason(format!("{} has no slot '{}'", obj.class.name, slot)))
+}
+
+// `(make class :slot value...)`
+fn make(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let class = match args::get("make", args, 0, "a class name")? {
+        RispExp::Symbol(name) => class_named(name)?,
+        exp => return Err(args::wrong("make", 0, "a class name", exp)),
+    };
+
+    let obj = Object { slots: RefCell::new(class.all_slots()), class };
+    if args.len().is_multiple_of(2) {
+        return Err(RispErr::Reason("expected a value for every slot".to_string()))
+    }
+    for idx in (1..args.len()).step_by(2) {
+        let at = slot_index(&obj, &slot_arg("make", args, idx)?)?;
+        obj.slots.borrow_mut()[at].1 = args[idx + 1].clone();
+    }
+
+    Ok(RispExp::Object(Rc::new(obj)))
+}
+
+// `(slot-value obj 'slot)`
+fn slot_value(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let obj = object_arg("slot-value", args)?;
+    let at = slot_index(obj, &slot_arg("slot-value", args, 1)?)?;
+    Ok(obj.slots.borrow()[at].1.clone())
+}
+
+// `(set-slot! obj 'slot value)`, returning `value`
+fn set_slot(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let obj = object_arg("set-slot!", args)?;
+    let at = slot_index(obj, &slot_arg("set-slot!", args, 1)?)?;
+    let value = args::get("set-slot!", args, 2, "a value to set")?;
+    obj.slots.borrow_mut()[at].1 = value.clone();
+
+    Ok(value.clone())
+}
+
+fn class_of(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    Ok(RispExp::Symbol(object_arg("class-of", args)?.class.name.clone()))
+}
+
+// `(is-a? obj 'class)`, for its own class or one it inherits from
+fn is_a(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let obj = object_arg("is-a?", args)?;
+    match args::get("is-a?", args, 1, "a class name")? {
+        RispExp::Symbol(name) => Ok(RispExp::Bool(obj.class.lineage().iter().any(|class| &class.name == name))),
+        exp => Err(args::wrong("is-a?", 1, "a class name", exp)),
+    }
+}
+
+fn is_object(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    Ok(RispExp::Bool(matches!(args.first(), Some(RispExp::Object(_)))))
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert("make".to_string(), RispExp::Func(make));
+    data.insert("slot-value".to_string(), RispExp::Func(slot_value));
+    data.insert("set-slot!".to_string(), RispExp::Func(set_slot));
+    data.insert("class-of".to_string(), RispExp::Func(class_of));
+    data.insert("is-a?".to_string(), RispExp::Func(is_a));
+    data.insert("object?".to_string(), RispExp::Func(is_object));
+}
@@ -0,0 +1,104 @@
+// Where a program's output goes: `(print x...)` and `(println x...)` write
+// to stdout, and `(warn message)` reports a warning on stderr. An embedder
+// evaluating through an `Interpreter` gets both back with the result instead,
+// so they can be shown next to it.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use super::{effect, RispErr, RispExp};
+
+#[derive(Default)]
+pub struct Captured {
+    pub stdout: String,
+    pub warnings: Vec<String>,
+}
+
+thread_local! {
+    static CAPTURED: RefCell<Option<Captured>> = const { RefCell::new(None) };
+    // whether what's been written to stdout ends with a newline, or nothing has
+    static LINE_ENDED: Cell<bool> = const { Cell::new(true) };
+}
+
+// runs `f` with output kept rather than written, and returns it with the result
+pub fn capture<T>(f: impl FnOnce() -> T) -> (T, Captured) {
+    let outer = CAPTURED.with(|captured| captured.replace(Some(Captured::default())));
+    let res = f();
+    let captured = CAPTURED.with(|captured| captured.replace(outer)).unwrap_or_default();
+
+    (res, captured)
+}
+
+pub fn write(text: &str) -> Result<(), RispErr> {
+    let captured = CAPTURED.with(|captured| match captured.borrow_mut().as_mut() {
+        Some(captured) => {
+            captured.stdout.push_str(text);
+            true
+        },
+        None => false,
+    });
+    if captured {
+        return Ok(())
+    }
+
+    if let Some(last) = text.chars().last() {
+        LINE_ENDED.with(|ended| ended.set(last == '\n'));
+    }
+    let mut out = io::stdout();
+    out.write_all(text.as_bytes())
+        .and_then(|_| out.flush())
+        .map_err(|e| RispErr::Reason(format!("could not write to stdout: {}", e)))
+}
+
+// ends the line a `print` left unfinished, so what's shown next starts its own
+pub fn end_line() {
+    if !LINE_ENDED.with(|ended| ended.replace(true)) {
+        println!();
+    }
+}
+
+pub fn warn(message: String) {
+    CAPTURED.with(|captured| match captured.borrow_mut().as_mut() {
+        Some(captured) => captured.warnings.push(message),
+        None => eprintln!("warning: {}", message),
+    });
+}
+
+// strings print as their text, without the quotes and escapes the REPL shows
+fn text(args: &[RispExp]) -> String {
+    let parts: Vec<String> = args
+        .iter()
+        .map(|arg| match arg {
+            RispExp::Str(s) => s.clone(),
+            RispExp::Char(c) => c.to_string(),
+            _ => arg.to_string(),
+        })
+        .collect();
+
+    parts.join(" ")
+}
+
+fn print(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    effect("print")?;
+    write(&text(args))?;
+    Ok(RispExp::Bool(true))
+}
+
+fn println(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    effect("print")?;
+    write(&format!("{}\n", text(args)))?;
+    Ok(RispExp::Bool(true))
+}
+
+fn warn_args(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    effect("warn")?;
+    warn(text(args));
+    Ok(RispExp::Bool(true))
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert("print".to_string(), RispExp::Func(print));
+    data.insert("println".to_string(), RispExp::Func(println));
+    data.insert("warn".to_string(), RispExp::Func(warn_args));
+}
@@ -0,0 +1,73 @@
+// Parsed forms of recently read source text, so the same snippets evaluated
+// over and over, like a server sent the same request or a config file read
+// again on every reload, are only tokenized and parsed once. Entries are
+// keyed by a hash of the text and checked against it, and the oldest go first
+// once there are `CAPACITY` of them. What a reader macro reads into can change
+// when macros are registered, so that empties the cache. `(session-stats)`
+// counts the lookups as `:parse-cache-hits` and `:parse-cache-misses`.
+
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+use super::{Pos, RispExp};
+
+const CAPACITY: usize = 256;
+
+type Forms = Rc<Vec<(RispExp, Pos)>>;
+
+#[derive(Default)]
+struct Cache {
+    entries: HashMap<u64, (String, Forms)>,
+    // hashes from the oldest entry to the newest
+    order: VecDeque<u64>,
+}
+
+thread_local! {
+    static CACHE: RefCell<Cache> = RefCell::new(Cache::default());
+    static HITS: Cell<u64> = const { Cell::new(0) };
+    static MISSES: Cell<u64> = const { Cell::new(0) };
+}
+
+fn hash(src: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    src.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub fn get(src: &str) -> Option<Forms> {
+    let forms = CACHE.with(|cache| match cache.borrow().entries.get(&hash(src)) {
+        Some((cached, forms)) if cached == src => Some(forms.clone()),
+        _ => None,
+    });
+    let counter = if forms.is_some() { &HITS } else { &MISSES };
+    counter.with(|n| n.set(n.get() + 1));
+
+    forms
+}
+
+// how many lookups found the text's forms and how many didn't
+pub fn counts() -> (u64, u64) {
+    (HITS.with(Cell::get), MISSES.with(Cell::get))
+}
+
+pub fn insert(src: &str, forms: Forms) {
+    CACHE.with(|cache| {
+        let cache = &mut *cache.borrow_mut();
+        let key = hash(src);
+        if cache.entries.insert(key, (src.to_string(), forms)).is_none() {
+            cache.order.push_back(key);
+        }
+        while cache.order.len() > CAPACITY {
+            if let Some(oldest) = cache.order.pop_front() {
+                cache.entries.remove(&oldest);
+            }
+        }
+    })
+}
+
+pub fn clear() {
+    CACHE.with(|cache| *cache.borrow_mut() = Cache::default());
+}
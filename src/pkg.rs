@@ -0,0 +1,309 @@
+// Risp libraries fetched with git. `risp pkg add url` clones a package into
+// `risp_packages/name`, where the name is the last part of the url, and adds
+// it to the project's `risp.toml`:
+//
+//     [dependencies]
+//     strings = "https://example.com/strings.git"
+//
+// A bare name is cloned from `$RISP_REGISTRY/name.git`. The commit it was
+// cloned at goes in `risp.lock`, and `risp pkg vendor` copies every package at
+// its locked commit into `vendor/`, for deploying without a network.
+//
+// `(require 'strings)` then evaluates `strings.risp` from `vendor/strings`, or
+// from `risp_packages/strings` when it isn't vendored, in the global
+// environment, unless it's already in `*modules*`, the packages loaded so far.
+// What it defines is also bound as `strings/name`.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use super::{diff, env_get, err_message, eval, global_env, namespace, parse_forms, sandbox, RispEnv, RispErr, RispExp};
+
+pub const MANIFEST: &str = "risp.toml";
+pub const LOCKFILE: &str = "risp.lock";
+pub const PACKAGES: &str = "risp_packages";
+pub const VENDOR: &str = "vendor";
+
+const USAGE: &str = "usage: risp pkg add (git-url | name)
+       risp pkg vendor";
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+// the package name and the url to clone it from
+fn source(arg: &str) -> Result<(String, String), String> {
+    // git would take it for an option
+    if arg.starts_with('-') {
+        return Err(format!("'{}' isn't a url or a package name", arg))
+    }
+    if valid_name(arg) {
+        let registry = env::var("RISP_REGISTRY")
+            .map_err(|_| format!("'{}' isn't a url, and RISP_REGISTRY isn't set to look it up in", arg))?;
+        return Ok((arg.to_string(), format!("{}/{}.git", registry.trim_end_matches('/'), arg)))
+    }
+
+    let last = arg.trim_end_matches('/').rsplit(['/', ':']).next().unwrap_or("");
+    let name = last.strip_suffix(".git").unwrap_or(last);
+    match valid_name(name) {
+        true => Ok((name.to_string(), arg.to_string())),
+        false => Err(format!("can not tell the package name from '{}'", arg)),
+    }
+}
+
+// the `key = "value"` entries of a toml file, with the section each is in
+fn entries(path: &str, text: &str) -> Result<Vec<(String, String, String)>, String> {
+    let mut entries = vec![];
+    let mut section = String::new();
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            section = name.trim().to_string();
+            continue
+        }
+
+        let entry = line.split_once('=').and_then(|(key, value)| {
+            let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+            Some((section.clone(), key.trim().to_string(), value.to_string()))
+        });
+        match entry {
+            Some(entry) => entries.push(entry),
+            None => return Err(format!("{}:{}: expected key = \"value\"", path, idx + 1)),
+        }
+    }
+
+    Ok(entries)
+}
+
+// the `name = "source"` entries of the `[dependencies]` section
+pub fn dependencies(manifest: &str) -> Result<Vec<(String, String)>, String> {
+    Ok(entries(MANIFEST, manifest)?
+        .into_iter()
+        .filter(|(section, _, _)| section == "dependencies")
+        .map(|(_, name, source)| (name, source))
+        .collect())
+}
+
+pub struct Locked {
+    pub name: String,
+    pub source: String,
+    // the commit it's pinned to
+    pub rev: String,
+}
+
+// the packages of `risp.lock`, a section for each with its source and rev
+pub fn locked(lockfile: &str) -> Result<Vec<Locked>, String> {
+    let mut packages: Vec<Locked> = vec![];
+    for (name, key, value) in entries(LOCKFILE, lockfile)? {
+        if packages.last().is_none_or(|package| package.name != name) {
+            packages.push(Locked { name: name.clone(), source: String::new(), rev: String::new() });
+        }
+        let package = packages.last_mut().expect("pushed above");
+        match key.as_str() {
+            "source" => package.source = value,
+            "rev" => package.rev = value,
+            _ => return Err(format!("{}: unknown key '{}' for '{}'", LOCKFILE, key, name)),
+        }
+    }
+
+    if let Some(package) = packages.iter().find(|package| package.source.is_empty() || package.rev.is_empty()) {
+        return Err(format!("{}: '{}' needs a source and a rev", LOCKFILE, package.name))
+    }
+    // both are passed to git, which would take them for options
+    match packages.iter().find(|package| package.source.starts_with('-') || package.rev.starts_with('-')) {
+        Some(package) => Err(format!("{}: the source and rev of '{}' can not start with '-'", LOCKFILE, package.name)),
+        None => Ok(packages),
+    }
+}
+
+fn write_lockfile(packages: &[Locked]) -> Result<(), String> {
+    let mut out = "# written by `risp pkg`, the exact commit of every package\n".to_string();
+    for package in packages {
+        out.push_str(&format!("\n[{}]\nsource = \"{}\"\nrev = \"{}\"\n", package.name, package.source, package.rev));
+    }
+
+    fs::write(LOCKFILE, out).map_err(|e| format!("could not write '{}': {}", LOCKFILE, e))
+}
+
+fn read_lockfile() -> Result<Vec<Locked>, String> {
+    match fs::read_to_string(LOCKFILE) {
+        Ok(lockfile) => locked(&lockfile),
+        Err(_) => Ok(vec![]),
+    }
+}
+
+// runs git, its output when it succeeds
+fn git(args: &[&str], dir: Option<&Path>) -> Result<String, String> {
+    let mut cmd = Command::new("git");
+    if let Some(dir) = dir {
+        cmd.arg("-C").arg(dir);
+    }
+    let output = cmd.args(args).output().map_err(|e| format!("could not run git: {}", e))?;
+    match output.status.success() {
+        true => Ok(String::from_utf8_lossy(&output.stdout).trim().to_string()),
+        false => Err(format!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim())),
+    }
+}
+
+// `manifest` with `name` added to the end of its dependencies
+fn with_dependency(manifest: &str, name: &str, source: &str) -> String {
+    let entry = format!("{} = \"{}\"", name, source);
+    let mut lines: Vec<String> = manifest.lines().map(String::from).collect();
+
+    let start = match lines.iter().position(|line| line.trim() == "[dependencies]") {
+        Some(start) => start,
+        None => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push("[dependencies]".to_string());
+            lines.len() - 1
+        },
+    };
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| line.trim().starts_with('['))
+        .map_or(lines.len(), |n| start + 1 + n);
+    let last_entry = lines[start..end].iter().rposition(|line| !line.trim().is_empty()).map_or(start, |n| start + n);
+    lines.insert(last_entry + 1, entry);
+
+    lines.join("\n") + "\n"
+}
+
+fn add(arg: &str) -> Result<(), String> {
+    let (name, url) = source(arg)?;
+    let manifest = fs::read_to_string(MANIFEST).unwrap_or_default();
+    if dependencies(&manifest)?.iter().any(|(dep, _)| *dep == name) {
+        return Err(format!("'{}' is already a dependency", name))
+    }
+
+    let dir = Path::new(PACKAGES).join(&name);
+    if dir.exists() {
+        return Err(format!("'{}' is already there, but isn't in {}", dir.display(), MANIFEST))
+    }
+    git(&["clone", "--quiet", "--", &url, &dir.to_string_lossy()], None)?;
+    let rev = git(&["rev-parse", "HEAD"], Some(&dir))?;
+
+    fs::write(MANIFEST, with_dependency(&manifest, &name, &url))
+        .map_err(|e| format!("could not write '{}': {}", MANIFEST, e))?;
+    let mut packages = read_lockfile()?;
+    packages.retain(|package| package.name != name);
+    packages.push(Locked { name: name.clone(), source: url.clone(), rev: rev.clone() });
+    write_lockfile(&packages)?;
+    println!("added {} from {} at {}", name, url, rev);
+
+    Ok(())
+}
+
+// copies the files of `from` to `to`, leaving out the git repository
+fn copy_dir(from: &Path, to: &Path) -> Result<(), String> {
+    fs::create_dir_all(to).map_err(|e| format!("could not create '{}': {}", to.display(), e))?;
+    let entries = fs::read_dir(from).map_err(|e| format!("could not read '{}': {}", from.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let dest = to.join(entry.file_name());
+        if entry.file_name() == ".git" {
+            continue
+        }
+        match path.is_dir() {
+            true => copy_dir(&path, &dest)?,
+            false => {
+                fs::copy(&path, &dest).map_err(|e| format!("could not copy '{}': {}", path.display(), e))?;
+            },
+        }
+    }
+
+    Ok(())
+}
+
+// copies every locked package into `vendor/`, fetching the ones that aren't
+// there at their locked commit
+fn vendor() -> Result<(), String> {
+    let packages = read_lockfile()?;
+    if packages.is_empty() {
+        return Err(format!("no packages in {} to vendor", LOCKFILE))
+    }
+
+    for package in &packages {
+        let dir = Path::new(PACKAGES).join(&package.name);
+        if !dir.exists() {
+            git(&["clone", "--quiet", "--", &package.source, &dir.to_string_lossy()], None)?;
+        }
+        // a checkout that isn't on the locked commit is checked out to it
+        if git(&["rev-parse", "HEAD"], Some(&dir))? != package.rev {
+            git(&["checkout", "--quiet", &package.rev], Some(&dir))?;
+        }
+
+        let dest = Path::new(VENDOR).join(&package.name);
+        if dest.exists() {
+            fs::remove_dir_all(&dest).map_err(|e| format!("could not remove '{}': {}", dest.display(), e))?;
+        }
+        copy_dir(&dir, &dest)?;
+        println!("vendored {} at {}", package.name, package.rev);
+    }
+
+    Ok(())
+}
+
+// `risp pkg ...`
+pub fn run(args: &[String]) -> Result<(), String> {
+    match args {
+        [cmd, source] if cmd == "add" => add(source),
+        [cmd] if cmd == "vendor" => vendor(),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+// `(require 'name)`
+pub fn require(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let name = match args {
+        [RispExp::Symbol(name)] | [RispExp::Str(name)] if valid_name(name) => name.clone(),
+        _ => return Err(RispErr::Reason("expected a package name".to_string())),
+    };
+
+    // the package's definitions go in the global environment wherever it's required
+    let mut globals = RispEnv { data: global_env(env).data.clone(), outer: None };
+    let loaded = match env_get("*modules*", &globals) {
+        Some(RispExp::List(loaded)) => loaded,
+        _ => vec![].into(),
+    };
+    if loaded.iter().any(|module| matches!(module, RispExp::Symbol(module) if *module == name)) {
+        return Ok(RispExp::Symbol(name))
+    }
+
+    let vendored = format!("{}/{}/{}.risp", VENDOR, name, name);
+    let path = match sandbox::resolve(&vendored)?.exists() {
+        true => vendored,
+        false => format!("{}/{}/{}.risp", PACKAGES, name, name),
+    };
+    let src = fs::read_to_string(sandbox::resolve(&path)?).map_err(|_| {
+        RispErr::Reason(format!("no package named '{}', `risp pkg add` fetches one", name))
+    })?;
+
+    // marked before it's loaded, so packages requiring each other load once
+    let mut modules = loaded;
+    modules.push(RispExp::Symbol(name.clone()));
+    globals.insert("*modules*".to_string(), RispExp::List(modules));
+    let before = globals.data.borrow().clone();
+    namespace::within(&name, || {
+        let forms = parse_forms(src).map_err(|e| RispErr::Reason(format!("{}: {}", path, err_message(e))))?;
+        for form in forms {
+            eval(&form, &mut globals)?;
+        }
+        Ok(())
+    })?;
+
+    // and as `name/x`, whatever plain `x` is bound to later
+    let defined = diff::defined_names(&before, &globals.data.borrow());
+    for def in defined.into_iter().filter(|def| def != "*modules*") {
+        let value = globals.data.borrow()[&def].clone();
+        globals.insert(format!("{}/{}", name, def), value);
+    }
+
+    Ok(RispExp::Symbol(name))
+}
@@ -0,0 +1,154 @@
+// Values moved between interpreters, like a pool of them in a server sharing
+// data one of them computed. Risp values are reference counted and tied to
+// the thread that made them, so `Portable::detach` copies a value into a form
+// that can be sent to another thread, and `attach` turns it back into a value
+// there.
+//
+// Lambdas are carried by their source, their parameters and body, along with
+// what they closed over, and don't keep any type annotations. Builtins are
+// carried as they are. Resources, generators and the other values holding on
+// to host state can't be detached.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[cfg(feature = "bignum")]
+use super::bignum::Rational;
+use super::hashmap::MapKey;
+use super::queue;
+use super::sorted::SortKey;
+use super::tagged::Tagged;
+use super::{RispEnv, RispErr, RispExp, RispLambda};
+
+#[derive(Clone, Debug)]
+enum Value {
+    Bool(bool),
+    Symbol(String),
+    Keyword(String),
+    Number(f64),
+    Int(i64),
+    #[cfg(feature = "bignum")]
+    Exact(Rational),
+    Str(String),
+    Char(char),
+    List(Vec<Value>),
+    Vector(Vec<Value>),
+    Func(fn(&[RispExp]) -> Result<RispExp, RispErr>),
+    EnvFunc(fn(&[RispExp], &mut RispEnv) -> Result<RispExp, RispErr>),
+    // its params, body and the local bindings it closed over
+    Lambda(Box<Value>, Vec<Value>, Vec<(String, Value)>),
+    Tuple(Vec<Value>),
+    SortedMap(Vec<(Value, Value)>),
+    SortedSet(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Queue(Vec<Value>),
+    Stack(Vec<Value>),
+    Tagged(String, Box<Value>),
+}
+
+#[derive(Clone, Debug)]
+pub struct Portable(Value);
+
+fn detach_all<'a>(exps: impl Iterator<Item = &'a RispExp>) -> Result<Vec<Value>, RispErr> {
+    exps.map(detach).collect()
+}
+
+fn detach(exp: &RispExp) -> Result<Value, RispErr> {
+    let value = match exp {
+        RispExp::Bool(b) => Value::Bool(*b),
+        RispExp::Symbol(s) => Value::Symbol(s.clone()),
+        RispExp::Keyword(k) => Value::Keyword(k.clone()),
+        RispExp::Number(n) => Value::Number(*n),
+        RispExp::Int(n) => Value::Int(*n),
+        #[cfg(feature = "bignum")]
+        RispExp::Exact(r) => Value::Exact((**r).clone()),
+        RispExp::Str(s) => Value::Str(s.clone()),
+        RispExp::Char(c) => Value::Char(*c),
+        RispExp::List(xs) => Value::List(detach_all(xs.iter())?),
+        RispExp::Vector(xs) => Value::Vector(detach_all(xs.borrow().iter())?),
+        RispExp::Func(f) => Value::Func(*f),
+        RispExp::EnvFunc(f) => Value::EnvFunc(*f),
+        RispExp::Lambda(lambda) => Value::Lambda(
+            Box::new(detach(&lambda.params_exp)?),
+            detach_all(lambda.body_exps.iter())?,
+            lambda.closed_over().iter().map(|(k, v)| Ok((k.clone(), detach(v)?))).collect::<Result<_, RispErr>>()?,
+        ),
+        RispExp::Tuple(xs) => Value::Tuple(detach_all(xs.iter())?),
+        RispExp::SortedMap(map) => Value::SortedMap(
+            map.iter().map(|(k, v)| Ok((detach(k.exp())?, detach(v)?))).collect::<Result<_, RispErr>>()?,
+        ),
+        RispExp::SortedSet(set) => Value::SortedSet(detach_all(set.iter().map(SortKey::exp))?),
+        RispExp::Map(map) => Value::Map(
+            map.iter().map(|(k, v)| Ok((detach(&k.exp())?, detach(v)?))).collect::<Result<_, RispErr>>()?,
+        ),
+        RispExp::Queue(q) => Value::Queue(detach_all(q.values().iter())?),
+        RispExp::Stack(stack) => Value::Stack(detach_all(queue::stack_values(stack).iter())?),
+        RispExp::Tagged(tagged) => Value::Tagged(tagged.tag().to_string(), Box::new(detach(tagged.value())?)),
+        _ => return Err(RispErr::Reason(format!("'{}' can not be moved to another interpreter", exp))),
+    };
+
+    Ok(value)
+}
+
+fn attach_all(values: &[Value]) -> Vec<RispExp> {
+    values.iter().map(attach).collect()
+}
+
+fn attach(value: &Value) -> RispExp {
+    match value {
+        Value::Bool(b) => RispExp::Bool(*b),
+        Value::Symbol(s) => RispExp::Symbol(s.clone()),
+        Value::Keyword(k) => RispExp::Keyword(k.clone()),
+        Value::Number(n) => RispExp::Number(*n),
+        Value::Int(n) => RispExp::Int(*n),
+        #[cfg(feature = "bignum")]
+        Value::Exact(r) => RispExp::Exact(Rc::new(r.clone())),
+        Value::Str(s) => RispExp::Str(s.clone()),
+        Value::Char(c) => RispExp::Char(*c),
+        Value::List(xs) => RispExp::List(attach_all(xs).into()),
+        Value::Vector(xs) => RispExp::Vector(Rc::new(RefCell::new(attach_all(xs)))),
+        Value::Func(f) => RispExp::Func(*f),
+        Value::EnvFunc(f) => RispExp::EnvFunc(*f),
+        Value::Lambda(params, body, closure) => RispExp::Lambda(RispLambda {
+            params_exp: Rc::new(attach(params)),
+            body_exps: Rc::new(attach_all(body)),
+            signature: None,
+            closure: match closure.is_empty() {
+                true => Rc::new(vec![]),
+                false => Rc::new(vec![Rc::new(RefCell::new(closure.iter().map(|(k, v)| (k.clone(), attach(v))).collect()))]),
+            },
+        }),
+        Value::Tuple(xs) => RispExp::Tuple(Rc::new(attach_all(xs))),
+        // the keys were sortable where they were detached, so they still are
+        Value::SortedMap(pairs) => RispExp::SortedMap(Rc::new(
+            pairs
+                .iter()
+                .map(|(k, v)| (SortKey::new(attach(k)).expect("detached sorted key"), attach(v)))
+                .collect::<BTreeMap<_, _>>(),
+        )),
+        Value::SortedSet(keys) => RispExp::SortedSet(Rc::new(
+            keys.iter().map(|k| SortKey::new(attach(k)).expect("detached sorted key")).collect::<BTreeSet<_>>(),
+        )),
+        Value::Map(pairs) => RispExp::Map(Rc::new(
+            pairs
+                .iter()
+                .map(|(k, v)| (MapKey::new(&attach(k)).expect("detached map key"), attach(v)))
+                .collect(),
+        )),
+        Value::Queue(xs) => queue::queue_of(&attach_all(xs)),
+        Value::Stack(xs) => queue::stack_of(attach_all(xs).iter().rev()),
+        Value::Tagged(tag, value) => RispExp::Tagged(Rc::new(Tagged::new(tag.clone(), attach(value)))),
+    }
+}
+
+impl Portable {
+    pub fn detach(exp: &RispExp) -> Result<Portable, RispErr> {
+        Ok(Portable(detach(exp)?))
+    }
+
+    // a new copy of the value for the interpreter on this thread
+    pub fn attach(&self) -> RispExp {
+        attach(&self.0)
+    }
+}
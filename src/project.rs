@@ -0,0 +1,125 @@
+// Projects, a directory with a `risp.toml`. `risp new name` makes one:
+//
+//     name/risp.toml
+//     name/src/lib.risp     definitions
+//     name/src/main.risp    the program
+//     name/tests/lib.risp   checks of the definitions
+//
+// Inside one, `risp run` evaluates the files of `src/` with `main.risp` last,
+// and `risp test` evaluates each file of `tests/` in a fresh environment with
+// the other files of `src/` loaded first. A test fails on its first error,
+// like an `assert` that doesn't hold.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::pkg::MANIFEST;
+use super::{default_env, err_message, eval, parse_forms_spanned, RispEnv};
+
+fn write(path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("could not create '{}': {}", dir.display(), e))?;
+    }
+    fs::write(path, contents).map_err(|e| format!("could not write '{}': {}", path.display(), e))
+}
+
+// `risp new name`
+pub fn new(name: &str) -> Result<(), String> {
+    let dir = Path::new(name);
+    if dir.exists() {
+        return Err(format!("'{}' already exists", name))
+    }
+    let package = dir.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+
+    write(&dir.join(MANIFEST), &format!("[package]\nname = \"{}\"\n\n[dependencies]\n", package))?;
+    write(&dir.join("src/lib.risp"), "(defn greet (name) (str-concat \"Hello, \" name \"!\"))\n")?;
+    write(&dir.join("src/main.risp"), "(println (greet \"world\"))\n")?;
+    write(&dir.join("tests/lib.risp"), "(assert (str-contains? (greet \"risp\") \"risp\"))\n")?;
+    println!("created {}", name);
+
+    Ok(())
+}
+
+// evaluates each form in the file, stopping at the first that fails
+pub fn eval_file(path: &Path, env: &mut RispEnv) -> Result<(), String> {
+    let src = fs::read_to_string(path).map_err(|e| format!("could not read '{}': {}", path.display(), e))?;
+    let forms = parse_forms_spanned(&src).map_err(|e| format!("{}: {}", path.display(), err_message(e)))?;
+    for (form, pos) in &forms {
+        eval(form, env).map_err(|e| format!("{}:{}: {}", path.display(), pos, err_message(e)))?;
+    }
+
+    Ok(())
+}
+
+// the `.risp` files of a project directory, in order
+fn risp_files(dir: &str) -> Result<Vec<PathBuf>, String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(vec![]),
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "risp"))
+        .collect();
+    files.sort();
+
+    Ok(files)
+}
+
+fn in_project() -> Result<(), String> {
+    match Path::new(MANIFEST).is_file() {
+        true => Ok(()),
+        false => Err(format!("no {} here, `risp new name` makes a project", MANIFEST)),
+    }
+}
+
+// the files of `src/` other than `main.risp`
+fn library() -> Result<Vec<PathBuf>, String> {
+    Ok(risp_files("src")?.into_iter().filter(|path| !path.ends_with("main.risp")).collect())
+}
+
+// `risp run [path]`, a single file or the project's program
+pub fn run(args: &[String], env: &mut RispEnv) -> Result<(), String> {
+    if let [path] = args {
+        return eval_file(Path::new(path), env)
+    }
+
+    in_project()?;
+    let main = Path::new("src/main.risp");
+    if !main.is_file() {
+        return Err(format!("'{}' isn't there", main.display()))
+    }
+    for path in library()? {
+        eval_file(&path, env)?;
+    }
+
+    eval_file(main, env)
+}
+
+// `risp test`
+pub fn test() -> Result<(), String> {
+    in_project()?;
+    let library = library()?;
+    let tests = risp_files("tests")?;
+
+    let mut failed = 0;
+    for test in &tests {
+        let env = &mut default_env();
+        let res = library.iter().chain([test]).try_for_each(|path| eval_file(path, env));
+        match res {
+            Ok(()) => println!("ok      {}", test.display()),
+            Err(e) => {
+                failed += 1;
+                println!("FAILED  {}", e);
+            },
+        }
+    }
+    println!("{} tests, {} failed", tests.len(), failed);
+
+    match failed {
+        0 => Ok(()),
+        1 => Err("1 test failed".to_string()),
+        n => Err(format!("{} tests failed", n)),
+    }
+}
@@ -0,0 +1,244 @@
+// Persistent queues and stacks. Both share structure between versions, so
+// `(enqueue q x)` or `(push s x)` leave `q` and `s` usable and don't copy them.
+//
+// A queue is a `front` list to take from and a reversed `back` list to add to.
+// `back` is only reversed into `front` when `front` runs out, which keeps
+// every operation O(1) amortized and `peek` O(1).
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{args, tagged, RispErr, RispExp};
+
+#[derive(Debug)]
+pub enum Cons {
+    Nil,
+    Cell(RispExp, Rc<Cons>),
+}
+
+impl Cons {
+    fn iter(&self) -> ConsIter<'_> {
+        ConsIter { next: self }
+    }
+
+    fn reversed(&self) -> Rc<Cons> {
+        self.iter().fold(Rc::new(Cons::Nil), |list, x| Rc::new(Cons::Cell(x.clone(), list)))
+    }
+}
+
+// unlinks cells one at a time, so dropping a long list doesn't recurse
+impl Drop for Cons {
+    fn drop(&mut self) {
+        let mut next = match self {
+            Cons::Cell(_, rest) => std::mem::replace(rest, Rc::new(Cons::Nil)),
+            Cons::Nil => return,
+        };
+
+        while let Ok(mut cell) = Rc::try_unwrap(next) {
+            next = match &mut cell {
+                Cons::Cell(_, rest) => std::mem::replace(rest, Rc::new(Cons::Nil)),
+                Cons::Nil => return,
+            };
+        }
+    }
+}
+
+struct ConsIter<'a> {
+    next: &'a Cons,
+}
+
+impl<'a> Iterator for ConsIter<'a> {
+    type Item = &'a RispExp;
+
+    fn next(&mut self) -> Option<&'a RispExp> {
+        match self.next {
+            Cons::Nil => None,
+            Cons::Cell(x, rest) => {
+                self.next = rest;
+                Some(x)
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RispQueue {
+    front: Rc<Cons>,
+    back: Rc<Cons>,
+}
+
+impl RispQueue {
+    // keeps `front` non-empty unless the whole queue is
+    fn new(front: Rc<Cons>, back: Rc<Cons>) -> RispQueue {
+        match front.as_ref() {
+            Cons::Nil => RispQueue { front: back.reversed(), back: Rc::new(Cons::Nil) },
+            _ => RispQueue { front, back },
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(self.front.as_ref(), Cons::Nil)
+    }
+
+    pub fn values(&self) -> Vec<RispExp> {
+        let mut values: Vec<RispExp> = self.front.iter().cloned().collect();
+        let mut back: Vec<RispExp> = self.back.iter().cloned().collect();
+        back.reverse();
+        values.extend(back);
+        values
+    }
+}
+
+pub fn stack_values(stack: &Cons) -> Vec<RispExp> {
+    stack.iter().cloned().collect()
+}
+
+pub fn display(name: &str, values: &[RispExp]) -> String {
+    let xs: Vec<String> = values
+        .iter()
+        .map(|x| x.to_string())
+        .collect();
+    format!("#{}({})", name, xs.join(","))
+}
+
+pub fn queue_of(xs: &[RispExp]) -> RispExp {
+    let front = xs
+        .iter()
+        .rev()
+        .fold(Rc::new(Cons::Nil), |list, x| Rc::new(Cons::Cell(x.clone(), list)));
+
+    RispExp::Queue(Rc::new(RispQueue::new(front, Rc::new(Cons::Nil))))
+}
+
+// the last value ends up on top, as if they'd been pushed in order
+pub fn stack_of<'a>(xs: impl Iterator<Item = &'a RispExp>) -> RispExp {
+    RispExp::Stack(xs.fold(Rc::new(Cons::Nil), |list, x| Rc::new(Cons::Cell(x.clone(), list))))
+}
+
+fn queue_arg<'a>(op: &str, args: &'a [RispExp]) -> Result<&'a Rc<RispQueue>, RispErr> {
+    match args::get(op, args, 0, "a queue")? {
+        RispExp::Queue(queue) => Ok(queue),
+        exp => Err(args::wrong(op, 0, "a queue", exp)),
+    }
+}
+
+fn stack_arg<'a>(op: &str, args: &'a [RispExp]) -> Result<&'a Rc<Cons>, RispErr> {
+    match args::get(op, args, 0, "a stack")? {
+        RispExp::Stack(stack) => Ok(stack),
+        exp => Err(args::wrong(op, 0, "a stack", exp)),
+    }
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    // both print as tagged literals, `#queue(1,2)` reads back as the same queue
+    tagged::register(
+        "queue",
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                match args {
+                    [RispExp::List(xs)] => Ok(queue_of(xs)),
+                    _ => Err(RispErr::Reason("expected #queue to be followed by a list".to_string())),
+                }
+            }
+        )
+    );
+
+    // stacks print top first
+    tagged::register(
+        "stack",
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                match args {
+                    [RispExp::List(xs)] => Ok(stack_of(xs.iter().rev())),
+                    _ => Err(RispErr::Reason("expected #stack to be followed by a list".to_string())),
+                }
+            }
+        )
+    );
+
+    data.insert(
+        "queue".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                Ok(queue_of(args))
+            }
+        )
+    );
+
+    data.insert(
+        "enqueue".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                let queue = queue_arg("enqueue", args)?;
+                let x = args.get(1).ok_or(RispErr::Reason("expected a value to enqueue".to_string()))?;
+                let back = Rc::new(Cons::Cell(x.clone(), queue.back.clone()));
+
+                Ok(RispExp::Queue(Rc::new(RispQueue::new(queue.front.clone(), back))))
+            }
+        )
+    );
+
+    data.insert(
+        "dequeue".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                let queue = queue_arg("dequeue", args)?;
+                match queue.front.as_ref() {
+                    Cons::Cell(_, rest) => Ok(RispExp::Queue(Rc::new(RispQueue::new(rest.clone(), queue.back.clone())))),
+                    Cons::Nil => Err(RispErr::Reason("can not dequeue from an empty queue".to_string())),
+                }
+            }
+        )
+    );
+
+    data.insert(
+        "stack".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                Ok(stack_of(args.iter()))
+            }
+        )
+    );
+
+    data.insert(
+        "push".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                let stack = stack_arg("push", args)?;
+                let x = args.get(1).ok_or(RispErr::Reason("expected a value to push".to_string()))?;
+
+                Ok(RispExp::Stack(Rc::new(Cons::Cell(x.clone(), stack.clone()))))
+            }
+        )
+    );
+
+    data.insert(
+        "pop".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                match stack_arg("pop", args)?.as_ref() {
+                    Cons::Cell(_, rest) => Ok(RispExp::Stack(rest.clone())),
+                    Cons::Nil => Err(RispErr::Reason("can not pop from an empty stack".to_string())),
+                }
+            }
+        )
+    );
+
+    data.insert(
+        "peek".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                let list = match args::get("peek", args, 0, "a queue or stack")? {
+                    RispExp::Queue(queue) => &queue.front,
+                    RispExp::Stack(stack) => stack,
+                    exp => return Err(args::wrong("peek", 0, "a queue or stack", exp)),
+                };
+
+                match list.as_ref() {
+                    Cons::Cell(x, _) => Ok(x.clone()),
+                    Cons::Nil => Err(RispErr::Reason("can not peek into an empty collection".to_string())),
+                }
+            }
+        )
+    );
+}
@@ -0,0 +1,134 @@
+// Reader macros. A token starting with `#` is read by the macro registered
+// under the rest of the token, or as a tagged literal when there's none: `#inst "2024-01-01"` calls the `inst` macro
+// with the string that follows, `#"..."` calls the `""` macro with the
+// string. `#{` is special and reads the forms up to the matching `}` as one
+// list for the `{` macro. Each macro turns what was read into the form that
+// stands for it, so new syntax doesn't need changes to `tokenize` or `parse`.
+//
+// `register` is how the interpreter's own macros, installed along with the
+// default environment, and embedders add new ones, as
+// `risp::register_reader_macro`. Macros belong to the thread, every
+// interpreter on it reads with them:
+//
+//     risp::register_reader_macro("upper", |form| match form {
+//         RispExp::Str(s) => Ok(RispExp::Str(s.to_uppercase())),
+//         _ => Err(RispErr::Reason("expected a string after #upper".to_string())),
+//     });
+//
+// `#?(:files (glob "*.txt") :default (list))` reads as the form after the
+// first key that holds: an enabled feature, `(:version "0.2")` for this
+// version of risp or a later one, or `:default`. With none it reads as
+// `(begin)`, which evaluates to `nil`.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::tagged::Tagged;
+use super::{enabled_features, parse, parse_cache, read_delimited, RispErr, RispExp};
+
+pub type ReaderMacro = fn(RispExp) -> Result<RispExp, RispErr>;
+
+thread_local! {
+    static MACROS: RefCell<HashMap<String, ReaderMacro>> = RefCell::new(HashMap::new());
+}
+
+pub fn register(name: &str, f: ReaderMacro) {
+    let old = MACROS.with(|macros| macros.borrow_mut().insert(name.to_string(), f));
+    // the default macros are registered again with every environment, which
+    // doesn't change how anything reads
+    if old.map(|old| old as usize) != Some(f as usize) {
+        parse_cache::clear();
+    }
+}
+
+pub fn lookup(name: &str) -> Option<ReaderMacro> {
+    MACROS.with(|macros| macros.borrow().get(name).copied())
+}
+
+// reads whatever follows a `#name` token and hands it to that macro, without
+// a macro it's a tagged literal
+pub fn read_dispatch<'a>(name: &str, tokens: &'a [String]) -> Result<(RispExp, &'a [String]), RispErr> {
+    let f = match lookup(name) {
+        Some(f) => f,
+        None if name.is_empty() => return Err(RispErr::Reason("expected a tag after `#`".to_string())),
+        None => {
+            let (form, rest) = parse(tokens)?;
+            return Ok((RispExp::Tagged(Rc::new(Tagged::new(name.to_string(), form))), rest))
+        },
+    };
+
+    let (form, rest) = match name {
+        "{" => read_delimited(tokens, "}")?,
+        _ => parse(tokens)?,
+    };
+
+    Ok((f(form)?, rest))
+}
+
+pub fn add_default_macros() {
+    // `#{a b}` reads as `(sorted-set a b)`
+    register("{", |form| match form {
+        RispExp::List(mut xs) => {
+            xs.insert(0, RispExp::Symbol("sorted-set".to_string()));
+            Ok(RispExp::List(xs))
+        },
+        _ => Err(RispErr::Reason("expected set elements".to_string())),
+    });
+
+    register("?", read_conditional);
+
+    // `#inst "2024-01-01"` checks the date while reading, and reads as the string
+    register("inst", |form| match &form {
+        RispExp::Str(s) if is_date(s) => Ok(form),
+        _ => Err(RispErr::Reason(format!("expected a \"yyyy-mm-dd\" date after #inst, got '{}'", form))),
+    });
+}
+
+fn version_parts(version: &str) -> Vec<u64> {
+    version.split('.').map(|part| part.parse().unwrap_or(0)).collect()
+}
+
+fn holds(key: &RispExp) -> Result<bool, RispErr> {
+    match key {
+        RispExp::Keyword(key) if key == ":default" => Ok(true),
+        RispExp::Keyword(key) => Ok(enabled_features().contains(&key.trim_start_matches(':'))),
+        RispExp::List(key) => match &key[..] {
+            [RispExp::Keyword(name), RispExp::Str(version)] if name == ":version" =>
+                Ok(version_parts(env!("CARGO_PKG_VERSION")) >= version_parts(version)),
+            _ => Err(RispErr::Reason(format!("expected (:version \"x.y\"), got '{}'", RispExp::List(key.clone())))),
+        },
+        _ => Err(RispErr::Reason(format!("expected a feature, got '{}'", key))),
+    }
+}
+
+fn read_conditional(form: RispExp) -> Result<RispExp, RispErr> {
+    let branches = match form {
+        RispExp::List(branches) if branches.len() % 2 == 0 => branches,
+        _ => return Err(RispErr::Reason("expected feature and form pairs after #?".to_string())),
+    };
+
+    for branch in branches.chunks(2) {
+        if holds(&branch[0])? {
+            return Ok(branch[1].clone())
+        }
+    }
+
+    Ok(RispExp::List(vec![RispExp::Symbol("begin".to_string())].into()))
+}
+
+// `yyyy-mm-dd`, optionally followed by a `T` and a time that isn't checked
+fn is_date(s: &str) -> bool {
+    let date = s.split('T').next().unwrap_or("");
+    let parts: Vec<&str> = date.split('-').collect();
+    let number = |part: &str, len: usize| part.len() == len && part.chars().all(|c| c.is_ascii_digit());
+
+    match &parts[..] {
+        [y, m, d] if number(y, 4) && number(m, 2) && number(d, 2) => {
+            let month: u32 = m.parse().unwrap_or(0);
+            let day: u32 = d.parse().unwrap_or(0);
+            (1..=12).contains(&month) && (1..=31).contains(&day)
+        },
+        _ => false,
+    }
+}
@@ -0,0 +1,166 @@
+// A plain-text network REPL. Clients send forms a line at a time and get one
+// reply line back for each: what the forms printed, then `=> value` or
+// `// error` for every form on the line, in order. Forms and replies both
+// escape `\` and newlines as `\\` and `\n` to stay on one line. When the
+// server has a token, the first line a client sends must be `auth <token>`.
+//
+// Every connection evaluates against the same environment: connections are
+// read on their own threads, but forms are evaluated one at a time on the
+// thread that called `serve`.
+
+use std::io::{self, BufRead, BufReader, Error, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+use super::{err_message, eval, output, parse_forms, run_pending_finalizers, stats, RispEnv};
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:5555";
+
+struct Request {
+    expr: String,
+    reply: Sender<String>,
+}
+
+pub fn serve(addr: &str, token: Option<String>, env: &mut RispEnv) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    eprintln!("risp remote REPL listening on {}", listener.local_addr()?);
+    if token.is_none() {
+        eprintln!("warning: no --token given, any client that can connect may evaluate code");
+    }
+
+    let (tx, rx) = mpsc::channel::<Request>();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let tx = tx.clone();
+            let token = token.clone();
+            thread::spawn(move || {
+                if let Err(e) = handle_client(stream, tx, token) {
+                    eprintln!("remote client error: {}", e);
+                }
+            });
+        }
+    });
+
+    for Request { expr, reply } in rx {
+        let (results, captured) = output::capture(|| match parse_forms(expr) {
+            Ok(forms) => forms.iter().map(|form| stats::timed(|| eval(form, env))).collect(),
+            Err(e) => vec![Err(e)],
+        });
+
+        let mut lines: Vec<String> = vec![];
+        if !captured.stdout.is_empty() {
+            lines.push(captured.stdout.strip_suffix('\n').unwrap_or(&captured.stdout).to_string());
+        }
+        lines.extend(captured.warnings.into_iter().map(|warning| format!("warning: {}", warning)));
+        for res in results {
+            lines.push(match res {
+                Ok(res) => format!("=> {}", res),
+                Err(e) => format!("// {}", err_message(e)),
+            });
+        }
+        for e in run_pending_finalizers(env) {
+            lines.push(format!("// {}", err_message(e)));
+        }
+
+        // the client may have hung up while we were evaluating
+        let _ = reply.send(lines.join("\n"));
+    }
+
+    Ok(())
+}
+
+fn handle_client(stream: TcpStream, tx: Sender<Request>, token: Option<String>) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut lines = BufReader::new(stream).lines();
+
+    if let Some(token) = token {
+        let authed = match lines.next() {
+            Some(line) => line?
+                .strip_prefix("auth ")
+                .map(|given| tokens_match(given.trim(), &token))
+                .unwrap_or(false),
+            None => false,
+        };
+
+        if !authed {
+            return writeln!(writer, "// authentication failed")
+        }
+        writeln!(writer, "// authenticated")?;
+    }
+
+    for line in lines {
+        let expr = unescape_line(&line?);
+        if expr.trim().is_empty() {
+            continue
+        }
+
+        let (reply_tx, reply_rx) = mpsc::channel();
+        if tx.send(Request { expr, reply: reply_tx }).is_err() {
+            break
+        }
+
+        match reply_rx.recv() {
+            Ok(reply) => writeln!(writer, "{}", escape_line(&reply))?,
+            Err(_) => break,
+        }
+    }
+
+    Ok(())
+}
+
+// Sends each form to a running server, returning the replies in order.
+pub fn send(addr: &str, token: Option<String>, forms: &[String]) -> io::Result<Vec<String>> {
+    let stream = TcpStream::connect(addr)?;
+    let mut writer = stream.try_clone()?;
+    let mut lines = BufReader::new(stream).lines();
+    let mut next_line = move || lines.next().unwrap_or_else(
+        || Err(Error::new(ErrorKind::UnexpectedEof, "server closed the connection"))
+    );
+
+    if let Some(token) = token {
+        writeln!(writer, "auth {}", token)?;
+        let reply = next_line()?;
+        if reply != "// authenticated" {
+            return Err(Error::new(ErrorKind::PermissionDenied, reply))
+        }
+    }
+
+    forms
+        .iter()
+        .map(|form| {
+            writeln!(writer, "{}", escape_line(form))?;
+            next_line().map(|line| unescape_line(&line))
+        })
+        .collect()
+}
+
+pub fn escape_line(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+pub fn unescape_line(line: &str) -> String {
+    let mut text = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some('n')) => {
+                text.push('\n');
+                chars.next();
+            },
+            ('\\', Some('\\')) => {
+                text.push('\\');
+                chars.next();
+            },
+            _ => text.push(c),
+        }
+    }
+
+    text
+}
+
+// compares every byte so response timing doesn't leak how much of a guess matched
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given.bytes().zip(expected.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
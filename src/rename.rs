@@ -0,0 +1,233 @@
+// `risp rename old new path...` renames a symbol everywhere it's defined or
+// referred to in the `.risp` files under the paths. The files are parsed, so
+// strings, comments and quoted data are left alone, as are the fn and `let`
+// bindings shadowing the name. Only the symbols themselves are rewritten, the
+// rest of each file, comments and layout included, stays as it was.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{err_message, graph, parse_atom, parse_forms, tokenize_spanned, Pos, RispExp};
+
+enum Node {
+    // the index of its token
+    Atom(usize),
+    List(Vec<Node>),
+    Quoted,
+}
+
+// the form starting at `tokens[*at]`, moving `at` past it
+fn node(tokens: &[(String, Pos)], at: &mut usize) -> Option<Node> {
+    let idx = *at;
+    let (token, _) = tokens.get(idx)?;
+    *at += 1;
+
+    match token.as_str() {
+        "(" | "{" | "#{" | "[" => {
+            let mut children = vec![];
+            while let Some((token, _)) = tokens.get(*at) {
+                if token == ")" || token == "}" || token == "]" {
+                    *at += 1;
+                    break
+                }
+                children.extend(node(tokens, at));
+            }
+            Some(Node::List(children))
+        },
+        "'" => {
+            node(tokens, at);
+            Some(Node::Quoted)
+        },
+        // the rest of the shorthand, and tags, are followed by code
+        "`" | "," | ",@" | "@" => node(tokens, at),
+        _ if token.starts_with("#\\") => Some(Node::Atom(idx)),
+        _ if token.starts_with('#') => node(tokens, at),
+        _ => Some(Node::Atom(idx)),
+    }
+}
+
+// the symbol a token names, `@xs` spreading `xs`
+fn symbol(token: &str) -> &str {
+    token.strip_prefix('@').filter(|s| !s.is_empty()).unwrap_or(token)
+}
+
+fn head<'t>(nodes: &[Node], tokens: &'t [(String, Pos)]) -> Option<&'t str> {
+    match nodes.first() {
+        Some(Node::Atom(idx)) => Some(tokens[*idx].0.as_str()),
+        _ => None,
+    }
+}
+
+// whether a param list binds `name`, including annotated params like `(x : number)`
+fn binds(params: Option<&Node>, name: &str, tokens: &[(String, Pos)]) -> bool {
+    let bound = |node: &Node| match node {
+        Node::Atom(idx) => symbol(&tokens[*idx].0) == name,
+        Node::List(annotated) => head(annotated, tokens) == Some(name),
+        Node::Quoted => false,
+    };
+
+    match params {
+        Some(Node::List(params)) => params.iter().any(bound),
+        Some(param) => bound(param),
+        None => false,
+    }
+}
+
+struct Renaming<'t> {
+    tokens: &'t [(String, Pos)],
+    old: &'t str,
+    // the tokens to rename
+    found: Vec<usize>,
+}
+
+impl Renaming<'_> {
+    fn walk_all(&mut self, nodes: &[Node], shadowed: bool) {
+        nodes.iter().for_each(|node| self.walk(node, shadowed))
+    }
+
+    fn walk(&mut self, node: &Node, shadowed: bool) {
+        let list = match node {
+            Node::Atom(idx) => {
+                if !shadowed && symbol(&self.tokens[*idx].0) == self.old {
+                    self.found.push(*idx);
+                }
+                return
+            },
+            Node::List(list) => list,
+            Node::Quoted => return,
+        };
+
+        match head(list, self.tokens) {
+            Some("quote") => (),
+            Some("fn") => {
+                let shadowed = shadowed || binds(list.get(1), self.old, self.tokens);
+                self.walk_all(list.get(2..).unwrap_or_default(), shadowed);
+            },
+            Some("defn") | Some("defmacro") => {
+                self.walk_all(&list[..list.len().min(2)], shadowed);
+                let shadowed = shadowed || binds(list.get(2), self.old, self.tokens);
+                self.walk_all(list.get(3..).unwrap_or_default(), shadowed);
+            },
+            // each binding can see the ones before it
+            Some("let") => {
+                let mut shadowed = shadowed;
+                if let Some(Node::List(bindings)) = list.get(1) {
+                    for binding in bindings {
+                        match binding {
+                            Node::List(binding) => {
+                                self.walk_all(binding.get(1..).unwrap_or_default(), shadowed);
+                                shadowed = shadowed || binds(binding.first(), self.old, self.tokens);
+                            },
+                            _ => self.walk(binding, shadowed),
+                        }
+                    }
+                }
+                self.walk_all(list.get(2..).unwrap_or_default(), shadowed);
+            },
+            _ => self.walk_all(list, shadowed),
+        }
+    }
+}
+
+// `src` with every reference to `old` renamed to `new`, and how many there were
+pub fn rename(src: &str, old: &str, new: &str) -> (String, usize) {
+    let tokens = tokenize_spanned(src);
+    let mut renaming = Renaming { tokens: &tokens, old, found: vec![] };
+    let mut at = 0;
+    while let Some(node) = node(&tokens, &mut at) {
+        renaming.walk(&node, false);
+    }
+
+    let mut starts: Vec<(usize, usize)> =
+        renaming.found.iter().map(|idx| (tokens[*idx].1.line, tokens[*idx].1.col)).collect();
+    starts.sort();
+
+    let mut out = String::new();
+    let mut pos = (1, 1);
+    let mut chars = src.chars().peekable();
+    for start in &starts {
+        while pos != *start {
+            let c = match chars.next() {
+                Some(c) => c,
+                None => break,
+            };
+            out.push(c);
+            pos = if c == '\n' { (pos.0 + 1, 1) } else { (pos.0, pos.1 + 1) };
+        }
+        // a symbol token doesn't span lines, so only the column moves past it
+        if chars.peek() == Some(&'@') {
+            out.push('@');
+            chars.next();
+            pos.1 += 1;
+        }
+        out.push_str(new);
+        for _ in 0..old.chars().count() {
+            chars.next();
+        }
+        pos.1 += old.chars().count();
+    }
+    out.extend(chars);
+
+    (out, starts.len())
+}
+
+// the `.risp` files at `path`, in the directories below it too
+fn risp_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(())
+    }
+
+    let entries = fs::read_dir(path).map_err(|e| format!("could not read '{}': {}", path.display(), e))?;
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() || path.extension().is_some_and(|ext| ext == "risp") {
+            risp_files(&path, files)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn rename_files(old: &str, new: &str, paths: &[String]) -> Result<(), String> {
+    for name in [old, new] {
+        let is_symbol = matches!(parse_atom(name), RispExp::Symbol(_))
+            && matches!(&tokenize_spanned(name)[..], [(token, _)] if token == name);
+        if !is_symbol {
+            return Err(format!("'{}' isn't a symbol", name))
+        }
+    }
+
+    let mut files = vec![];
+    for path in paths {
+        risp_files(Path::new(path), &mut files)?;
+    }
+
+    // every file has to parse, and can't already define the new name, before
+    // any of them are changed
+    let mut sources = vec![];
+    for path in &files {
+        let src = fs::read_to_string(path).map_err(|e| format!("could not read '{}': {}", path.display(), e))?;
+        let forms = parse_forms(src.clone()).map_err(|e| format!("{}: {}", path.display(), err_message(e)))?;
+        if graph::graph(&forms).deps.iter().any(|(name, _)| name == new) {
+            return Err(format!("{}: '{}' is already defined", path.display(), new))
+        }
+        sources.push(src);
+    }
+
+    let mut renamed = 0;
+    for (path, src) in files.iter().zip(sources) {
+        let (out, count) = rename(&src, old, new);
+        if count > 0 {
+            fs::write(path, out).map_err(|e| format!("could not write '{}': {}", path.display(), e))?;
+            println!("{}: {}", path.display(), count);
+            renamed += count;
+        }
+    }
+    if renamed == 0 {
+        println!("no references to '{}'", old);
+    }
+
+    Ok(())
+}
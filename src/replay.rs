@@ -0,0 +1,116 @@
+// Recording and replaying what a script can't reproduce on its own. With
+// `--record log` every result of `rand`, `now-ms`, `read-line`, `slurp`,
+// `glob`, `read-key` and each line `with-lines` reads is appended to the log,
+// and with `--replay log` those builtins hand back the logged results in order
+// instead, so a run that failed once can be stepped through again exactly as
+// it happened.
+//
+// Each line of the log is the builtin's name and its result as EDN.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{as_float, edn, RispErr, RispExp};
+
+enum Mode {
+    Live,
+    Record(File),
+    Replay(VecDeque<(String, String)>),
+}
+
+thread_local! {
+    static MODE: RefCell<Mode> = const { RefCell::new(Mode::Live) };
+    static SEED: Cell<u64> = const { Cell::new(0) };
+}
+
+pub fn record(path: &str) -> Result<(), String> {
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(path)
+        .map_err(|e| format!("could not write '{}': {}", path, e))?;
+    MODE.with(|mode| *mode.borrow_mut() = Mode::Record(file));
+
+    Ok(())
+}
+
+pub fn replay(path: &str) -> Result<(), String> {
+    let log = fs::read_to_string(path).map_err(|e| format!("could not read '{}': {}", path, e))?;
+    let entries = log
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.split_once(' ') {
+            Some((name, value)) => Ok((name.to_string(), value.to_string())),
+            None => Err(format!("{}: expected `name value` lines, got '{}'", path, line)),
+        })
+        .collect::<Result<_, String>>()?;
+    MODE.with(|mode| *mode.borrow_mut() = Mode::Replay(entries));
+
+    Ok(())
+}
+
+// the result of `live` for builtin `name`, or the logged one when replaying
+pub fn recorded(name: &str, live: impl FnOnce() -> Result<RispExp, RispErr>) -> Result<RispExp, RispErr> {
+    MODE.with(|mode| match &mut *mode.borrow_mut() {
+        Mode::Live => live(),
+        Mode::Record(file) => {
+            let value = live()?;
+            writeln!(file, "{} {}", name, edn::write_exp(&value)?)
+                .map_err(|e| RispErr::Reason(format!("could not record {}: {}", name, e)))?;
+            Ok(value)
+        },
+        // an entry for another builtin is left for it, the script has gone
+        // off the recorded path
+        Mode::Replay(entries) => match entries.front() {
+            Some((logged, _)) if logged == name => edn::read_data(&entries.pop_front().expect("front entry").1),
+            Some((logged, _)) => Err(RispErr::Reason(format!("replay expected a call to {}, not {}", logged, name))),
+            None => Err(RispErr::Reason(format!("replay log ran out at a call to {}", name))),
+        },
+    })
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+// xorshift64*, seeded from the clock the first time it's used
+fn next_random() -> f64 {
+    SEED.with(|seed| {
+        let mut x = match seed.get() {
+            0 => now() | 1,
+            x => x,
+        };
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        seed.set(x);
+        (x.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11) as f64 / (1u64 << 53) as f64
+    })
+}
+
+// `(rand)` is a number from 0 up to 1 and `(rand n)` a whole number below n
+fn rand(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let below = match args.iter().map(as_float).collect::<Option<Vec<f64>>>().as_deref() {
+        Some([]) => None,
+        Some([n]) if *n >= 1.0 => Some(n.floor()),
+        _ => return Err(RispErr::Reason("expected nothing or a number of at least 1".to_string())),
+    };
+
+    recorded("rand", || match below {
+        Some(n) => Ok(RispExp::Int((next_random() * n) as i64)),
+        None => Ok(RispExp::Number(next_random())),
+    })
+}
+
+fn now_ms(_args: &[RispExp]) -> Result<RispExp, RispErr> {
+    recorded("now-ms", || Ok(RispExp::Int(now() as i64)))
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert("rand".to_string(), RispExp::Func(rand));
+    data.insert("now-ms".to_string(), RispExp::Func(now_ms));
+}
@@ -0,0 +1,126 @@
+// Host objects handed to risp programs, like open files, are resources: the
+// object behind an opaque id and a kind tag such as `file`, so builtins for
+// new kinds of handles share one way of checking what they were given,
+// closing and finalizing.
+//
+// A resource is closed once, either by hand or when it's collected. Closing
+// it again is an error rather than a second close of the host object, and
+// using it after it's closed reports which handle it was. Finalizers only run
+// for resources collected while still open.
+
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::Rc;
+
+use super::{RispErr, RispExp};
+
+// runs when a resource is collected while still open
+pub type Finalizer = Box<dyn FnOnce(&mut Resource)>;
+
+thread_local! {
+    static NEXT_ID: Cell<u64> = const { Cell::new(1) };
+}
+
+pub struct Resource {
+    id: u64,
+    kind: &'static str,
+    // what the resource is for people reading it, like the path of a file
+    label: String,
+    object: Option<Box<dyn Any>>,
+    finalizers: Vec<Finalizer>,
+}
+
+impl Resource {
+    pub fn new<T: Any>(kind: &'static str, label: String, object: T) -> Resource {
+        let id = NEXT_ID.with(|next| next.replace(next.get() + 1));
+        Resource { id, kind, label, object: Some(Box::new(object)), finalizers: vec![] }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.object.is_some()
+    }
+
+    pub fn get_mut<T: Any>(&mut self) -> Result<&mut T, RispErr> {
+        let name = self.to_string();
+        self.object
+            .as_mut()
+            .ok_or(RispErr::Reason(format!("{} is closed", name)))?
+            .downcast_mut()
+            .ok_or(RispErr::Reason(format!("{} does not hold the expected host object", name)))
+    }
+
+    pub fn add_finalizer(&mut self, finalizer: Finalizer) {
+        self.finalizers.push(finalizer);
+    }
+
+    // closing by hand means the resource no longer needs finalizing
+    pub fn close(&mut self) -> Result<(), RispErr> {
+        match self.release() {
+            true => Ok(()),
+            false => Err(RispErr::Reason(format!("{} is already closed", self))),
+        }
+    }
+
+    // closes the resource if it's still open, whether it was is returned
+    pub fn release(&mut self) -> bool {
+        self.finalizers.clear();
+        self.object.take().is_some()
+    }
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#<{} {} {}>", self.kind, self.id, self.label)
+    }
+}
+
+impl fmt::Debug for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Resource")
+            .field("id", &self.id)
+            .field("kind", &self.kind)
+            .field("label", &self.label)
+            .field("open", &self.is_open())
+            .field("finalizers", &self.finalizers.len())
+            .finish()
+    }
+}
+
+impl Drop for Resource {
+    fn drop(&mut self) {
+        if !self.is_open() {
+            return
+        }
+
+        for finalizer in std::mem::take(&mut self.finalizers) {
+            finalizer(self);
+        }
+        self.object.take();
+    }
+}
+
+pub fn wrap<T: Any>(kind: &'static str, label: String, object: T) -> RispExp {
+    RispExp::Resource(Rc::new(RefCell::new(Resource::new(kind, label, object))))
+}
+
+pub fn any(exp: Option<&RispExp>) -> Result<Rc<RefCell<Resource>>, RispErr> {
+    match exp {
+        Some(RispExp::Resource(resource)) => Ok(resource.clone()),
+        _ => Err(RispErr::Reason("expected a resource like a file".to_string())),
+    }
+}
+
+// the resource `exp` refers to, checked to be of `kind`
+pub fn expect(exp: Option<&RispExp>, kind: &str) -> Result<Rc<RefCell<Resource>>, RispErr> {
+    match exp {
+        Some(RispExp::Resource(resource)) if resource.borrow().kind() == kind => Ok(resource.clone()),
+        Some(RispExp::Resource(resource)) =>
+            Err(RispErr::Reason(format!("expected a {}, got {}", kind, resource.borrow()))),
+        _ => Err(RispErr::Reason(format!("expected a {}", kind))),
+    }
+}
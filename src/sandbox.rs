@@ -0,0 +1,69 @@
+// An optional directory the file builtins are confined to. With a root set,
+// a relative path a script gives is taken relative to it and an absolute one
+// has to be inside it, and either is resolved through any symlinks before
+// it's used, so neither `..` nor a link pointing outside can reach a file
+// elsewhere. The root is set on an `Interpreter` and applies while it's
+// evaluating.
+
+use std::cell::RefCell;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use super::RispErr;
+
+thread_local! {
+    static ROOT: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+// runs `f` with file builtins confined to `root`, or to nothing for `None`
+pub fn with_root<T>(root: Option<&Path>, f: impl FnOnce() -> T) -> T {
+    let outer = ROOT.with(|current| current.replace(root.map(Path::to_path_buf)));
+    let res = f();
+    ROOT.with(|current| current.replace(outer));
+    res
+}
+
+fn outside(path: &str) -> RispErr {
+    RispErr::Reason(format!("'{}' is outside of the directory scripts can access", path))
+}
+
+// the path builtins should use for `path`, the path itself without a root
+pub fn resolve(path: &str) -> Result<PathBuf, RispErr> {
+    let root = match ROOT.with(|root| root.borrow().clone()) {
+        Some(root) => root,
+        None => return Ok(PathBuf::from(path)),
+    };
+
+    let mut joined = match Path::new(path).is_absolute() {
+        true => PathBuf::new(),
+        false => root.clone(),
+    };
+    for component in Path::new(path).components() {
+        match component {
+            Component::CurDir => (),
+            _ => joined.push(component),
+        }
+    }
+
+    // a file that's about to be created doesn't exist yet, so the deepest
+    // directory that does is resolved and the rest added back on
+    let mut existing = joined.as_path();
+    let mut missing: Vec<OsString> = vec![];
+    let resolved = loop {
+        match fs::canonicalize(existing) {
+            Ok(resolved) => break resolved,
+            Err(_) => {
+                let name = existing.file_name().ok_or_else(|| outside(path))?;
+                missing.push(name.to_os_string());
+                existing = existing.parent().ok_or_else(|| outside(path))?;
+            },
+        }
+    };
+
+    let resolved = missing.iter().rev().fold(resolved, |resolved, name| resolved.join(name));
+    match resolved.starts_with(&root) {
+        true => Ok(resolved),
+        false => Err(outside(path)),
+    }
+}
@@ -0,0 +1,317 @@
+// Ordered, tree-backed `sorted-map` and `sorted-set` collections. Keys can be
+// booleans, numbers, strings, symbols and lists of those; values of different
+// kinds sort by kind first, in that order.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::rc::Rc;
+
+use super::hashmap::MapKey;
+use super::{args, meta, RispErr, RispExp};
+
+#[derive(Clone, Debug)]
+pub struct SortKey(RispExp);
+
+impl SortKey {
+    pub fn new(exp: RispExp) -> Result<SortKey, RispErr> {
+        if is_sortable(&exp) {
+            Ok(SortKey(exp))
+        } else {
+            Err(RispErr::Reason(format!("'{}' can not be used as a sorted key", exp)))
+        }
+    }
+
+    pub fn exp(&self) -> &RispExp {
+        &self.0
+    }
+}
+
+impl Ord for SortKey {
+    fn cmp(&self, other: &SortKey) -> Ordering {
+        compare_keys(&self.0, &other.0)
+    }
+}
+
+impl PartialOrd for SortKey {
+    fn partial_cmp(&self, other: &SortKey) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for SortKey {
+    fn eq(&self, other: &SortKey) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SortKey {}
+
+fn is_sortable(exp: &RispExp) -> bool {
+    match exp {
+        RispExp::Bool(_) | RispExp::Number(_) | RispExp::Int(_) | RispExp::Str(_) | RispExp::Char(_) | RispExp::Symbol(_) | RispExp::Keyword(_) => true,
+        RispExp::List(list) => list.iter().all(is_sortable),
+        _ => false,
+    }
+}
+
+fn kind_rank(exp: &RispExp) -> u8 {
+    match exp {
+        RispExp::Bool(_) => 0,
+        RispExp::Number(_) | RispExp::Int(_) => 1,
+        RispExp::Str(_) => 2,
+        RispExp::Char(_) => 3,
+        RispExp::Symbol(_) => 4,
+        RispExp::Keyword(_) => 5,
+        _ => 6,
+    }
+}
+
+fn compare_keys(a: &RispExp, b: &RispExp) -> Ordering {
+    match (a, b) {
+        (RispExp::Bool(a), RispExp::Bool(b)) => a.cmp(b),
+        (RispExp::Number(a), RispExp::Number(b)) => a.total_cmp(b),
+        (RispExp::Int(a), RispExp::Int(b)) => a.cmp(b),
+        // by value, an int before the float equal to it
+        (RispExp::Int(a), RispExp::Number(b)) => (*a as f64).total_cmp(b).then(Ordering::Less),
+        (RispExp::Number(a), RispExp::Int(b)) => a.total_cmp(&(*b as f64)).then(Ordering::Greater),
+        (RispExp::Str(a), RispExp::Str(b)) => a.cmp(b),
+        (RispExp::Char(a), RispExp::Char(b)) => a.cmp(b),
+        (RispExp::Symbol(a), RispExp::Symbol(b)) => a.cmp(b),
+        (RispExp::Keyword(a), RispExp::Keyword(b)) => a.cmp(b),
+        (RispExp::List(a), RispExp::List(b)) => a
+            .iter()
+            .zip(b.iter())
+            .map(|(a, b)| compare_keys(a, b))
+            .find(|ord| *ord != Ordering::Equal)
+            .unwrap_or(a.len().cmp(&b.len())),
+        _ => kind_rank(a).cmp(&kind_rank(b)),
+    }
+}
+
+pub fn display_map(map: &BTreeMap<SortKey, RispExp>) -> String {
+    let xs: Vec<String> = map
+        .iter()
+        .map(|(k, v)| format!("{} {}", k.0, v))
+        .collect();
+    format!("{{{}}}", xs.join(", "))
+}
+
+pub fn display_set(set: &BTreeSet<SortKey>) -> String {
+    let xs: Vec<String> = set
+        .iter()
+        .map(|k| k.0.to_string())
+        .collect();
+    format!("#{{{}}}", xs.join(","))
+}
+
+// what iterating over the collection produces: set members, or `(key value)` lists
+pub fn entries(exp: &RispExp) -> Option<Vec<RispExp>> {
+    match exp {
+        RispExp::SortedMap(map) => Some(
+            map.iter()
+                .map(|(k, v)| RispExp::List(vec![k.0.clone(), v.clone()].into()))
+                .collect()
+        ),
+        RispExp::SortedSet(set) => Some(set.iter().map(|k| k.0.clone()).collect()),
+        _ => None,
+    }
+}
+
+fn key_arg(op: &str, args: &[RispExp], idx: usize) -> Result<SortKey, RispErr> {
+    SortKey::new(args::get(op, args, idx, "a key")?.clone())
+}
+
+fn map_key_arg(op: &str, args: &[RispExp], idx: usize) -> Result<MapKey, RispErr> {
+    MapKey::new(args::get(op, args, idx, "a key")?)
+}
+
+fn key_exp(key: &SortKey) -> RispExp {
+    key.0.clone()
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert(
+        "sorted-map".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                if !args.len().is_multiple_of(2) {
+                    return Err(RispErr::Reason("expected keys and values to come in pairs".to_string()))
+                }
+
+                let mut map = BTreeMap::new();
+                for pair in args.chunks(2) {
+                    map.insert(SortKey::new(pair[0].clone())?, pair[1].clone());
+                }
+
+                Ok(RispExp::SortedMap(Rc::new(map)))
+            }
+        )
+    );
+
+    data.insert(
+        "sorted-set".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                let set = args
+                    .iter()
+                    .map(|x| SortKey::new(x.clone()))
+                    .collect::<Result<BTreeSet<SortKey>, RispErr>>()?;
+
+                Ok(RispExp::SortedSet(Rc::new(set)))
+            }
+        )
+    );
+
+    data.insert(
+        "subrange".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                let from = key_arg("subrange", args, 1)?;
+                let to = key_arg("subrange", args, 2)?;
+                if from > to {
+                    return Ok(match args.first() {
+                        Some(RispExp::SortedSet(_)) => RispExp::SortedSet(Rc::new(BTreeSet::new())),
+                        _ => RispExp::SortedMap(Rc::new(BTreeMap::new())),
+                    })
+                }
+
+                match args::get("subrange", args, 0, "a sorted collection")? {
+                    RispExp::SortedMap(map) => Ok(RispExp::SortedMap(Rc::new(
+                        map.range(from..to).map(|(k, v)| (k.clone(), v.clone())).collect()
+                    ))),
+                    RispExp::SortedSet(set) => Ok(RispExp::SortedSet(Rc::new(
+                        set.range(from..to).cloned().collect()
+                    ))),
+                    exp => Err(args::wrong("subrange", 0, "a sorted collection", exp)),
+                }
+            }
+        )
+    );
+
+    data.insert(
+        "first-key".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                let key = match args::get("first-key", args, 0, "a sorted collection")? {
+                    RispExp::SortedMap(map) => map.keys().next(),
+                    RispExp::SortedSet(set) => set.iter().next(),
+                    exp => return Err(args::wrong("first-key", 0, "a sorted collection", exp)),
+                };
+
+                Ok(key.map(key_exp).unwrap_or(RispExp::Bool(false)))
+            }
+        )
+    );
+
+    data.insert(
+        "last-key".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                let key = match args::get("last-key", args, 0, "a sorted collection")? {
+                    RispExp::SortedMap(map) => map.keys().next_back(),
+                    RispExp::SortedSet(set) => set.iter().next_back(),
+                    exp => return Err(args::wrong("last-key", 0, "a sorted collection", exp)),
+                };
+
+                Ok(key.map(key_exp).unwrap_or(RispExp::Bool(false)))
+            }
+        )
+    );
+
+    data.insert(
+        "contains?".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                match args::get("contains?", args, 0, "a collection")? {
+                    RispExp::SortedMap(map) => Ok(RispExp::Bool(map.contains_key(&key_arg("contains?", args, 1)?))),
+                    RispExp::SortedSet(set) => Ok(RispExp::Bool(set.contains(&key_arg("contains?", args, 1)?))),
+                    RispExp::Map(map) => Ok(RispExp::Bool(map.contains_key(&map_key_arg("contains?", args, 1)?))),
+                    exp => Err(args::wrong("contains?", 0, "a collection", exp)),
+                }
+            }
+        )
+    );
+
+    data.insert(
+        "dissoc".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                match args::get("dissoc", args, 0, "a map")? {
+                    RispExp::SortedMap(map) => {
+                        let mut map = map.as_ref().clone();
+                        map.remove(&key_arg("dissoc", args, 1)?);
+
+                        Ok(meta::carry(&args[0], RispExp::SortedMap(Rc::new(map))))
+                    },
+                    RispExp::Map(map) => {
+                        let mut map = map.as_ref().clone();
+                        map.remove(&map_key_arg("dissoc", args, 1)?);
+
+                        Ok(meta::carry(&args[0], RispExp::Map(Rc::new(map))))
+                    },
+                    exp => Err(args::wrong("dissoc", 0, "a map", exp)),
+                }
+            }
+        )
+    );
+
+    data.insert(
+        "conj".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                match args::get("conj", args, 0, "a set")? {
+                    RispExp::SortedSet(set) => {
+                        let mut set = set.as_ref().clone();
+                        set.insert(key_arg("conj", args, 1)?);
+
+                        Ok(meta::carry(&args[0], RispExp::SortedSet(Rc::new(set))))
+                    },
+                    exp => Err(args::wrong("conj", 0, "a set", exp)),
+                }
+            }
+        )
+    );
+
+    data.insert(
+        "disj".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                match args::get("disj", args, 0, "a set")? {
+                    RispExp::SortedSet(set) => {
+                        let mut set = set.as_ref().clone();
+                        set.remove(&key_arg("disj", args, 1)?);
+
+                        Ok(meta::carry(&args[0], RispExp::SortedSet(Rc::new(set))))
+                    },
+                    exp => Err(args::wrong("disj", 0, "a set", exp)),
+                }
+            }
+        )
+    );
+
+    data.insert(
+        "keys".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                match args::get("keys", args, 0, "a map")? {
+                    RispExp::SortedMap(map) => Ok(RispExp::List(map.keys().map(key_exp).collect())),
+                    RispExp::Map(map) => Ok(RispExp::List(map.keys().map(MapKey::exp).collect())),
+                    exp => Err(args::wrong("keys", 0, "a map", exp)),
+                }
+            }
+        )
+    );
+
+    data.insert(
+        "vals".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                match args::get("vals", args, 0, "a map")? {
+                    RispExp::SortedMap(map) => Ok(RispExp::List(map.values().cloned().collect())),
+                    RispExp::Map(map) => Ok(RispExp::List(map.values().cloned().collect())),
+                    exp => Err(args::wrong("vals", 0, "a map", exp)),
+                }
+            }
+        )
+    );
+}
@@ -0,0 +1,173 @@
+// Specs describe the shape of data and are plain values:
+//
+// - a predicate function, like `number?`, that must return `true`
+// - a map of key -> spec, for maps that must have those keys
+// - a list of specs, for lists with one valid element per spec
+//
+// `instrument` wraps a function so its arguments and return value are checked
+// against specs on every call.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{call_exp, parse_options, RispEnv, RispErr, RispExp};
+
+#[derive(Debug)]
+pub struct Instrumented {
+    f: RispExp,
+    args: Option<RispExp>,
+    ret: Option<RispExp>,
+}
+
+fn is_spec(spec: &RispExp) -> bool {
+    match spec {
+        RispExp::Func(_) | RispExp::EnvFunc(_) | RispExp::Lambda(_) | RispExp::Instrumented(_) => true,
+        RispExp::SortedMap(map) => map.values().all(is_spec),
+        RispExp::Map(map) => map.values().all(is_spec),
+        RispExp::List(specs) => specs.iter().all(is_spec),
+        _ => false,
+    }
+}
+
+pub fn check_spec(spec: &RispExp) -> Result<(), RispErr> {
+    if is_spec(spec) {
+        Ok(())
+    } else {
+        Err(RispErr::Reason(format!("'{}' is not a spec", spec)))
+    }
+}
+
+// the key specs of a map spec, a hash map's in order too like a sorted one's
+fn key_specs(spec: &RispExp) -> Vec<(RispExp, RispExp)> {
+    match spec {
+        RispExp::SortedMap(specs) => specs.iter().map(|(k, v)| (k.exp().clone(), v.clone())).collect(),
+        RispExp::Map(specs) => {
+            let mut specs: Vec<_> = specs.iter().map(|(k, v)| (k.exp(), v.clone())).collect();
+            specs.sort_by_key(|(k, _)| k.to_string());
+            specs
+        },
+        _ => vec![],
+    }
+}
+
+// `None` when the value conforms, otherwise why it doesn't
+pub fn explain(spec: &RispExp, value: &RispExp, env: &mut RispEnv) -> Result<Option<String>, RispErr> {
+    match spec {
+        RispExp::SortedMap(_) | RispExp::Map(_) => {
+            for (key, key_spec) in key_specs(spec) {
+                let key_value = match value {
+                    RispExp::SortedMap(_) | RispExp::Map(_) => super::access::get_key(value, &key)?,
+                    _ => return Ok(Some(format!("expected a map, got '{}'", value))),
+                };
+
+                match key_value {
+                    Some(key_value) => {
+                        if let Some(why) = explain(&key_spec, &key_value, env)? {
+                            return Ok(Some(format!("at {}: {}", key, why)))
+                        }
+                    },
+                    None => return Ok(Some(format!("missing key {}", key))),
+                }
+            }
+
+            Ok(None)
+        },
+        RispExp::List(specs) => {
+            let values = match value {
+                RispExp::List(values) => values,
+                _ => return Ok(Some(format!("expected a list, got '{}'", value))),
+            };
+
+            if values.len() != specs.len() {
+                return Ok(Some(format!("expected {} elements, got {}", specs.len(), values.len())))
+            }
+
+            for (idx, (elem_spec, elem)) in specs.iter().zip(values.iter()).enumerate() {
+                if let Some(why) = explain(elem_spec, elem, env)? {
+                    return Ok(Some(format!("at {}: {}", idx, why)))
+                }
+            }
+
+            Ok(None)
+        },
+        _ => match call_exp(spec, vec![value.clone()], env)? {
+            RispExp::Bool(true) => Ok(None),
+            _ => Ok(Some(format!("'{}' failed its predicate", value))),
+        },
+    }
+}
+
+fn spec_and_value(args: &[RispExp]) -> Result<(&RispExp, &RispExp), RispErr> {
+    match args {
+        [spec, value] => {
+            check_spec(spec)?;
+            Ok((spec, value))
+        },
+        _ => Err(RispErr::Reason("expected a spec and a value".to_string())),
+    }
+}
+
+// `(valid? spec value)`
+fn valid(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let (spec, value) = spec_and_value(args)?;
+    Ok(RispExp::Bool(explain(spec, value, env)?.is_none()))
+}
+
+// `(conform spec value)` is the value itself when valid, `:invalid` otherwise
+fn conform(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let (spec, value) = spec_and_value(args)?;
+    match explain(spec, value, env)? {
+        None => Ok(value.clone()),
+        Some(_) => Ok(RispExp::Keyword(":invalid".to_string())),
+    }
+}
+
+// `(explain-spec spec value)` is why the value doesn't conform, or `true`
+fn explain_args(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let (spec, value) = spec_and_value(args)?;
+    match explain(spec, value, env)? {
+        None => Ok(RispExp::Bool(true)),
+        Some(why) => Ok(RispExp::Str(why)),
+    }
+}
+
+// `(instrument f :args (list spec...) :ret spec)`
+fn instrument(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let f = match args.first() {
+        Some(f @ RispExp::Func(_)) | Some(f @ RispExp::EnvFunc(_)) | Some(f @ RispExp::Lambda(_)) | Some(f @ RispExp::Instrumented(_)) => f.clone(),
+        _ => return Err(RispErr::Reason("expected a function to instrument".to_string())),
+    };
+
+    let options = parse_options(&args[1..], &[":args", ":ret"])?;
+    let args_spec = options.get(":args").cloned();
+    let ret_spec = options.get(":ret").cloned();
+    for spec in args_spec.iter().chain(ret_spec.iter()) {
+        check_spec(spec)?;
+    }
+
+    Ok(RispExp::Instrumented(Rc::new(Instrumented { f, args: args_spec, ret: ret_spec })))
+}
+
+pub fn call_instrumented(inst: &Instrumented, args: Vec<RispExp>, env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    if let Some(spec) = &inst.args {
+        if let Some(why) = explain(spec, &RispExp::List(args.clone().into()), env)? {
+            return Err(RispErr::Reason(format!("arguments don't conform to their spec, {}", why)))
+        }
+    }
+
+    let res = call_exp(&inst.f, args, env)?;
+    if let Some(spec) = &inst.ret {
+        if let Some(why) = explain(spec, &res, env)? {
+            return Err(RispErr::Reason(format!("return value doesn't conform to its spec, {}", why)))
+        }
+    }
+
+    Ok(res)
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert("instrument".to_string(), RispExp::Func(instrument));
+    data.insert("valid?".to_string(), RispExp::EnvFunc(valid));
+    data.insert("conform".to_string(), RispExp::EnvFunc(conform));
+    data.insert("explain-spec".to_string(), RispExp::EnvFunc(explain_args));
+}
@@ -0,0 +1,301 @@
+// Usage statistics for the current session, reported by `(session-stats)`.
+// Nothing is written or sent anywhere, the numbers only exist in this process
+// until something asks for them.
+//
+// Allocations are counted by `CountingAllocator`, which wraps the system
+// allocator at the cost of an atomic increment per allocation. The library
+// doesn't install it, the `risp` binary does and so can an embedder:
+//
+//     #[global_allocator]
+//     static ALLOCATOR: risp::CountingAllocator = risp::CountingAllocator;
+//
+// Without it there's nothing counted, and `(session-stats)` leaves the
+// allocation counts out. The same counts per thread back the memory limit:
+// with one set, a top-level evaluation fails as soon as the memory it holds on
+// to grows past the limit. It's checked before each form is evaluated, and by
+// the builtins that make something as big as their arguments, like `append`
+// and `str-concat`, before they allocate it, so one call can't go far over.
+// Those fail on their own even without the allocator, when what they'd make
+// is bigger than the limit.
+//
+// Evaluation recurses in Rust, so a deep enough risp recursion would overflow
+// the thread's stack and abort the process. Instead, each form checks how much
+// stack the evaluation has used so far and fails with an error once it's more
+// than the stack limit, 1 MiB unless the thread's been given a bigger one.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use super::sorted::SortKey;
+use super::{lazy, parse_cache, RispErr, RispExp};
+
+pub struct CountingAllocator;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+static FREED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    // bytes allocated minus bytes freed on this thread, negative when it frees
+    // what another thread allocated
+    static THREAD_LIVE: Cell<i64> = const { Cell::new(0) };
+    static THREAD_ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+    static MEMORY_LIMIT: Cell<Option<u64>> = const { Cell::new(None) };
+    // `THREAD_LIVE` when the current top-level evaluation started
+    static BASELINE: Cell<i64> = const { Cell::new(0) };
+    // forms being evaluated, the address of the stack when the outermost one
+    // started and how far below that evaluation can go
+    static NESTING: Cell<usize> = const { Cell::new(0) };
+    static STACK_BASE: Cell<usize> = const { Cell::new(0) };
+    static STACK_LIMIT: Cell<usize> = const { Cell::new(DEFAULT_STACK_LIMIT) };
+}
+
+// half the stack of a thread spawned with the defaults
+const DEFAULT_STACK_LIMIT: usize = 1 << 20;
+
+// thread locals can already be gone while a thread exits
+fn count_live(bytes: i64) {
+    let _ = THREAD_LIVE.try_with(|live| live.set(live.get() + bytes));
+}
+
+fn count_allocation() {
+    let _ = THREAD_ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        count_live(layout.size() as i64);
+        count_allocation();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        FREED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        count_live(-(layout.size() as i64));
+        System.dealloc(ptr, layout)
+    }
+}
+
+// whether `CountingAllocator` is the global allocator, which has counted
+// something by the time anything asks
+fn counting() -> bool {
+    ALLOCATIONS.load(Ordering::Relaxed) > 0
+}
+
+// the counts of one session, the REPL's or an `Interpreter`'s
+pub struct Stats {
+    started: Instant,
+    // top-level evaluations, from the REPL, a watched file or a remote client
+    evaluations: u64,
+    errors: u64,
+    // every form `eval` was called on, nested ones included
+    forms: u64,
+    // how deeply nested the form being evaluated is, and the most it's been
+    depth: usize,
+    peak_depth: usize,
+    // made on this thread during top-level evaluations
+    allocations: u64,
+    eval_time: Duration,
+    builtin_time: Duration,
+}
+
+impl Stats {
+    pub fn new() -> Stats {
+        Stats {
+            started: Instant::now(),
+            evaluations: 0,
+            errors: 0,
+            forms: 0,
+            depth: 0,
+            peak_depth: 0,
+            allocations: 0,
+            eval_time: Duration::from_secs(0),
+            builtin_time: Duration::from_secs(0),
+        }
+    }
+
+    pub fn metrics(&self) -> Metrics {
+        Metrics {
+            evaluations: self.evaluations,
+            errors: self.errors,
+            forms: self.forms,
+            peak_depth: self.peak_depth,
+            allocations: self.allocations,
+            eval_time: self.eval_time,
+            builtin_time: self.builtin_time,
+            uptime: self.started.elapsed(),
+        }
+    }
+}
+
+// a snapshot of the counts of a session, for embedders to export
+#[derive(Clone, Debug)]
+pub struct Metrics {
+    pub evaluations: u64,
+    pub errors: u64,
+    pub forms: u64,
+    pub peak_depth: usize,
+    pub allocations: u64,
+    pub eval_time: Duration,
+    pub builtin_time: Duration,
+    pub uptime: Duration,
+}
+
+thread_local! {
+    static STATS: RefCell<Stats> = RefCell::new(Stats::new());
+}
+
+// the counts of the session being counted
+pub fn current() -> Metrics {
+    STATS.with(|stats| stats.borrow().metrics())
+}
+
+// makes `stats` the session being counted, returning the one it replaces
+pub fn replace(stats: Stats) -> Stats {
+    STATS.with(|current| current.replace(stats))
+}
+
+// leaves the nesting level of the form it was made for when dropped
+pub struct Depth;
+
+impl Drop for Depth {
+    fn drop(&mut self) {
+        let _ = NESTING.try_with(|nesting| nesting.set(nesting.get() - 1));
+        let _ = STATS.try_with(|stats| stats.borrow_mut().depth -= 1);
+    }
+}
+
+// starts the session clock, which otherwise starts at the first evaluation
+pub fn start() {
+    STATS.with(|_| ());
+}
+
+// sets the most memory one top-level evaluation can hold, returning the old limit
+pub fn set_memory_limit(bytes: Option<u64>) -> Option<u64> {
+    MEMORY_LIMIT.with(|limit| limit.replace(bytes))
+}
+
+// sets how many bytes of this thread's stack an evaluation can use, returning
+// the old limit. It has to leave room for the builtins the deepest form calls
+pub fn set_stack_limit(bytes: usize) -> usize {
+    STACK_LIMIT.with(|limit| limit.replace(bytes))
+}
+
+// the address of the caller's frame, near enough
+fn stack_position() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+// fails if allocating `bytes` more would take the current top-level
+// evaluation past the memory limit, for a builtin about to make something big
+pub fn reserve(bytes: usize) -> Result<(), RispErr> {
+    if let Some(limit) = MEMORY_LIMIT.with(Cell::get) {
+        let held = THREAD_LIVE.with(Cell::get) - BASELINE.with(Cell::get);
+        if held.saturating_add(bytes as i64) > limit as i64 {
+            return Err(RispErr::Reason(format!("memory limit of {} bytes exceeded", limit)))
+        }
+    }
+
+    Ok(())
+}
+
+// room for `n` values, for a builtin about to make a list or vector of them
+pub fn reserve_values(n: usize) -> Result<(), RispErr> {
+    reserve(n.saturating_mul(std::mem::size_of::<RispExp>()))
+}
+
+// counts a form about to be evaluated, which is nested until the result is dropped
+pub fn count_form() -> Result<Depth, RispErr> {
+    reserve(0)?;
+
+    let here = stack_position();
+    if NESTING.with(Cell::get) == 0 {
+        STACK_BASE.with(|base| base.set(here));
+    }
+    let limit = STACK_LIMIT.with(Cell::get);
+    if STACK_BASE.with(Cell::get).abs_diff(here) > limit {
+        return Err(RispErr::Reason(format!("recursion too deep, evaluation used over {} bytes of stack", limit)))
+    }
+    NESTING.with(|nesting| nesting.set(nesting.get() + 1));
+
+    STATS.with(|stats| {
+        let mut stats = stats.borrow_mut();
+        stats.forms += 1;
+        stats.depth += 1;
+        stats.peak_depth = stats.peak_depth.max(stats.depth);
+    });
+
+    Ok(Depth)
+}
+
+// runs a builtin function, counting the time spent in it
+pub fn builtin<T>(f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let res = f();
+    STATS.with(|stats| stats.borrow_mut().builtin_time += start.elapsed());
+
+    res
+}
+
+// runs a top-level evaluation, counting it, its time and whether it failed
+pub fn timed<T>(f: impl FnOnce() -> Result<T, RispErr>) -> Result<T, RispErr> {
+    let start = Instant::now();
+    let allocations = THREAD_ALLOCATIONS.with(Cell::get);
+    BASELINE.with(|baseline| baseline.set(THREAD_LIVE.with(Cell::get)));
+    let res = f();
+    STATS.with(|stats| {
+        let mut stats = stats.borrow_mut();
+        stats.evaluations += 1;
+        stats.errors += res.is_err() as u64;
+        stats.allocations += THREAD_ALLOCATIONS.with(Cell::get) - allocations;
+        stats.eval_time += start.elapsed();
+    });
+
+    res
+}
+
+fn millis(d: Duration) -> RispExp {
+    RispExp::Number(d.as_secs_f64() * 1000.0)
+}
+
+// `(session-stats)` as a sorted map of `:name` -> number
+pub fn session_stats(_args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let allocated = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let freed = FREED_BYTES.load(Ordering::Relaxed);
+    let mut entries = STATS.with(|stats| {
+        let stats = stats.borrow();
+        vec![
+            (":evaluations", RispExp::Int(stats.evaluations as i64)),
+            (":errors", RispExp::Int(stats.errors as i64)),
+            (":forms", RispExp::Int(stats.forms as i64)),
+            (":peak-depth", RispExp::Int(stats.peak_depth as i64)),
+            (":eval-ms", millis(stats.eval_time)),
+            (":builtin-ms", millis(stats.builtin_time)),
+            (":uptime-ms", millis(stats.started.elapsed())),
+            (":lazy-modules", RispExp::Int(lazy::installed_modules() as i64)),
+            (":parse-cache-hits", RispExp::Int(parse_cache::counts().0 as i64)),
+            (":parse-cache-misses", RispExp::Int(parse_cache::counts().1 as i64)),
+        ]
+    });
+    if counting() {
+        entries.extend(vec![
+            (":allocations", RispExp::Int(ALLOCATIONS.load(Ordering::Relaxed) as i64)),
+            (":allocated-bytes", RispExp::Int(allocated as i64)),
+            (":live-bytes", RispExp::Int(allocated.saturating_sub(freed) as i64)),
+        ]);
+    }
+
+    let mut map = BTreeMap::new();
+    for (name, value) in entries {
+        map.insert(SortKey::new(RispExp::Keyword(name.to_string()))?, value);
+    }
+
+    Ok(RispExp::SortedMap(Rc::new(map)))
+}
@@ -0,0 +1,118 @@
+// Text builtins. Lengths and positions count characters, not bytes, so
+// `(substring s 0 1)` is the first character whatever it's encoded as.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use super::{args, as_index, stats, RispErr, RispExp};
+
+// `(str-len s)`
+fn str_len(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    Ok(RispExp::Int(args::string("str-len", args, 0)?.chars().count() as i64))
+}
+
+// `(str-concat s...)`
+fn str_concat(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let parts = (0..args.len()).map(|idx| args::string("str-concat", args, idx)).collect::<Result<Vec<_>, RispErr>>()?;
+    stats::reserve(parts.iter().map(|s| s.len()).sum())?;
+    let mut out = String::new();
+    for s in parts {
+        out.push_str(s);
+    }
+
+    Ok(RispExp::Str(out))
+}
+
+// `(substring s start [end])`, from `start` up to but not including `end`
+fn substring(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let s = args::string("substring", args, 0)?;
+    let len = s.chars().count();
+    let start = args::index("substring", args, 1)?;
+    let end = match args.get(2) {
+        Some(_) => args::index("substring", args, 2)?,
+        None => len,
+    };
+    if start > end || end > len {
+        return Err(RispErr::Reason(format!("`substring` can not take {}..{} of a string of length {}", start, end, len)))
+    }
+
+    Ok(RispExp::Str(s.chars().skip(start).take(end - start).collect()))
+}
+
+// `(str-split s [sep])`, at whitespace without a separator
+fn str_split(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let s = args::string("str-split", args, 0)?;
+    let parts: Vec<RispExp> = match args.get(1) {
+        None => s.split_whitespace().map(|part| RispExp::Str(part.to_string())).collect(),
+        Some(_) => match args::string("str-split", args, 1)? {
+            "" => return Err(args::wrong("str-split", 1, "a separator that isn't empty", &args[1])),
+            sep => s.split(sep).map(|part| RispExp::Str(part.to_string())).collect(),
+        },
+    };
+
+    Ok(RispExp::List(parts.into()))
+}
+
+fn str_upper(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    Ok(RispExp::Str(args::string("str-upper", args, 0)?.to_uppercase()))
+}
+
+fn str_lower(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    Ok(RispExp::Str(args::string("str-lower", args, 0)?.to_lowercase()))
+}
+
+// `(str-contains? s part)`
+fn str_contains(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    Ok(RispExp::Bool(args::string("str-contains?", args, 0)?.contains(args::string("str-contains?", args, 1)?)))
+}
+
+fn str_trim(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    Ok(RispExp::Str(args::string("str-trim", args, 0)?.trim().to_string()))
+}
+
+// `(string->chars s)`
+fn string_to_chars(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let s = args::string("string->chars", args, 0)?;
+    stats::reserve_values(s.chars().count())?;
+    Ok(RispExp::List(s.chars().map(RispExp::Char).collect()))
+}
+
+// `(char->int c)`, its unicode code point
+fn char_to_int(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    match args::get("char->int", args, 0, "a character")? {
+        RispExp::Char(c) => Ok(RispExp::Int(*c as i64)),
+        exp => Err(args::wrong("char->int", 0, "a character", exp)),
+    }
+}
+
+fn int_to_char(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let c = match args::get("int->char", args, 0, "a code point")? {
+        n @ (RispExp::Number(_) | RispExp::Int(_)) =>
+            as_index(n).and_then(|n| u32::try_from(n).ok()).and_then(char::from_u32),
+        exp => return Err(args::wrong("int->char", 0, "a code point", exp)),
+    };
+
+    c.map(RispExp::Char).ok_or(RispErr::Reason(format!("'{}' isn't a character's code point", args[0])))
+}
+
+// `(str->list s)`, a string for each character
+fn str_to_list(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let s = args::string("str->list", args, 0)?;
+    stats::reserve_values(s.chars().count())?;
+    Ok(RispExp::List(s.chars().map(|c| RispExp::Str(c.to_string())).collect()))
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert("str-len".to_string(), RispExp::Func(str_len));
+    data.insert("str-concat".to_string(), RispExp::Func(str_concat));
+    data.insert("substring".to_string(), RispExp::Func(substring));
+    data.insert("str-split".to_string(), RispExp::Func(str_split));
+    data.insert("str-upper".to_string(), RispExp::Func(str_upper));
+    data.insert("str-lower".to_string(), RispExp::Func(str_lower));
+    data.insert("str-contains?".to_string(), RispExp::Func(str_contains));
+    data.insert("str-trim".to_string(), RispExp::Func(str_trim));
+    data.insert("str->list".to_string(), RispExp::Func(str_to_list));
+    data.insert("string->chars".to_string(), RispExp::Func(string_to_chars));
+    data.insert("char->int".to_string(), RispExp::Func(char_to_int));
+    data.insert("int->char".to_string(), RispExp::Func(int_to_char));
+}
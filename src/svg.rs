@@ -0,0 +1,80 @@
+// SVG from risp data. `(circle cx cy r)`, `(line x1 y1 x2 y2)` and
+// `(rect x y width height)` build shapes, each taking an optional map of
+// extra attributes like `{:fill "red"}`, and
+// `(svg width height shape...)` renders them as a document. Shapes are the
+// hiccup elements `html` renders, so any other SVG element can be written as
+// one, and the document is a string to `spit` into a file.
+
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+use super::html;
+use super::sorted::SortKey;
+use super::{RispErr, RispExp};
+
+fn element(tag: &str, names: &[&str], defaults: &[(&str, &str)], args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let (values, extra) = match args.get(names.len()) {
+        Some(extra @ (RispExp::SortedMap(_) | RispExp::Map(_))) if args.len() == names.len() + 1 =>
+            (&args[..names.len()], Some(extra)),
+        _ if args.len() == names.len() => (args, None),
+        _ => return Err(RispErr::Reason(format!("expected {} and optionally a map of attributes", names.join(", ")))),
+    };
+
+    let mut attrs = BTreeMap::new();
+    for (name, value) in defaults {
+        attrs.insert(SortKey::new(RispExp::Keyword(format!(":{}", name)))?, RispExp::Str(value.to_string()));
+    }
+    for (name, value) in names.iter().zip(values) {
+        match value {
+            RispExp::Number(_) | RispExp::Int(_) => attrs.insert(SortKey::new(RispExp::Keyword(format!(":{}", name)))?, value.clone()),
+            _ => return Err(RispErr::Reason(format!("expected {} to be a number, got '{}'", name, value))),
+        };
+    }
+    match extra {
+        Some(RispExp::SortedMap(extra)) => attrs.extend(extra.iter().map(|(name, value)| (name.clone(), value.clone()))),
+        Some(RispExp::Map(extra)) => for (name, value) in extra.iter() {
+            attrs.insert(SortKey::new(name.exp())?, value.clone());
+        },
+        _ => (),
+    }
+
+    Ok(RispExp::List(vec![RispExp::Keyword(format!(":{}", tag)), RispExp::SortedMap(Rc::new(attrs))].into()))
+}
+
+fn circle(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    element("circle", &["cx", "cy", "r"], &[], args)
+}
+
+// lines have no stroke unless they're given one, which would make them invisible
+fn line(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    element("line", &["x1", "y1", "x2", "y2"], &[("stroke", "black")], args)
+}
+
+fn rect(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    element("rect", &["x", "y", "width", "height"], &[], args)
+}
+
+fn svg(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let (width, height, shapes) = match args {
+        [width @ (RispExp::Number(_) | RispExp::Int(_)), height @ (RispExp::Number(_) | RispExp::Int(_)), shapes @ ..] =>
+            (width, height, shapes),
+        _ => return Err(RispErr::Reason("expected a width, a height and the shapes to draw".to_string())),
+    };
+
+    let mut attrs = BTreeMap::new();
+    attrs.insert(SortKey::new(RispExp::Keyword(":xmlns".to_string()))?, RispExp::Str("http://www.w3.org/2000/svg".to_string()));
+    attrs.insert(SortKey::new(RispExp::Keyword(":width".to_string()))?, width.clone());
+    attrs.insert(SortKey::new(RispExp::Keyword(":height".to_string()))?, height.clone());
+
+    let mut document = vec![RispExp::Keyword(":svg".to_string()), RispExp::SortedMap(Rc::new(attrs))];
+    document.extend_from_slice(shapes);
+
+    html::html(&[RispExp::List(document.into())])
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert("circle".to_string(), RispExp::Func(circle));
+    data.insert("line".to_string(), RispExp::Func(line));
+    data.insert("rect".to_string(), RispExp::Func(rect));
+    data.insert("svg".to_string(), RispExp::Func(svg));
+}
@@ -0,0 +1,170 @@
+// Tagged literals, `#tag form`, for any tag that isn't a reader macro. The
+// form is read as data, not code, and when the literal is evaluated it's
+// passed to the reader function registered for its tag, either in risp with
+// `(deftag point (fn (data) ...))` or natively with `register`, which is
+// `risp::register_tag_reader` for embedders. Readers belong to the thread, like
+// reader macros. Without a reader the literal stays a tagged value, which
+// prints as it was written so data with tags nobody here knows about still
+// round-trips.
+//
+// What a reader gives back keeps the literal it was read from, so it prints
+// and is written as EDN as that literal again: with a `point` reader making
+// maps, `#point (1 2)` is a map that prints as `#point (1 2)`. It's kept by
+// the result's identity, like metadata, so only maps, sets, tuples and
+// lambdas can keep it, and a changed copy like an `assoc` of it is a plain
+// value again. Anything else a reader gives prints as itself.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{call_exp, eval, meta, RispEnv, RispErr, RispExp};
+
+#[derive(Debug)]
+pub struct Tagged {
+    tag: String,
+    value: RispExp,
+}
+
+impl Tagged {
+    pub fn new(tag: String, value: RispExp) -> Tagged {
+        Tagged { tag, value }
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    pub fn value(&self) -> &RispExp {
+        &self.value
+    }
+
+    pub fn display(&self) -> String {
+        format!("#{} {}", self.tag, self.value)
+    }
+}
+
+struct Read {
+    // whether the result is still around, holding on to its allocation so its
+    // address isn't given to a new value while it's in here
+    alive: Box<dyn Fn() -> bool>,
+    literal: Rc<Tagged>,
+}
+
+thread_local! {
+    static READERS: RefCell<HashMap<String, RispExp>> = RefCell::new(HashMap::new());
+    // the literals reader results were read from, by the result's address
+    static READ: RefCell<HashMap<usize, Read>> = RefCell::new(HashMap::new());
+}
+
+fn remember(result: &RispExp, literal: &Rc<Tagged>) {
+    // vectors can be changed in place, after which the literal is wrong
+    if matches!(result, RispExp::Vector(_)) {
+        return
+    }
+    if let Some((key, alive)) = meta::identity(result) {
+        READ.with(|read| {
+            let mut read = read.borrow_mut();
+            if read.len() == read.capacity() {
+                read.retain(|_, read| (read.alive)());
+            }
+            read.insert(key, Read { alive, literal: literal.clone() });
+        });
+    }
+}
+
+// the literal `exp` was read from, if it's what a tag reader gave back
+pub fn literal(exp: &RispExp) -> Option<Rc<Tagged>> {
+    if READ.with(|read| read.borrow().is_empty()) {
+        return None
+    }
+
+    let (key, _) = meta::identity(exp)?;
+    READ.with(|read| match read.borrow().get(&key) {
+        Some(read) if (read.alive)() => Some(read.literal.clone()),
+        _ => None,
+    })
+}
+
+pub fn register(tag: &str, reader: RispExp) {
+    READERS.with(|readers| readers.borrow_mut().insert(tag.to_string(), reader));
+}
+
+// the readers registered so far, for `restore` to put back
+pub fn saved() -> HashMap<String, RispExp> {
+    READERS.with(|readers| readers.borrow().clone())
+}
+
+pub fn restore(saved: &HashMap<String, RispExp>) {
+    READERS.with(|readers| readers.borrow_mut().clone_from(saved));
+}
+
+// evaluating the literal runs its tag's reader on the data
+pub fn eval_tagged(tagged: &Rc<Tagged>, env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let reader = READERS.with(|readers| readers.borrow().get(&tagged.tag).cloned());
+    match reader {
+        Some(reader) => {
+            let result = call_exp(&reader, vec![tagged.value.clone()], env)?;
+            remember(&result, tagged);
+            Ok(result)
+        },
+        None => Ok(RispExp::Tagged(tagged.clone())),
+    }
+}
+
+// `(deftag name reader)`
+pub fn eval_deftag_args(arg_forms: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let tag = match arg_forms {
+        [RispExp::Symbol(tag), _] => Ok(tag.clone()),
+        _ => Err(RispErr::Reason("expected (deftag name reader)".to_string())),
+    }?;
+
+    let reader = eval(&arg_forms[1], env)?;
+    match reader {
+        RispExp::Func(_) | RispExp::EnvFunc(_) | RispExp::Lambda(_) | RispExp::Instrumented(_) => register(&tag, reader),
+        _ => return Err(RispErr::Reason("expected the reader to be a function".to_string())),
+    }
+
+    Ok(arg_forms[0].clone())
+}
+
+fn tagged_arg(args: &[RispExp]) -> Result<&Rc<Tagged>, RispErr> {
+    match args.first() {
+        Some(RispExp::Tagged(tagged)) => Ok(tagged),
+        _ => Err(RispErr::Reason("expected a tagged value".to_string())),
+    }
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    // `(tagged :point data)`, for readers that want their result to print tagged
+    data.insert(
+        "tagged".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                match args {
+                    [RispExp::Keyword(tag), value] =>
+                        Ok(RispExp::Tagged(Rc::new(Tagged::new(tag[1..].to_string(), value.clone())))),
+                    _ => Err(RispErr::Reason("expected a `:tag` and a value".to_string())),
+                }
+            }
+        )
+    );
+
+    data.insert(
+        "tag".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                Ok(RispExp::Keyword(format!(":{}", tagged_arg(args)?.tag)))
+            }
+        )
+    );
+
+    data.insert(
+        "untag".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                Ok(tagged_arg(args)?.value.clone())
+            }
+        )
+    );
+}
@@ -0,0 +1,149 @@
+// Templates for generating code and documents. A template is a form, usually
+// a quasiquoted one so that `,x` and `,@xs` fill it in from the calling scope
+// like any other quasiquote, and `(template :html form)` renders it for its
+// target: `:html` renders it as hiccup with text escaped, `:sql` writes
+// symbols as SQL words and anything else as SQL literals, `:risp` writes it
+// as risp source and `:text` joins it up as it prints. The same escaping is
+// available on its own as `escape-html`, `escape-sql` and `escape-risp`.
+
+use std::fs;
+
+use super::{args, edn, html, sandbox, RispErr, RispExp};
+
+pub fn escape_html(text: &str) -> String {
+    let mut out = String::new();
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+fn text(exp: &RispExp) -> String {
+    match exp {
+        RispExp::Str(s) => s.clone(),
+        _ => exp.to_string(),
+    }
+}
+
+fn sql(exp: &RispExp) -> Result<String, RispErr> {
+    match exp {
+        RispExp::Str(s) => Ok(format!("'{}'", s.replace('\'', "''"))),
+        RispExp::Number(n) if n.is_finite() => Ok(n.to_string()),
+        RispExp::Int(n) => Ok(n.to_string()),
+        RispExp::Bool(true) => Ok("TRUE".to_string()),
+        RispExp::Bool(false) => Ok("FALSE".to_string()),
+        // lists are written for `IN (...)`
+        RispExp::List(xs) => {
+            let xs: Vec<String> = xs.iter().map(sql).collect::<Result<_, _>>()?;
+            Ok(format!("({})", xs.join(", ")))
+        },
+        _ => Err(RispErr::Reason(format!("'{}' can not be written as SQL", exp))),
+    }
+}
+
+fn escape(target: &str, exp: &RispExp) -> Result<String, RispErr> {
+    match target {
+        ":text" => Ok(text(exp)),
+        ":html" => Ok(escape_html(&text(exp))),
+        ":sql" => sql(exp),
+        ":risp" => edn::write_exp(exp),
+        _ => Err(RispErr::Reason(format!("unknown template target '{}', expected one of :text, :html, :sql, :risp", target))),
+    }
+}
+
+// a clause of a SQL template: symbols are words, vectors are comma separated
+// and a nested list with words in it is a parenthesised clause, any other
+// value is a literal so what's unquoted into it can't be read as SQL
+fn sql_clause(exp: &RispExp) -> Result<String, RispErr> {
+    let words = |xs: &[RispExp], sep: &str| -> Result<String, RispErr> {
+        let xs: Vec<String> = xs.iter().map(sql_clause).collect::<Result<_, _>>()?;
+        Ok(xs.join(sep))
+    };
+
+    match exp {
+        RispExp::Symbol(word) => Ok(word.clone()),
+        RispExp::Vector(xs) => words(&xs.borrow(), ", "),
+        RispExp::List(xs) if xs.iter().any(|x| matches!(x, RispExp::Symbol(_) | RispExp::List(_) | RispExp::Vector(_))) => {
+            Ok(format!("({})", words(xs, " ")?))
+        },
+        _ => sql(exp),
+    }
+}
+
+fn text_form(out: &mut String, exp: &RispExp) {
+    match exp {
+        RispExp::List(xs) => xs.iter().for_each(|x| text_form(out, x)),
+        _ => out.push_str(&text(exp)),
+    }
+}
+
+pub fn render(target: &str, form: &RispExp) -> Result<String, RispErr> {
+    match target {
+        ":html" => match html::html(std::slice::from_ref(form))? {
+            RispExp::Str(out) => Ok(out),
+            _ => unreachable!(),
+        },
+        ":sql" => match form {
+            RispExp::List(xs) => {
+                let xs: Vec<String> = xs.iter().map(sql_clause).collect::<Result<_, _>>()?;
+                Ok(xs.join(" "))
+            },
+            _ => sql_clause(form),
+        },
+        ":risp" => edn::write_exp(form),
+        ":text" => {
+            let mut out = String::new();
+            text_form(&mut out, form);
+            Ok(out)
+        },
+        _ => escape(target, form),
+    }
+}
+
+// `(template target form)`
+pub fn template(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    match args {
+        [RispExp::Keyword(target), form] => Ok(RispExp::Str(render(target, form)?)),
+        _ => Err(RispErr::Reason("expected a target like :html and the template form".to_string())),
+    }
+}
+
+pub fn escape_sql(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let exp = args::get("escape-sql", args, 0, "a value")?;
+    Ok(RispExp::Str(escape(":sql", exp)?))
+}
+
+pub fn escape_html_args(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let exp = args::get("escape-html", args, 0, "a value")?;
+    Ok(RispExp::Str(escape(":html", exp)?))
+}
+
+pub fn escape_risp(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let exp = args::get("escape-risp", args, 0, "a value")?;
+    Ok(RispExp::Str(escape(":risp", exp)?))
+}
+
+// `(emit-file path text)` writes generated text, creating missing directories
+pub fn emit_file(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let (path, content) = match args {
+        [RispExp::Str(path), RispExp::Str(content)] => (path, content),
+        _ => return Err(RispErr::Reason("expected a path and the text to write".to_string())),
+    };
+
+    let resolved = sandbox::resolve(path)?;
+    if let Some(dir) = resolved.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)
+            .map_err(|e| RispErr::Reason(format!("could not create '{}': {}", dir.display(), e)))?;
+    }
+    fs::write(resolved, content).map_err(|e| RispErr::Reason(format!("could not write '{}': {}", path, e)))?;
+
+    Ok(RispExp::Str(path.clone()))
+}
@@ -0,0 +1,164 @@
+// Terminal primitives for games and interactive programs: `(clear-screen)`,
+// `(move-cursor row col)` counting from the top left at 0 0, `(term-size)` as
+// `(rows cols)`, `(raw-mode true)` to get keys as they're pressed without
+// echoing them, and `(read-key)`.
+//
+// `read-key` returns the key as a one character string, or one of :up :down
+// :left :right :enter :tab :backspace :escape, and `false` at the end of
+// input. Raw mode is switched with `stty`, so it's only available where that
+// exists, and it's switched back off when risp exits.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, Read, Write};
+use std::process::Command;
+
+use super::{as_index, output, replay, RispErr, RispExp};
+
+thread_local! {
+    static RAW: Cell<bool> = const { Cell::new(false) };
+}
+
+fn flushed(text: &str) -> Result<RispExp, RispErr> {
+    output::write(text)?;
+    Ok(RispExp::Bool(true))
+}
+
+fn clear_screen(_args: &[RispExp]) -> Result<RispExp, RispErr> {
+    flushed("\x1b[2J\x1b[H")
+}
+
+fn move_cursor(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    match args.iter().map(as_index).collect::<Option<Vec<usize>>>().as_deref() {
+        Some([row, col]) => flushed(&format!("\x1b[{};{}H", row + 1, col + 1)),
+        _ => Err(RispErr::Reason("expected a row and a column, counting from 0".to_string())),
+    }
+}
+
+fn stty(args: &[&str]) -> Result<String, RispErr> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(std::process::Stdio::inherit())
+        .output()
+        .map_err(|e| RispErr::Reason(format!("could not run stty: {}", e)))?;
+
+    match output.status.success() {
+        true => Ok(String::from_utf8_lossy(&output.stdout).trim().to_string()),
+        false => Err(RispErr::Reason("stdin is not a terminal".to_string())),
+    }
+}
+
+// from `stty size` where it works, otherwise from $LINES and $COLUMNS
+fn term_size(_args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let from_stty = stty(&["size"]).ok().and_then(|size| {
+        let size: Vec<i64> = size.split_whitespace().filter_map(|n| n.parse().ok()).collect();
+        match size[..] {
+            [rows, cols] => Some((rows, cols)),
+            _ => None,
+        }
+    });
+    let from_env = || {
+        let var = |name| env::var(name).ok().and_then(|n| n.parse().ok());
+        Some((var("LINES")?, var("COLUMNS")?))
+    };
+
+    let (rows, cols) = from_stty.or_else(from_env).unwrap_or((24, 80));
+    Ok(RispExp::List(vec![RispExp::Int(rows), RispExp::Int(cols)].into()))
+}
+
+fn raw_mode(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let on = match args {
+        [RispExp::Bool(on)] => *on,
+        _ => return Err(RispErr::Reason("expected true or false".to_string())),
+    };
+
+    stty(if on { &["raw", "-echo"] } else { &["-raw", "echo"] })?;
+    RAW.with(|raw| raw.set(on));
+
+    Ok(RispExp::Bool(on))
+}
+
+// leaves the terminal usable if a program exits while still in raw mode
+pub fn restore() {
+    if RAW.with(|raw| raw.replace(false)) {
+        let _ = stty(&["-raw", "echo"]);
+    }
+}
+
+fn read_byte() -> Option<u8> {
+    let mut byte = [0];
+    match io::stdin().read(&mut byte) {
+        Ok(1) => Some(byte[0]),
+        _ => None,
+    }
+}
+
+fn keyword(k: &str) -> RispExp {
+    RispExp::Keyword(k.to_string())
+}
+
+// the rest of an escape sequence. In raw mode the reads after the escape give
+// up after a tenth of a second, so a lone escape key doesn't wait for the next
+fn escape_sequence() -> RispExp {
+    let raw = RAW.with(|raw| raw.get());
+    if raw {
+        let _ = stty(&["min", "0", "time", "1"]);
+    }
+    let sequence = match read_byte() {
+        Some(b'[') | Some(b'O') => read_byte(),
+        _ => None,
+    };
+    if raw {
+        let _ = stty(&["min", "1", "time", "0"]);
+    }
+
+    match sequence {
+        Some(b'A') => keyword(":up"),
+        Some(b'B') => keyword(":down"),
+        Some(b'C') => keyword(":right"),
+        Some(b'D') => keyword(":left"),
+        _ => keyword(":escape"),
+    }
+}
+
+fn read_key(_args: &[RispExp]) -> Result<RispExp, RispErr> {
+    io::stdout().flush().map_err(|e| RispErr::Reason(format!("could not write to the terminal: {}", e)))?;
+    replay::recorded("read-key", read_live_key)
+}
+
+fn read_live_key() -> Result<RispExp, RispErr> {
+    let first = match read_byte() {
+        Some(byte) => byte,
+        None => return Ok(RispExp::Bool(false)),
+    };
+
+    let key = match first {
+        0x1b => escape_sequence(),
+        b'\r' | b'\n' => keyword(":enter"),
+        b'\t' => keyword(":tab"),
+        0x7f | 0x08 => keyword(":backspace"),
+        // the continuation bytes of a multi-byte character
+        _ => {
+            let mut bytes = vec![first];
+            let len = first.leading_ones().max(1) as usize;
+            while bytes.len() < len {
+                match read_byte() {
+                    Some(byte) => bytes.push(byte),
+                    None => break,
+                }
+            }
+            RispExp::Str(String::from_utf8_lossy(&bytes).to_string())
+        },
+    };
+
+    Ok(key)
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert("clear-screen".to_string(), RispExp::Func(clear_screen));
+    data.insert("move-cursor".to_string(), RispExp::Func(move_cursor));
+    data.insert("term-size".to_string(), RispExp::Func(term_size));
+    data.insert("raw-mode".to_string(), RispExp::Func(raw_mode));
+    data.insert("read-key".to_string(), RispExp::Func(read_key));
+}
@@ -0,0 +1,134 @@
+// Logo-style turtle graphics. The turtle starts at the origin facing up with
+// its pen down; `(forward n)` and `(back n)` move it, drawing a line while the
+// pen is down, `(turn deg)` turns it clockwise (negative to turn the other
+// way), and `(pen-up)`/`(pen-down)` lift and lower the pen.
+//
+// `(turtle-save path)` writes what's been drawn so far as an SVG, scaled to
+// fit, and `(turtle-reset)` clears the drawing and puts the turtle back.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+
+use super::{as_float, sandbox, RispErr, RispExp};
+
+const MARGIN: f64 = 10.0;
+
+struct Turtle {
+    x: f64,
+    y: f64,
+    // in degrees clockwise from up
+    heading: f64,
+    pen_down: bool,
+    lines: Vec<(f64, f64, f64, f64)>,
+}
+
+impl Turtle {
+    fn new() -> Turtle {
+        Turtle { x: 0.0, y: 0.0, heading: 0.0, pen_down: true, lines: vec![] }
+    }
+
+    fn advance(&mut self, distance: f64) {
+        let (sin, cos) = self.heading.to_radians().sin_cos();
+        let (x, y) = (self.x + distance * sin, self.y + distance * cos);
+        if self.pen_down {
+            self.lines.push((self.x, self.y, x, y));
+        }
+        self.x = x;
+        self.y = y;
+    }
+
+    // y grows downwards in SVG, so the drawing is flipped on the way out
+    fn svg(&self) -> String {
+        let xs = self.lines.iter().flat_map(|(x1, _, x2, _)| [*x1, *x2]);
+        let ys = self.lines.iter().flat_map(|(_, y1, _, y2)| [0.0 - *y1, 0.0 - *y2]);
+        let (min_x, max_x) = xs.fold((0.0f64, 0.0f64), |(lo, hi), x| (lo.min(x), hi.max(x)));
+        let (min_y, max_y) = ys.fold((0.0f64, 0.0f64), |(lo, hi), y| (lo.min(y), hi.max(y)));
+
+        let mut out = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"{}\" height=\"{}\">\n",
+            min_x - MARGIN,
+            min_y - MARGIN,
+            max_x - min_x + 2.0 * MARGIN,
+            max_y - min_y + 2.0 * MARGIN,
+            (max_x - min_x + 2.0 * MARGIN).ceil(),
+            (max_y - min_y + 2.0 * MARGIN).ceil(),
+        );
+        out.push_str("<g stroke=\"black\" stroke-width=\"1\" stroke-linecap=\"round\" fill=\"none\">\n");
+        for (x1, y1, x2, y2) in &self.lines {
+            out.push_str(&format!(
+                "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\"/>\n",
+                x1, 0.0 - y1, x2, 0.0 - y2,
+            ));
+        }
+        out.push_str("</g>\n</svg>\n");
+
+        out
+    }
+}
+
+thread_local! {
+    static TURTLE: RefCell<Turtle> = RefCell::new(Turtle::new());
+}
+
+fn number(args: &[RispExp], what: &str) -> Result<f64, RispErr> {
+    match args {
+        [n] => as_float(n).filter(|n| n.is_finite()),
+        _ => None,
+    }
+    .ok_or(RispErr::Reason(format!("expected {}", what)))
+}
+
+fn with_turtle(f: impl FnOnce(&mut Turtle)) -> Result<RispExp, RispErr> {
+    TURTLE.with(|turtle| f(&mut turtle.borrow_mut()));
+    Ok(RispExp::Bool(true))
+}
+
+fn forward(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let distance = number(args, "a distance")?;
+    with_turtle(|turtle| turtle.advance(distance))
+}
+
+fn back(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let distance = number(args, "a distance")?;
+    with_turtle(|turtle| turtle.advance(-distance))
+}
+
+fn turn(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let degrees = number(args, "an angle in degrees")?;
+    with_turtle(|turtle| turtle.heading = (turtle.heading + degrees).rem_euclid(360.0))
+}
+
+fn pen_up(_args: &[RispExp]) -> Result<RispExp, RispErr> {
+    with_turtle(|turtle| turtle.pen_down = false)
+}
+
+fn pen_down(_args: &[RispExp]) -> Result<RispExp, RispErr> {
+    with_turtle(|turtle| turtle.pen_down = true)
+}
+
+fn turtle_reset(_args: &[RispExp]) -> Result<RispExp, RispErr> {
+    with_turtle(|turtle| *turtle = Turtle::new())
+}
+
+fn turtle_save(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let path = match args {
+        [RispExp::Str(path)] => path,
+        _ => return Err(RispErr::Reason("expected a path to write the SVG to".to_string())),
+    };
+
+    let svg = TURTLE.with(|turtle| turtle.borrow().svg());
+    fs::write(sandbox::resolve(path)?, svg).map_err(|e| RispErr::Reason(format!("could not write '{}': {}", path, e)))?;
+
+    Ok(RispExp::Str(path.clone()))
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert("forward".to_string(), RispExp::Func(forward));
+    data.insert("back".to_string(), RispExp::Func(back));
+    data.insert("turn".to_string(), RispExp::Func(turn));
+    data.insert("pen-up".to_string(), RispExp::Func(pen_up));
+    data.insert("pen-down".to_string(), RispExp::Func(pen_down));
+    data.insert("turtle-reset".to_string(), RispExp::Func(turtle_reset));
+    data.insert("turtle-save".to_string(), RispExp::Func(turtle_save));
+}
@@ -0,0 +1,453 @@
+// Optional type annotations and the `risp check --types` pass.
+//
+// Params can be annotated as `(name : type)` and a return type can follow the
+// params as `: type`, both in `fn` and in `(defn name params body...)`. The
+// interpreter strips annotations and keeps them on the lambda as metadata,
+// only the checker reads them. The checker infers types of locals from their
+// definitions and reports calls whose arguments can't have the expected types,
+// anything it can't tell is `any` and always passes.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use super::{is_rest_marker, parse_atom, tokenize_spanned, Pos, RispErr, RispExp};
+
+#[derive(Clone, Debug)]
+pub enum Type {
+    Any,
+    Number,
+    Str,
+    Bool,
+    Symbol,
+    List,
+    Fn(Rc<Signature>),
+}
+
+#[derive(Clone, Debug)]
+pub struct Signature {
+    params: Vec<Type>,
+    // type of arguments past `params`, `None` when the arity is fixed
+    rest: Option<Type>,
+    ret: Type,
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Type::Any => "any",
+            Type::Number => "number",
+            Type::Str => "string",
+            Type::Bool => "bool",
+            Type::Symbol => "symbol",
+            Type::List => "list",
+            Type::Fn(_) => "fn",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut params: Vec<String> = self.params.iter().map(|t| t.to_string()).collect();
+        if let Some(rest) = &self.rest {
+            params.push(format!("{}...", rest));
+        }
+
+        write!(f, "({}) -> {}", params.join(" "), self.ret)
+    }
+}
+
+fn parse_type(exp: &RispExp) -> Result<Type, RispErr> {
+    match exp {
+        RispExp::Symbol(s) => match s.as_ref() {
+            "any" => Ok(Type::Any),
+            "number" => Ok(Type::Number),
+            "string" => Ok(Type::Str),
+            "bool" => Ok(Type::Bool),
+            "symbol" => Ok(Type::Symbol),
+            "list" => Ok(Type::List),
+            "fn" => Ok(Type::Fn(Rc::new(Signature { params: vec![], rest: Some(Type::Any), ret: Type::Any }))),
+            _ => Err(RispErr::Reason(format!("unknown type '{}'", s))),
+        },
+        _ => Err(RispErr::Reason(format!("expected a type, got '{}'", exp))),
+    }
+}
+
+fn is_colon(exp: &RispExp) -> bool {
+    matches!(exp, RispExp::Symbol(s) if s == ":")
+}
+
+// a lambda's params and body with their annotations taken out
+pub struct Annotated {
+    pub params: RispExp,
+    // how many leading body forms were the `: type` return annotation
+    pub skip: usize,
+    pub signature: Signature,
+    pub annotated: bool,
+}
+
+pub fn strip_annotations(params: &RispExp, body: &[RispExp]) -> Result<Annotated, RispErr> {
+    let list = match params {
+        RispExp::List(list) => list,
+        // not a param list, left for the lambda to complain about
+        _ => return Ok(Annotated {
+            params: params.clone(),
+            skip: 0,
+            signature: Signature { params: vec![], rest: Some(Type::Any), ret: Type::Any },
+            annotated: false,
+        }),
+    };
+
+    let mut plain = vec![];
+    let mut types = vec![];
+    let mut annotated = false;
+    let mut variadic = false;
+    for param in list {
+        match param {
+            RispExp::Symbol(s) if s == "&key" || is_rest_marker(s) => {
+                variadic = true;
+                plain.push(param.clone());
+            },
+            RispExp::List(typed) if !variadic && typed.len() == 3 && is_colon(&typed[1]) => {
+                annotated = true;
+                types.push(parse_type(&typed[2])?);
+                plain.push(typed[0].clone());
+            },
+            _ if !variadic => {
+                types.push(Type::Any);
+                plain.push(param.clone());
+            },
+            _ => plain.push(param.clone()),
+        }
+    }
+
+    // `: type` only counts as a return annotation when a body follows it
+    let (skip, ret) = match body {
+        [colon, ret, _, ..] if is_colon(colon) => {
+            annotated = true;
+            (2, parse_type(ret)?)
+        },
+        _ => (0, Type::Any),
+    };
+
+    Ok(Annotated {
+        params: RispExp::List(plain.into()),
+        skip,
+        signature: Signature { params: types, rest: if variadic { Some(Type::Any) } else { None }, ret },
+        annotated,
+    })
+}
+
+fn builtin_type(name: &str) -> Option<Type> {
+    let (rest, ret) = match name {
+        "+" | "-" => (Type::Number, Type::Number),
+        "=" | ">" | ">=" | "<" | "<=" => (Type::Number, Type::Bool),
+        "list" => (Type::Any, Type::List),
+        "str-concat" => (Type::Str, Type::Str),
+        _ => return None,
+    };
+
+    Some(Type::Fn(Rc::new(Signature { params: vec![], rest: Some(rest), ret })))
+}
+
+// source forms with the position of every node, for diagnostics
+enum Node {
+    Atom(RispExp, Pos),
+    List(Vec<Node>, Pos),
+}
+
+impl Node {
+    fn pos(&self) -> Pos {
+        match self {
+            Node::Atom(_, pos) | Node::List(_, pos) => *pos,
+        }
+    }
+
+    fn to_exp(&self) -> RispExp {
+        match self {
+            Node::Atom(exp, _) => exp.clone(),
+            Node::List(nodes, _) => RispExp::List(nodes.iter().map(Node::to_exp).collect()),
+        }
+    }
+}
+
+type Token = (String, Pos);
+
+fn read_node(tokens: &[Token]) -> Result<(Node, &[Token]), RispErr> {
+    let ((token, pos), rest) = tokens.split_first()
+        .ok_or(RispErr::Reason("could not get token".to_string()))?;
+
+    match &token[..] {
+        // `[a b]` is `(vec a b)` and `{k v}` is `(hash-map k v)`
+        "(" | "[" | "{" => {
+            let (close, mut nodes) = match &token[..] {
+                "[" => ("]", vec![Node::Atom(RispExp::Symbol("vec".to_string()), *pos)]),
+                "{" => ("}", vec![Node::Atom(RispExp::Symbol("hash-map".to_string()), *pos)]),
+                _ => (")", vec![]),
+            };
+            let mut xs = rest;
+            loop {
+                match xs.split_first() {
+                    Some(((next, _), rest)) if next == close => return Ok((Node::List(nodes, *pos), rest)),
+                    Some(_) => {
+                        let (node, rest) = read_node(xs)?;
+                        nodes.push(node);
+                        xs = rest;
+                    },
+                    None => return Err(RispErr::Reason(format!("{}: could not find closing `{}`", pos, close))),
+                }
+            }
+        },
+        ")" | "}" | "]" => Err(RispErr::Reason(format!("{}: unexpected `{}`", pos, token))),
+        _ if token.starts_with("#\\") => match super::parse_char(token) {
+            Ok(exp) => Ok((Node::Atom(exp, *pos), rest)),
+            Err(e) => Err(RispErr::Reason(format!("{}: {}", pos, super::err_message(e)))),
+        },
+        // reader macros expand to forms without positions of their own
+        _ if token.starts_with('#') => {
+            let plain: Vec<String> = rest.iter().map(|(token, _)| token.clone()).collect();
+            let (exp, left) = super::reader::read_dispatch(&token[1..], &plain)
+                .map_err(|e| RispErr::Reason(format!("{}: {}", pos, super::err_message(e))))?;
+
+            Ok((Node::Atom(exp, *pos), &rest[rest.len() - left.len()..]))
+        },
+        _ if token.starts_with('"') => match super::parse_string(token) {
+            Ok(exp) => Ok((Node::Atom(exp, *pos), rest)),
+            Err(e) => Err(RispErr::Reason(format!("{}: {}", pos, super::err_message(e)))),
+        },
+        "'" | "`" | "," | ",@" => {
+            let (node, rest) = read_node(rest)?;
+            Ok((Node::List(vec![Node::Atom(RispExp::Symbol(super::quote_name(token).to_string()), *pos), node], *pos), rest))
+        },
+        "@" => {
+            let (node, rest) = read_node(rest)?;
+            Ok((Node::List(vec![Node::Atom(RispExp::Symbol("spread".to_string()), *pos), node], *pos), rest))
+        },
+        _ if token.starts_with('@') => {
+            let spread = vec![Node::Atom(RispExp::Symbol("spread".to_string()), *pos), Node::Atom(parse_atom(&token[1..]), *pos)];
+            Ok((Node::List(spread, *pos), rest))
+        },
+        _ => Ok((Node::Atom(parse_atom(token), *pos), rest)),
+    }
+}
+
+fn read_nodes(src: &str) -> Result<Vec<Node>, RispErr> {
+    let tokens = tokenize_spanned(src);
+    let mut nodes = vec![];
+    let mut rest = &tokens[..];
+    while !rest.is_empty() {
+        let (node, new_rest) = read_node(rest)?;
+        nodes.push(node);
+        rest = new_rest;
+    }
+
+    Ok(nodes)
+}
+
+pub struct Diagnostic {
+    pub pos: Pos,
+    pub message: String,
+}
+
+fn compatible(expected: &Type, actual: &Type) -> bool {
+    matches!(
+        (expected, actual),
+        (Type::Any, _) | (_, Type::Any)
+            | (Type::Number, Type::Number) | (Type::Str, Type::Str) | (Type::Bool, Type::Bool)
+            | (Type::Symbol, Type::Symbol) | (Type::List, Type::List) | (Type::Fn(_), Type::Fn(_))
+    )
+}
+
+fn join(a: Type, b: Type) -> Type {
+    if compatible(&a, &b) && !matches!(a, Type::Any) && !matches!(b, Type::Any) {
+        a
+    } else {
+        Type::Any
+    }
+}
+
+struct Checker {
+    scopes: Vec<HashMap<String, Type>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Checker {
+    fn report(&mut self, pos: Pos, message: String) {
+        self.diagnostics.push(Diagnostic { pos, message });
+    }
+
+    fn lookup(&self, name: &str) -> Type {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).cloned())
+            .or_else(|| builtin_type(name))
+            .unwrap_or(Type::Any)
+    }
+
+    fn bind(&mut self, name: String, t: Type) {
+        self.scopes.last_mut().expect("checker has no scope").insert(name, t);
+    }
+
+    fn signature(&mut self, params: &Node, body: &[Node]) -> Option<Annotated> {
+        let body: Vec<RispExp> = body.iter().map(Node::to_exp).collect();
+        match strip_annotations(&params.to_exp(), &body) {
+            Ok(annotated) => Some(annotated),
+            Err(e) => {
+                self.report(params.pos(), super::err_message(e));
+                None
+            },
+        }
+    }
+
+    // checks a lambda body against its signature, with the params in scope
+    fn check_lambda(&mut self, annotated: &Annotated, body: &[Node]) {
+        let mut scope = HashMap::new();
+        if let RispExp::List(params) = &annotated.params {
+            let names = params.iter().take_while(|p| !matches!(p, RispExp::Symbol(s) if s == "&key" || is_rest_marker(s)));
+            for (param, t) in names.zip(annotated.signature.params.iter()) {
+                if let RispExp::Symbol(name) = param {
+                    scope.insert(name.clone(), t.clone());
+                }
+            }
+        }
+
+        self.scopes.push(scope);
+        let body = &body[annotated.skip.min(body.len())..];
+        let mut ret = Type::Any;
+        for node in body {
+            ret = self.infer(node);
+        }
+        self.scopes.pop();
+
+        if let Some(last) = body.last() {
+            if !compatible(&annotated.signature.ret, &ret) {
+                self.report(last.pos(), format!("expected to return {}, got {}", annotated.signature.ret, ret));
+            }
+        }
+    }
+
+    fn infer(&mut self, node: &Node) -> Type {
+        let (nodes, pos) = match node {
+            Node::Atom(exp, _) => return match exp {
+                RispExp::Number(_) | RispExp::Int(_) => Type::Number,
+                #[cfg(feature = "bignum")]
+                RispExp::Exact(_) => Type::Number,
+                RispExp::Str(_) => Type::Str,
+                RispExp::Bool(_) => Type::Bool,
+                RispExp::Keyword(_) => Type::Symbol,
+                RispExp::Symbol(s) => self.lookup(s),
+                _ => Type::Any,
+            },
+            Node::List(nodes, pos) => (nodes, *pos),
+        };
+
+        let (head, args) = match nodes.split_first() {
+            Some(split) => split,
+            None => return Type::List,
+        };
+
+        let form = match head {
+            Node::Atom(RispExp::Symbol(s), _) => s.as_ref(),
+            _ => "",
+        };
+
+        match (form, args) {
+            ("if", [test, rest @ ..]) => {
+                let t = self.infer(test);
+                // lists are tests too, nil being false
+                if !compatible(&Type::Bool, &t) && !compatible(&Type::List, &t) {
+                    self.report(test.pos(), format!("expected a bool or list test, got {}", t));
+                }
+
+                let mut branches = rest.iter().map(|branch| self.infer(branch)).collect::<Vec<Type>>().into_iter();
+                match (branches.next(), branches.next()) {
+                    (Some(a), Some(b)) => join(a, b),
+                    _ => Type::Any,
+                }
+            },
+            ("def", [Node::Atom(RispExp::Symbol(name), _), value])
+            | ("define", [Node::Atom(RispExp::Symbol(name), _), value])
+            | ("defonce", [Node::Atom(RispExp::Symbol(name), _), value]) => {
+                let t = self.infer(value);
+                self.bind(name.clone(), t);
+                Type::Symbol
+            },
+            ("defn", [Node::Atom(RispExp::Symbol(name), _), params, body @ ..]) => {
+                if let Some(annotated) = self.signature(params, body) {
+                    self.bind(name.clone(), Type::Fn(Rc::new(annotated.signature.clone())));
+                    self.check_lambda(&annotated, body);
+                }
+                Type::Symbol
+            },
+            ("fn", [params, body @ ..]) => match self.signature(params, body) {
+                Some(annotated) => {
+                    self.check_lambda(&annotated, body);
+                    Type::Fn(Rc::new(annotated.signature))
+                },
+                None => Type::Any,
+            },
+            _ => self.infer_call(head, args, pos),
+        }
+    }
+
+    fn infer_call(&mut self, head: &Node, args: &[Node], pos: Pos) -> Type {
+        let signature = match self.infer(head) {
+            Type::Fn(signature) => signature,
+            Type::Any => {
+                for arg in args {
+                    self.infer(arg);
+                }
+                return Type::Any
+            },
+            t => {
+                self.report(head.pos(), format!("{} is not a function", t));
+                return Type::Any
+            },
+        };
+
+        let n = signature.params.len();
+        if args.len() < n || (signature.rest.is_none() && args.len() > n) {
+            self.report(pos, format!("expected {} arguments, got {}", n, args.len()));
+        }
+
+        for (idx, arg) in args.iter().enumerate() {
+            let t = self.infer(arg);
+            let expected = signature.params.get(idx).or(signature.rest.as_ref()).unwrap_or(&Type::Any);
+            if !compatible(expected, &t) {
+                self.report(arg.pos(), format!("expected {}, got {}", expected, t));
+            }
+        }
+
+        signature.ret.clone()
+    }
+}
+
+// type errors in the source text, in source order
+pub fn check(src: &str) -> Result<Vec<Diagnostic>, RispErr> {
+    let nodes = read_nodes(src)?;
+    let mut checker = Checker { scopes: vec![HashMap::new()], diagnostics: vec![] };
+
+    // `defn`s can be called before they're defined, as long as it's at runtime
+    for node in &nodes {
+        if let Node::List(form, _) = node {
+            if let [Node::Atom(RispExp::Symbol(defn), _), Node::Atom(RispExp::Symbol(name), _), params, body @ ..] = &form[..] {
+                if defn == "defn" {
+                    let body: Vec<RispExp> = body.iter().map(Node::to_exp).collect();
+                    if let Ok(annotated) = strip_annotations(&params.to_exp(), &body) {
+                        checker.bind(name.clone(), Type::Fn(Rc::new(annotated.signature)));
+                    }
+                }
+            }
+        }
+    }
+
+    for node in &nodes {
+        checker.infer(node);
+    }
+
+    checker.diagnostics.sort_by_key(|d| (d.pos.line, d.pos.col));
+    Ok(checker.diagnostics)
+}
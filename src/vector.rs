@@ -0,0 +1,70 @@
+// Vectors, `[1 2 3]`, for indexing in constant time and changing an element in
+// place. The literal reads as `(vec 1 2 3)`, so each evaluation of it makes a
+// new vector. `vec-set!` changes the vector itself, along with every binding
+// sharing it.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{args, stats, RispErr, RispExp};
+
+type Elements = Rc<RefCell<Vec<RispExp>>>;
+
+fn vec_arg<'a>(op: &str, args: &'a [RispExp]) -> Result<&'a Elements, RispErr> {
+    match args::get(op, args, 0, "a vector")? {
+        RispExp::Vector(xs) => Ok(xs),
+        exp => Err(args::wrong(op, 0, "a vector", exp)),
+    }
+}
+
+fn index_arg(op: &str, args: &[RispExp], len: usize) -> Result<usize, RispErr> {
+    match args::index(op, args, 1)? {
+        idx if idx < len => Ok(idx),
+        idx => Err(RispErr::Reason(format!("`{}` index {} is out of range for a vector of length {}", op, idx, len))),
+    }
+}
+
+// `(vec x...)`
+fn vec(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    Ok(RispExp::Vector(Rc::new(RefCell::new(args.to_vec()))))
+}
+
+// `(vec-ref v idx)`
+fn vec_ref(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let xs = vec_arg("vec-ref", args)?.borrow();
+    Ok(xs[index_arg("vec-ref", args, xs.len())?].clone())
+}
+
+// `(vec-set! v idx x)`, returning `x`
+fn vec_set(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let mut xs = vec_arg("vec-set!", args)?.borrow_mut();
+    let idx = index_arg("vec-set!", args, xs.len())?;
+    let value = args::get("vec-set!", args, 2, "a value to set")?;
+    xs[idx] = value.clone();
+
+    Ok(value.clone())
+}
+
+fn vec_len(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    Ok(RispExp::Int(vec_arg("vec-len", args)?.borrow().len() as i64))
+}
+
+fn vec_to_list(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let xs = vec_arg("vec->list", args)?.borrow();
+    stats::reserve_values(xs.len())?;
+    Ok(RispExp::List(xs.clone().into()))
+}
+
+fn is_vector(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    Ok(RispExp::Bool(matches!(args.first(), Some(RispExp::Vector(_)))))
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert("vec".to_string(), RispExp::Func(vec));
+    data.insert("vec-ref".to_string(), RispExp::Func(vec_ref));
+    data.insert("vec-set!".to_string(), RispExp::Func(vec_set));
+    data.insert("vec-len".to_string(), RispExp::Func(vec_len));
+    data.insert("vec->list".to_string(), RispExp::Func(vec_to_list));
+    data.insert("vector?".to_string(), RispExp::Func(is_vector));
+}
@@ -0,0 +1,89 @@
+// `prewalk` and `postwalk` apply a function to every node of a nested list,
+// vector or tuple structure. Both walk with an explicit work stack instead of recursing,
+// so deeply nested data can't overflow the Rust stack.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{call_exp, RispEnv, RispErr, RispExp};
+
+enum Kind {
+    List,
+    Vector,
+    Tuple,
+}
+
+enum Work {
+    Visit(RispExp),
+    // rebuild a node from the last `n` results
+    Build(Kind, usize),
+}
+
+fn children(node: &RispExp) -> Option<(Kind, Vec<RispExp>)> {
+    match node {
+        RispExp::List(list) => Some((Kind::List, list.to_vec())),
+        RispExp::Vector(xs) => Some((Kind::Vector, xs.borrow().clone())),
+        RispExp::Tuple(values) => Some((Kind::Tuple, values.as_ref().clone())),
+        _ => None,
+    }
+}
+
+fn rebuild(kind: Kind, children: Vec<RispExp>) -> RispExp {
+    match kind {
+        Kind::List => RispExp::List(children.into()),
+        Kind::Vector => RispExp::Vector(Rc::new(RefCell::new(children))),
+        Kind::Tuple => RispExp::Tuple(Rc::new(children)),
+    }
+}
+
+fn walk_args(args: &[RispExp]) -> Result<(&RispExp, &RispExp), RispErr> {
+    match args {
+        [f, form] => Ok((f, form)),
+        _ => Err(RispErr::Reason("expected a function and a form".to_string())),
+    }
+}
+
+fn walk(args: &[RispExp], env: &mut RispEnv, pre: bool) -> Result<RispExp, RispErr> {
+    let (f, form) = walk_args(args)?;
+    let mut work = vec![Work::Visit(form.clone())];
+    let mut results: Vec<RispExp> = vec![];
+
+    while let Some(next) = work.pop() {
+        match next {
+            Work::Visit(node) => {
+                let node = if pre { call_exp(f, vec![node], env)? } else { node };
+                match children(&node) {
+                    Some((kind, children)) => {
+                        work.push(Work::Build(kind, children.len()));
+                        work.extend(children.into_iter().rev().map(Work::Visit));
+                    },
+                    None if pre => results.push(node),
+                    None => results.push(call_exp(f, vec![node], env)?),
+                }
+            },
+            Work::Build(kind, n) => {
+                let children = results.split_off(results.len() - n);
+                let node = rebuild(kind, children);
+                results.push(if pre { node } else { call_exp(f, vec![node], env)? });
+            },
+        }
+    }
+
+    Ok(results.pop().expect("walk produced no result"))
+}
+
+// `(prewalk f form)` calls `f` on each node before walking into what it returned
+fn prewalk(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    walk(args, env, true)
+}
+
+// `(postwalk f form)` calls `f` on each node after its children were replaced
+fn postwalk(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    walk(args, env, false)
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert("prewalk".to_string(), RispExp::EnvFunc(prewalk));
+    data.insert("postwalk".to_string(), RispExp::EnvFunc(postwalk));
+}
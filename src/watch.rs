@@ -0,0 +1,90 @@
+// Watchpoints, for following the state of a script. `(watch 'x)` prints a line
+// whenever `x` is bound again, by `def`, `set!` or anything else changing a
+// binding, and `(watch 'x f)` calls `(f 'x old new)` instead, `old` being nil
+// when `x` wasn't bound in that scope before. `(unwatch 'x)` stops watching it.
+//
+// Bindings change where the environment is only borrowed, so each change is
+// noted as it's made and the handlers run once the form making it is done.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use super::{call_exp, output, RispEnv, RispErr, RispExp};
+
+type Change = (String, Option<RispExp>, RispExp);
+
+thread_local! {
+    // the watched names, with the handler of each when it has one
+    static WATCHES: RefCell<HashMap<String, Option<RispExp>>> = RefCell::new(HashMap::new());
+    static CHANGES: RefCell<Vec<Change>> = const { RefCell::new(vec![]) };
+}
+
+// the names watched so far, for `restore` to put back
+pub fn saved() -> HashMap<String, Option<RispExp>> {
+    WATCHES.with(|watches| watches.borrow().clone())
+}
+
+pub fn restore(saved: &HashMap<String, Option<RispExp>>) {
+    WATCHES.with(|watches| watches.borrow_mut().clone_from(saved));
+    CHANGES.with(|changes| changes.borrow_mut().clear());
+}
+
+pub fn is_watched(name: &str) -> bool {
+    WATCHES.with(|watches| watches.borrow().contains_key(name))
+}
+
+// notes that a watched name is being bound to `new`
+pub fn changed(name: &str, old: Option<RispExp>, new: &RispExp) {
+    CHANGES.with(|changes| changes.borrow_mut().push((name.to_string(), old, new.clone())));
+}
+
+// reports the changes noted since the last time
+pub fn fire(env: &mut RispEnv) -> Result<(), RispErr> {
+    let changes = CHANGES.with(|changes| changes.take());
+    for (name, old, new) in changes {
+        let handler = WATCHES.with(|watches| watches.borrow().get(&name).cloned());
+        match (handler, old) {
+            (Some(Some(f)), old) => {
+                let args = vec![RispExp::Symbol(name), old.unwrap_or(RispExp::List(vec![].into())), new];
+                call_exp(&f, args, env)?;
+            },
+            (Some(None), Some(old)) => output::write(&format!("watch: {} = {} (was {})\n", name, new, old))?,
+            (Some(None), None) => output::write(&format!("watch: {} = {}\n", name, new))?,
+            // unwatched since it changed
+            (None, _) => (),
+        }
+    }
+
+    Ok(())
+}
+
+fn name_arg(args: &[RispExp]) -> Result<String, RispErr> {
+    match args.first() {
+        Some(RispExp::Symbol(name)) => Ok(name.clone()),
+        _ => Err(RispErr::Reason("expected a quoted name, like 'x".to_string())),
+    }
+}
+
+// `(watch 'x [f])`
+fn watch(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let name = name_arg(args)?;
+    let handler = match args.get(1..) {
+        Some([]) => None,
+        Some([f @ (RispExp::Func(_) | RispExp::Lambda(_))]) => Some(f.clone()),
+        _ => return Err(RispErr::Reason("expected a name and a fn to call when it changes".to_string())),
+    };
+    WATCHES.with(|watches| watches.borrow_mut().insert(name.clone(), handler));
+
+    Ok(RispExp::Symbol(name))
+}
+
+// `(unwatch 'x)`, whether it was watched
+fn unwatch(args: &[RispExp]) -> Result<RispExp, RispErr> {
+    let name = name_arg(args)?;
+    Ok(RispExp::Bool(WATCHES.with(|watches| watches.borrow_mut().remove(&name)).is_some()))
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert("watch".to_string(), RispExp::Func(watch));
+    data.insert("unwatch".to_string(), RispExp::Func(unwatch));
+}
@@ -0,0 +1,184 @@
+// Zippers over nested lists and vectors. A zipper is a focused node plus the path back up
+// to the root: the siblings on either side of the focus at every level. Moves
+// that would leave the tree return `false` instead of a zipper.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{args, call_exp, RispEnv, RispErr, RispExp};
+
+#[derive(Debug)]
+pub struct Zipper {
+    focus: RispExp,
+    path: Option<Rc<Path>>,
+}
+
+#[derive(Debug)]
+struct Path {
+    left: Vec<RispExp>,
+    right: Vec<RispExp>,
+    // whether the parent is a vector rather than a list
+    vector: bool,
+    up: Option<Rc<Path>>,
+}
+
+impl Zipper {
+    pub fn focus(&self) -> &RispExp {
+        &self.focus
+    }
+
+    fn down(&self) -> Option<Zipper> {
+        let (list, vector) = match &self.focus {
+            RispExp::List(list) => (list.to_vec(), false),
+            RispExp::Vector(xs) => (xs.borrow().clone(), true),
+            _ => return None,
+        };
+        let (first, right) = list.split_first()?;
+
+        Some(Zipper {
+            focus: first.clone(),
+            path: Some(Rc::new(Path { left: vec![], right: right.to_vec(), vector, up: self.path.clone() })),
+        })
+    }
+
+    fn up(&self) -> Option<Zipper> {
+        let path = self.path.as_ref()?;
+        let mut list = path.left.clone();
+        list.push(self.focus.clone());
+        list.extend(path.right.iter().cloned());
+
+        let focus = match path.vector {
+            true => RispExp::Vector(Rc::new(RefCell::new(list))),
+            false => RispExp::List(list.into()),
+        };
+        Some(Zipper { focus, path: path.up.clone() })
+    }
+
+    fn left(&self) -> Option<Zipper> {
+        let path = self.path.as_ref()?;
+        let (focus, left) = path.left.split_last()?;
+        let mut right = vec![self.focus.clone()];
+        right.extend(path.right.iter().cloned());
+
+        Some(Zipper {
+            focus: focus.clone(),
+            path: Some(Rc::new(Path { left: left.to_vec(), right, vector: path.vector, up: path.up.clone() })),
+        })
+    }
+
+    fn right(&self) -> Option<Zipper> {
+        let path = self.path.as_ref()?;
+        let (focus, right) = path.right.split_first()?;
+        let mut left = path.left.clone();
+        left.push(self.focus.clone());
+
+        Some(Zipper {
+            focus: focus.clone(),
+            path: Some(Rc::new(Path { left, right: right.to_vec(), vector: path.vector, up: path.up.clone() })),
+        })
+    }
+
+    fn root(&self) -> RispExp {
+        let mut zipper = Zipper { focus: self.focus.clone(), path: self.path.clone() };
+        while let Some(up) = zipper.up() {
+            zipper = up;
+        }
+
+        zipper.focus
+    }
+}
+
+fn zipper_arg<'a>(op: &str, args: &'a [RispExp]) -> Result<&'a Rc<Zipper>, RispErr> {
+    match args::get(op, args, 0, "a zipper")? {
+        RispExp::Zipper(zipper) => Ok(zipper),
+        exp => Err(args::wrong(op, 0, "a zipper", exp)),
+    }
+}
+
+fn moved(zipper: Option<Zipper>) -> RispExp {
+    zipper
+        .map(|zipper| RispExp::Zipper(Rc::new(zipper)))
+        .unwrap_or(RispExp::Bool(false))
+}
+
+// `(edit z f args...)` replaces the focus with `(f focus args...)`
+fn edit(args: &[RispExp], env: &mut RispEnv) -> Result<RispExp, RispErr> {
+    let zipper = zipper_arg("edit", args)?;
+    let f = args::get("edit", args, 1, "an edit function")?;
+
+    let mut f_args = vec![zipper.focus.clone()];
+    f_args.extend(args[2..].iter().cloned());
+    let focus = call_exp(f, f_args, env)?;
+
+    Ok(RispExp::Zipper(Rc::new(Zipper { focus, path: zipper.path.clone() })))
+}
+
+pub fn add_builtins(data: &mut HashMap<String, RispExp>) {
+    data.insert(
+        "zip".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                let tree = args::get("zip", args, 0, "a tree")?;
+
+                Ok(RispExp::Zipper(Rc::new(Zipper { focus: tree.clone(), path: None })))
+            }
+        )
+    );
+
+    data.insert(
+        "node".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                Ok(zipper_arg("node", args)?.focus.clone())
+            }
+        )
+    );
+
+    data.insert(
+        "down".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                Ok(moved(zipper_arg("down", args)?.down()))
+            }
+        )
+    );
+
+    data.insert(
+        "up".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                Ok(moved(zipper_arg("up", args)?.up()))
+            }
+        )
+    );
+
+    data.insert(
+        "left".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                Ok(moved(zipper_arg("left", args)?.left()))
+            }
+        )
+    );
+
+    data.insert(
+        "right".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                Ok(moved(zipper_arg("right", args)?.right()))
+            }
+        )
+    );
+
+    data.insert(
+        "root".to_string(),
+        RispExp::Func(
+            |args: &[RispExp]| -> Result<RispExp, RispErr> {
+                Ok(zipper_arg("root", args)?.root())
+            }
+        )
+    );
+
+    data.insert("edit".to_string(), RispExp::EnvFunc(edit));
+}
@@ -0,0 +1,75 @@
+// Golden-file tests of the language. Each `tests/cases/name.risp` is evaluated
+// a form at a time in a fresh interpreter, and what happens is compared with
+// `tests/cases/name.expected`: what each form printed, then `=> value`, or
+// `// message` when it failed, the same as the REPL shows them. Warnings are
+// `warning: message` lines.
+//
+// A new case needs its `.expected` written once, checked by hand:
+//
+//     RISP_BLESS=1 cargo test --test golden
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use risp::{split_forms, Interpreter};
+
+fn cases_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("cases")
+}
+
+fn transcript(src: &str) -> String {
+    let mut interpreter = Interpreter::new();
+    let mut out = String::new();
+    for form in split_forms(src).expect("a case that reads") {
+        let outcome = interpreter.eval(&form);
+        out.push_str(&outcome.stdout);
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+        for warning in outcome.warnings {
+            out.push_str(&format!("warning: {}\n", warning));
+        }
+        match outcome.value {
+            Ok(value) => out.push_str(&format!("=> {}\n", value)),
+            Err(e) => out.push_str(&format!("// {}\n", e)),
+        }
+    }
+
+    out
+}
+
+#[test]
+fn cases() {
+    let bless = env::var_os("RISP_BLESS").is_some();
+    let mut cases: Vec<PathBuf> = fs::read_dir(cases_dir())
+        .expect("tests/cases should exist")
+        .map(|entry| entry.expect("a readable entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "risp"))
+        .collect();
+    cases.sort();
+    assert!(!cases.is_empty(), "no cases in tests/cases");
+
+    let mut failures = vec![];
+    for case in &cases {
+        let src = fs::read_to_string(case).expect("a readable case");
+        let actual = transcript(&src);
+        let expected_path = case.with_extension("expected");
+        if bless {
+            fs::write(&expected_path, &actual).expect("a writable .expected file");
+            continue
+        }
+
+        match fs::read_to_string(&expected_path) {
+            Ok(expected) if expected == actual => (),
+            Ok(expected) => failures.push(format!(
+                "{}:\n--- expected\n{}--- got\n{}", case.display(), expected, actual
+            )),
+            Err(_) => failures.push(format!(
+                "{} has no .expected file, write one with RISP_BLESS=1:\n{}", case.display(), actual
+            )),
+        }
+    }
+
+    assert!(failures.is_empty(), "{} of {} cases failed\n\n{}", failures.len(), cases.len(), failures.join("\n"));
+}
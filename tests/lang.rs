@@ -0,0 +1,50 @@
+// Error messages with `--lang`. The builtins' argument errors are translated
+// from what went wrong, not by matching their English message, so every
+// builtin checking its arguments with `args` is covered.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// what the REPL prints for `src` in `lang`, without its prompts
+fn repl(lang: &str, src: &str) -> String {
+    let mut risp = Command::new(env!("CARGO_BIN_EXE_risp"))
+        .args(["--lang", lang])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("the risp binary should run");
+    risp.stdin.take().expect("a stdin").write_all(src.as_bytes()).expect("a writable stdin");
+    let out = risp.wait_with_output().expect("risp to finish");
+
+    String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .filter(|line| *line != "risp >")
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn wrong_arguments() {
+    assert_eq!(
+        repl("es", "(+ 1 \"a\")\n(keys 1)\n"),
+        "// `+` esperaba un número como argumento 2, recibió una cadena \"a\"\n\
+         // `keys` esperaba un mapa como argumento 1, recibió un entero 1"
+    );
+    assert_eq!(
+        repl("fr", "(first)\n"),
+        "// `first` attendait une liste comme argument 1"
+    );
+}
+
+#[test]
+fn argument_counts() {
+    assert_eq!(
+        repl("es", "(assert-arity 'f '(1) 2)\n(assert-arity 'f '(1 2 3) 1 2)\n"),
+        "// `f` esperaba 2 argumentos, recibió 1\n\
+         // `f` esperaba como mucho 2 argumentos, recibió 3"
+    );
+    assert_eq!(
+        repl("fr", "(assert-arity 'f '() 1)\n"),
+        "// `f` attendait 1 argument, a reçu 0"
+    );
+}
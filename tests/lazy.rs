@@ -0,0 +1,28 @@
+// Rarely used builtins are installed a module at a time, only the one that
+// defines the name being looked up. Each test runs on its own thread, which
+// starts with none of them installed.
+
+use risp::Interpreter;
+
+fn lazy_modules(interpreter: &mut Interpreter) -> String {
+    interpreter.eval("(get (session-stats) :lazy-modules)").value.map(|v| v.to_string()).unwrap_or_else(|e| e.to_string())
+}
+
+#[test]
+fn unknown_names_install_nothing() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(lazy_modules(&mut interpreter), "0");
+    assert!(interpreter.eval("(turtel-reset)").value.is_err());
+    assert_eq!(lazy_modules(&mut interpreter), "0");
+}
+
+#[test]
+fn a_name_installs_only_its_module() {
+    let mut interpreter = Interpreter::new();
+    assert!(interpreter.eval("(turtle-reset)").value.is_ok());
+    assert_eq!(lazy_modules(&mut interpreter), "1");
+
+    // the rest of the module came with it
+    assert!(interpreter.eval("(pen-up)").value.is_ok());
+    assert_eq!(lazy_modules(&mut interpreter), "1");
+}
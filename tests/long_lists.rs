@@ -0,0 +1,69 @@
+// Builtins taking a list must loop over it rather than recurse once per
+// element, or a long enough list overflows the stack. These run on the test
+// thread's small stack with lists far longer than it could recurse through.
+
+use risp::{Interpreter, RispExp};
+
+const LEN: usize = 1_000_000;
+
+// `xs` is the numbers from 1 to `LEN`
+fn with_long_list() -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.define("xs", RispExp::List((1..=LEN).map(|n| RispExp::Int(n as i64)).collect()));
+    interpreter
+}
+
+fn eval(interpreter: &mut Interpreter, src: &str) -> String {
+    match interpreter.eval(src).value {
+        Ok(value) => value.to_string(),
+        Err(e) => panic!("{} failed: {:?}", src, e),
+    }
+}
+
+#[test]
+fn comparisons() {
+    let mut interpreter = with_long_list();
+    assert_eq!(eval(&mut interpreter, "(< @xs)"), "true");
+    assert_eq!(eval(&mut interpreter, "(<= @xs)"), "true");
+    assert_eq!(eval(&mut interpreter, "(> @xs)"), "false");
+}
+
+#[test]
+fn reading() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(eval(&mut interpreter, &format!("(= {})", "1 ".repeat(LEN))), "true");
+}
+
+#[test]
+fn arithmetic() {
+    let mut interpreter = with_long_list();
+    assert_eq!(eval(&mut interpreter, "(+ @xs)"), (LEN * (LEN + 1) / 2).to_string());
+}
+
+#[test]
+fn iteration() {
+    let mut interpreter = with_long_list();
+    match interpreter.eval("(for (x xs) x)").value {
+        Ok(RispExp::List(xs)) => assert_eq!(xs.len(), LEN),
+        other => panic!("expected a list, got {:?}", other.map(|v| v.to_string())),
+    }
+    assert_eq!(eval(&mut interpreter, "(empty? (take 3 xs))"), "false");
+}
+
+#[test]
+fn collections() {
+    let mut interpreter = with_long_list();
+    assert_eq!(eval(&mut interpreter, "(empty? (sorted-set @xs))"), "false");
+    assert_eq!(eval(&mut interpreter, "(peek (stack @xs))"), LEN.to_string());
+    assert_eq!(eval(&mut interpreter, "(peek (queue @xs))"), "1");
+    // dropping them unlinks one cell at a time
+    eval(&mut interpreter, "(def xs false)");
+}
+
+#[test]
+fn printing() {
+    let mut interpreter = with_long_list();
+    let printed = eval(&mut interpreter, "xs");
+    assert_eq!(printed.matches(',').count(), LEN - 1);
+    assert_eq!(eval(&mut interpreter, "(empty? (edn-read (edn-write xs)))"), "false");
+}
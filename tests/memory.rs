@@ -0,0 +1,39 @@
+// A builtin that makes something as big as its arguments checks the memory
+// limit before it allocates, with an error handlers can catch. The test binary
+// doesn't count allocations, so only what one call would make is checked.
+
+use risp::{Interpreter, RispErr};
+
+const LIMIT: u64 = 4096;
+
+fn limited() -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval("(def xs (str->list \"0123456789012345678901234567890123456789\"))");
+    interpreter.eval("(def s (apply str-concat (append xs xs xs xs xs xs xs xs xs xs xs xs xs xs xs xs xs xs xs xs xs xs xs xs xs)))");
+    interpreter.set_memory_limit(Some(LIMIT));
+    interpreter
+}
+
+#[test]
+fn big_results_fail_before_allocating() {
+    let mut interpreter = limited();
+    for src in ["(append xs xs xs xs xs xs xs xs xs xs)", "(str-concat s s s s s)"] {
+        match interpreter.eval(src).value {
+            Err(RispErr::Reason(msg)) => assert_eq!(msg, format!("memory limit of {} bytes exceeded", LIMIT)),
+            other => panic!("expected {} to fail, got {:?}", src, other.map(|v| v.to_string())),
+        }
+    }
+
+    // what fits is still made
+    assert_eq!(interpreter.eval("(len (append xs xs))").value.map(|v| v.to_string()).ok(), Some("80".to_string()));
+    assert_eq!(interpreter.eval("(str-len (str-concat s s))").value.map(|v| v.to_string()).ok(), Some("2000".to_string()));
+}
+
+#[test]
+fn going_over_can_be_handled() {
+    let mut interpreter = limited();
+    let handled = interpreter.eval(
+        "(handler-bind (fn (c) (invoke-restart 'use-value :too-big)) (restart-case (append xs xs xs xs xs xs xs xs xs xs)))"
+    );
+    assert_eq!(handled.value.map(|v| v.to_string()).ok(), Some(":too-big".to_string()));
+}
@@ -0,0 +1,57 @@
+// Source text evaluated again is parsed once, the second time its forms come
+// from the cache, and text that's changed since is parsed afresh. Each test
+// runs on its own thread, which starts with an empty cache.
+
+use risp::Interpreter;
+
+const STATS: &str = "(list (get (session-stats) :parse-cache-hits) (get (session-stats) :parse-cache-misses))";
+
+// hits and misses so far, this lookup of `STATS` included
+fn counts(interpreter: &mut Interpreter) -> (i64, i64) {
+    let counts = interpreter.eval(STATS).value.unwrap().to_string();
+    let (hits, misses) = counts.trim_matches(|c| c == '(' || c == ')').split_once(',').unwrap();
+    (hits.parse().unwrap(), misses.parse().unwrap())
+}
+
+#[test]
+fn the_same_source_is_parsed_once() {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval("(def runs 0)");
+    let src = "(set! runs (+ runs 1)) (list :runs runs)";
+    let (hits, misses) = counts(&mut interpreter);
+
+    assert_eq!(interpreter.eval(src).value.unwrap().to_string(), "(:runs,1)");
+    // a miss for `src`, and a hit for looking again
+    assert_eq!(counts(&mut interpreter), (hits + 1, misses + 1));
+
+    // cached forms evaluate just the same
+    assert_eq!(interpreter.eval(src).value.unwrap().to_string(), "(:runs,2)");
+    assert_eq!(counts(&mut interpreter), (hits + 3, misses + 1));
+}
+
+#[test]
+fn changed_source_is_parsed_again() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(interpreter.eval("(+ 1 2)").value.unwrap().to_string(), "3");
+    assert_eq!(interpreter.eval("(+ 1 2)").value.unwrap().to_string(), "3");
+    let (hits, misses) = counts(&mut interpreter);
+
+    assert_eq!(interpreter.eval("(+ 1 20)").value.unwrap().to_string(), "21");
+    assert_eq!(counts(&mut interpreter), (hits + 1, misses + 1));
+
+    // and the text it was before is still cached
+    assert_eq!(interpreter.eval("(+ 1 2)").value.unwrap().to_string(), "3");
+    assert_eq!(counts(&mut interpreter), (hits + 3, misses + 1));
+}
+
+#[test]
+fn registering_a_reader_macro_empties_the_cache() {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval("(+ 1 2)");
+    interpreter.eval("(+ 1 2)");
+    risp::register_reader_macro("answer", |_| Ok(risp::RispExp::Int(42)));
+    let (hits, misses) = counts(&mut interpreter);
+
+    interpreter.eval("(+ 1 2)");
+    assert_eq!(counts(&mut interpreter), (hits + 1, misses + 1));
+}
@@ -0,0 +1,46 @@
+// `risp pkg` passes package sources to git, so one that git would read as an
+// option is refused before git runs. Each test works in its own empty
+// directory.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn project(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("risp-pkg-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("a writable temp dir");
+    dir
+}
+
+// what `risp pkg args...` prints to stderr in `dir`
+fn pkg(dir: &Path, args: &[&str]) -> String {
+    let out = Command::new(env!("CARGO_BIN_EXE_risp"))
+        .arg("pkg")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("the risp binary should run");
+    assert!(!out.status.success());
+
+    String::from_utf8_lossy(&out.stderr).into_owned()
+}
+
+#[test]
+fn add_refuses_options() {
+    let dir = project("add");
+    let err = pkg(&dir, &["add", "--upload-pack=touch pwned"]);
+    assert!(err.contains("'--upload-pack=touch pwned' isn't a url or a package name"), "{}", err);
+    assert!(!dir.join("risp_packages").exists());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn vendor_refuses_locked_options() {
+    let dir = project("vendor");
+    fs::write(dir.join("risp.lock"), "[evil]\nsource = \"--upload-pack=touch pwned\"\nrev = \"abc\"\n").expect("a writable lockfile");
+    let err = pkg(&dir, &["vendor"]);
+    assert!(err.contains("the source and rev of 'evil' can not start with '-'"), "{}", err);
+    assert!(!dir.join("risp_packages").exists());
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,56 @@
+// An interpreter handed back to a `RispPool` is reset before the next request
+// gets it: what the last one defined, in its environment or for the thread,
+// is gone and the prelude's definitions are back.
+
+use risp::RispPool;
+
+#[test]
+fn checkouts_start_from_the_prelude() {
+    let pool = RispPool::with_prelude(1, "(def greeting \"hi\")").unwrap();
+    {
+        let mut interpreter = pool.checkout();
+        assert_eq!(pool.idle(), 0);
+        let outcome = interpreter.eval(concat!(
+            "(def secret 42)",
+            "(set! greeting \"bye\")",
+            "(watch 'greeting (fn (name old new) (print \"changed\")))",
+            "(deftag point (fn (xy) {:x (first xy) :y (nth xy 1)}))",
+            "(defmacro unless (c body) (list 'if c nil body))",
+            "(list (get #point (1 2) :x) (unless false :ran))",
+        ));
+        assert_eq!(outcome.value.unwrap().to_string(), "(1,:ran)");
+
+        let outcome = interpreter.eval("(set! greeting \"again\")");
+        assert_eq!(outcome.stdout, "changed");
+    }
+    assert_eq!(pool.idle(), 1);
+
+    let mut interpreter = pool.checkout();
+    assert_eq!(pool.idle(), 0);
+    assert!(interpreter.eval("secret").value.is_err());
+    assert_eq!(interpreter.eval("greeting").value.unwrap().to_string(), "\"hi\"");
+
+    // the watch handler is gone
+    let outcome = interpreter.eval("(set! greeting \"hello\")");
+    assert!(outcome.value.is_ok());
+    assert_eq!(outcome.stdout, "");
+
+    // and so is the tag reader, the literal stays tagged
+    assert_eq!(interpreter.eval("#point (1 2)").value.unwrap().to_string(), "#point (1,2)");
+
+    // and the macro
+    assert!(interpreter.eval("(unless false :ran)").value.is_err());
+}
+
+#[test]
+fn the_prelude_keeps_what_it_registers() {
+    let pool = RispPool::with_prelude(1, "(deftag point (fn (xy) (first xy)))").unwrap();
+    {
+        let mut interpreter = pool.checkout();
+        assert!(interpreter.eval("(deftag point (fn (xy) (nth xy 1)))").value.is_ok());
+        assert_eq!(interpreter.eval("#point (1 2)").value.unwrap().to_string(), "2");
+    }
+
+    let mut interpreter = pool.checkout();
+    assert_eq!(interpreter.eval("#point (1 2)").value.unwrap().to_string(), "1");
+}
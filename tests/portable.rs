@@ -0,0 +1,94 @@
+// Values detached from one interpreter as `Portable`s can be sent to another
+// thread, attached to an interpreter there and sent back again, coming back
+// equal to what was sent, which `sexp-diff` finds no differences in.
+
+use std::sync::mpsc;
+use std::thread;
+
+use risp::{Interpreter, Portable};
+
+fn value(interpreter: &mut Interpreter, src: &str) -> risp::RispExp {
+    interpreter.eval(src).value.unwrap_or_else(|e| panic!("{}: {}", src, e))
+}
+
+fn show(interpreter: &mut Interpreter, src: &str) -> String {
+    match interpreter.eval(src).value {
+        Ok(v) => v.to_string(),
+        Err(e) => e.to_string(),
+    }
+}
+
+// sends `portable` to a new thread, which attaches it, checks it with
+// `check` there and sends it back detached again
+fn round_trip(portable: Portable, check: &'static str) -> (Portable, String) {
+    let (to_thread, from_here) = mpsc::channel::<Portable>();
+    let (to_here, from_thread) = mpsc::channel::<(Portable, String)>();
+    let worker = thread::spawn(move || {
+        let mut interpreter = Interpreter::new();
+        let value = from_here.recv().unwrap().attach();
+        interpreter.define("it", value.clone());
+        let checked = show(&mut interpreter, check);
+        to_here.send((Portable::detach(&value).unwrap(), checked)).unwrap();
+    });
+
+    to_thread.send(portable).unwrap();
+    let res = from_thread.recv().unwrap();
+    worker.join().unwrap();
+    res
+}
+
+#[test]
+fn nested_collections_come_back_equal() {
+    let mut interpreter = Interpreter::new();
+    let original = value(&mut interpreter, concat!(
+        "(list 1 2.5 \"three\" :four 'five #\\6 true",
+        " [1 (list 2 [3])] {:a (list 1 2) :b {:c [\"d\"]}} (sorted-map :z 1 :y (list 2))",
+        " (sorted-set 3 1 2) (values 1 :two) #unknown (1 2))",
+    ));
+    interpreter.define("original", original.clone());
+
+    let (back, there) = round_trip(Portable::detach(&original).unwrap(), "(list (get (nth it 8) :b) (nth it 7))");
+    assert_eq!(there, "({:c [\"d\"]},[1,(2,[3])])");
+
+    interpreter.define("back", back.attach());
+    assert_eq!(show(&mut interpreter, "(sexp-diff original back)"), "nil");
+    assert_eq!(show(&mut interpreter, "back"), original.to_string());
+}
+
+#[test]
+fn lambdas_come_back_with_what_they_closed_over() {
+    let mut interpreter = Interpreter::new();
+    let original = value(&mut interpreter, "(let ((k 10) (xs (list 1 2))) (fn (x) (cons (* x k) xs)))");
+    interpreter.define("original", original.clone());
+
+    let (back, there) = round_trip(Portable::detach(&original).unwrap(), "(map (fn (x) (it x)) (list 1 2))");
+    assert_eq!(there, "((10,1,2),(20,1,2))");
+
+    interpreter.define("back", back.attach());
+    assert_eq!(show(&mut interpreter, "(back 3)"), "(30,1,2)");
+    assert_eq!(show(&mut interpreter, "(sexp-diff (original 4) (back 4))"), "nil");
+
+    // builtins go as they are
+    let (back, there) = round_trip(Portable::detach(&value(&mut interpreter, "map")).unwrap(), "(it (fn (x) (+ x 1)) (list 1))");
+    assert_eq!(there, "(2)");
+    interpreter.define("back", back.attach());
+    assert_eq!(show(&mut interpreter, "(back (fn (x) (* x 2)) (list 2))"), "(4)");
+}
+
+#[test]
+fn values_tied_to_their_thread_can_not_be_detached() {
+    let mut interpreter = Interpreter::new();
+    for src in [
+        "(generator (fn () (yield 1)))",
+        "(current-env)",
+        "(list 1 (generator (fn () (yield 1))))",
+        "{:gen (generator (fn () (yield 1)))}",
+        "(let ((g (generator (fn () (yield 1))))) (fn () (take 1 g)))",
+    ] {
+        let exp = value(&mut interpreter, src);
+        match Portable::detach(&exp) {
+            Err(e) => assert!(e.to_string().contains("can not be moved to another interpreter"), "{}: {}", src, e),
+            Ok(_) => panic!("{} was detached", src),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+// Embedders extending the reader: a reader macro changes what `#name form`
+// reads as, a tag reader what a `#tag form` literal evaluates to.
+
+use risp::{register_reader_macro, register_tag_reader, Interpreter, RispErr, RispExp};
+
+fn eval(interpreter: &mut Interpreter, src: &str) -> String {
+    match interpreter.eval(src).value {
+        Ok(value) => value.to_string(),
+        Err(e) => format!("// {}", e),
+    }
+}
+
+#[test]
+fn reader_macro() {
+    register_reader_macro("upper", |form| match form {
+        RispExp::Str(s) => Ok(RispExp::Str(s.to_uppercase())),
+        _ => Err(RispErr::Reason("expected a string after #upper".to_string())),
+    });
+
+    let mut interpreter = Interpreter::new();
+    assert_eq!(eval(&mut interpreter, "#upper \"abc\""), "\"ABC\"");
+    // it's read before anything is evaluated, quoted or not
+    assert_eq!(eval(&mut interpreter, "'(#upper \"x\")"), "(\"X\")");
+    assert_eq!(eval(&mut interpreter, "#upper 1"), "// expected a string after #upper");
+}
+
+#[test]
+fn tag_reader() {
+    fn point(args: &[RispExp]) -> Result<RispExp, RispErr> {
+        match args {
+            [RispExp::List(xy)] if xy.len() == 2 => Ok(RispExp::List(vec![RispExp::Keyword(":point".to_string()), xy[0].clone(), xy[1].clone()].into())),
+            _ => Err(RispErr::Reason("expected (x y) after #point".to_string())),
+        }
+    }
+
+    let mut interpreter = Interpreter::new();
+    // without a reader it stays a tagged value
+    assert_eq!(eval(&mut interpreter, "#point (1 2)"), "#point (1,2)");
+
+    register_tag_reader("point", RispExp::Func(point));
+    assert_eq!(eval(&mut interpreter, "#point (1 2)"), "(:point,1,2)");
+    assert_eq!(eval(&mut interpreter, "#point (1)"), "// expected (x y) after #point");
+}
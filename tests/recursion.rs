@@ -0,0 +1,30 @@
+// Evaluation recurses in Rust, so recursion in risp is stopped with an error
+// once it's used the stack limit, rather than overflowing the thread's stack
+// and aborting. These run on the test thread's stack, with the default limit.
+
+use risp::{Interpreter, RispErr};
+
+const REC: &str = "(defn rec (n) (if (= n 0) 0 (+ 1 (rec (- n 1)))))";
+
+#[test]
+fn deep_recursion_fails() {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval(REC);
+    match interpreter.eval("(rec 100000)").value {
+        Err(RispErr::Reason(msg)) => assert!(msg.starts_with("recursion too deep"), "{}", msg),
+        other => panic!("expected an error, got {:?}", other.map(|v| v.to_string())),
+    }
+
+    // the interpreter is fine afterwards, and shallow recursion still works
+    assert_eq!(interpreter.eval("(rec 10)").value.map(|v| v.to_string()).ok(), Some("10".to_string()));
+}
+
+#[test]
+fn deep_recursion_can_be_handled() {
+    let mut interpreter = Interpreter::new();
+    interpreter.eval(REC);
+    let handled = interpreter.eval(
+        "(handler-bind (fn (c) (invoke-restart 'use-value :too-deep)) (restart-case (rec 100000)))"
+    );
+    assert_eq!(handled.value.map(|v| v.to_string()).ok(), Some(":too-deep".to_string()));
+}
@@ -0,0 +1,122 @@
+// `risp repl --remote` over loopback: clients have to authenticate first when
+// the server has a token, and each line they send gets one reply holding what
+// its forms printed and the result of every one of them.
+
+use std::io::{BufRead, BufReader, Lines, Write};
+use std::net::TcpStream;
+use std::process::{Child, ChildStderr, Command, Stdio};
+
+const TOKEN: &str = "s3cret";
+
+struct Server {
+    child: Child,
+    addr: String,
+    // kept open, the server fails writing its warnings to a closed pipe
+    _stderr: BufReader<ChildStderr>,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// a server on a free port, once it's listening
+fn serve(token: Option<&str>) -> Server {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_risp"));
+    command.args(["repl", "--remote", "--addr", "127.0.0.1:0"]).env_remove("RISP_REMOTE_TOKEN");
+    if let Some(token) = token {
+        command.args(["--token", token]);
+    }
+    let mut child = command.stderr(Stdio::piped()).spawn().expect("the risp binary should run");
+
+    let mut stderr = BufReader::new(child.stderr.take().expect("a stderr"));
+    let mut line = String::new();
+    stderr.read_line(&mut line).expect("the server to say where it's listening");
+    let addr = line.trim().rsplit(' ').next().expect("an address").to_string();
+
+    Server { child, addr, _stderr: stderr }
+}
+
+struct Client {
+    writer: TcpStream,
+    lines: Lines<BufReader<TcpStream>>,
+}
+
+impl Client {
+    fn connect(server: &Server) -> Client {
+        let stream = TcpStream::connect(&server.addr).expect("the server to accept");
+        Client { writer: stream.try_clone().unwrap(), lines: BufReader::new(stream).lines() }
+    }
+
+    // the reply to `line`, with its escaped newlines read back, or None when
+    // the server hung up
+    fn send(&mut self, line: &str) -> Option<String> {
+        writeln!(self.writer, "{}", line).ok()?;
+        let reply = self.lines.next()?.ok()?;
+        Some(reply.replace("\\n", "\n"))
+    }
+}
+
+#[test]
+fn every_form_on_a_line_is_evaluated() {
+    let server = serve(Some(TOKEN));
+    let mut client = Client::connect(&server);
+    assert_eq!(client.send(&format!("auth {}", TOKEN)).unwrap(), "// authenticated");
+
+    assert_eq!(client.send("(def x 1) (+ x 1)").unwrap(), "=> x\n=> 2");
+    // a form that fails doesn't stop the ones after it
+    let reply = client.send("(car (list)) (+ x 2)").unwrap();
+    let lines: Vec<&str> = reply.lines().collect();
+    assert!(lines[0].starts_with("// "), "{}", reply);
+    assert_eq!(lines[1..], ["=> 3"]);
+
+    // text that doesn't parse is a single error
+    let reply = client.send("(+ 1").unwrap();
+    assert!(reply.starts_with("// ") && reply.lines().count() == 1, "{}", reply);
+}
+
+#[test]
+fn printed_output_comes_back_in_the_reply() {
+    let server = serve(Some(TOKEN));
+    let mut client = Client::connect(&server);
+    client.send(&format!("auth {}", TOKEN)).unwrap();
+
+    assert_eq!(client.send("(print \"a\") (println \"b\") (+ 1 1)").unwrap(), "ab\n=> true\n=> true\n=> 2");
+    assert_eq!(client.send("(warn \"careful\") 3").unwrap(), "warning: careful\n=> true\n=> 3");
+
+    // what one reply printed doesn't show up in the next
+    assert_eq!(client.send("4").unwrap(), "=> 4");
+}
+
+#[test]
+fn connections_share_the_environment() {
+    let server = serve(None);
+    let mut first = Client::connect(&server);
+    assert_eq!(first.send("(def shared 41)").unwrap(), "=> shared");
+
+    let mut second = Client::connect(&server);
+    assert_eq!(second.send("(+ shared 1)").unwrap(), "=> 42");
+}
+
+#[test]
+fn clients_without_the_token_are_refused() {
+    let server = serve(Some(TOKEN));
+
+    let mut wrong = Client::connect(&server);
+    assert_eq!(wrong.send("auth guess").unwrap(), "// authentication failed");
+    assert_eq!(wrong.send("(def pwned 1)"), None);
+
+    // a form instead of the auth line is refused without being evaluated
+    let mut none = Client::connect(&server);
+    assert_eq!(none.send("(def pwned 1)").unwrap(), "// authentication failed");
+
+    let mut shorter = Client::connect(&server);
+    assert_eq!(shorter.send("auth s3cre").unwrap(), "// authentication failed");
+
+    let mut right = Client::connect(&server);
+    assert_eq!(right.send(&format!("auth {}", TOKEN)).unwrap(), "// authenticated");
+    let reply = right.send("pwned").unwrap();
+    assert!(reply.starts_with("// unexpected symbol"), "{}", reply);
+}
@@ -0,0 +1,32 @@
+// What the REPL shows. Output a form prints goes on its own lines, before the
+// result or error, even when it doesn't end with a newline.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// what the REPL prints for `src`, without its prompts
+fn repl(src: &str) -> String {
+    let mut risp = Command::new(env!("CARGO_BIN_EXE_risp"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("the risp binary should run");
+    risp.stdin.take().expect("a stdin").write_all(src.as_bytes()).expect("a writable stdin");
+    let out = risp.wait_with_output().expect("risp to finish");
+
+    String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .filter(|line| *line != "risp >")
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn printed_output_ends_its_line() {
+    assert_eq!(
+        repl("(print \"side\")\n(println \"full\")\n(begin (print \"a\") (car 5))\n"),
+        "side\n=> true\n\
+         full\n=> true\n\
+         a\n// `car` expected a list as argument 1, got an int 5"
+    );
+}
@@ -0,0 +1,107 @@
+// With a root set the file builtins can't reach anything outside it, neither
+// reading a file that's there nor creating a new one. Each test has its own
+// `root` directory with a `secret` file beside it, outside.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use risp::Interpreter;
+
+fn dirs(name: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("risp-sandbox-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let root = dir.join("root");
+    fs::create_dir_all(&root).expect("a writable temp dir");
+    fs::write(root.join("inside.txt"), "inside").expect("a writable temp dir");
+    fs::write(dir.join("secret"), "secret").expect("a writable temp dir");
+    (dir, root)
+}
+
+fn confined(root: &Path) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_fs_root(root).expect("the root to exist");
+    interpreter
+}
+
+// reading and creating `path` are both refused, and nothing outside is written
+fn assert_refused(interpreter: &mut Interpreter, dir: &Path, path: &str) {
+    let read = interpreter.eval(&format!("(slurp {:?})", path)).value;
+    match read {
+        Err(e) => assert!(e.to_string().contains("is outside of the directory scripts can access"), "{}", e),
+        Ok(v) => panic!("reading {:?} gave {}", path, v),
+    }
+
+    let new = format!("{}/new.txt", path);
+    let write = interpreter.eval(&format!("(spit {:?} \"pwned\")", new)).value;
+    match write {
+        Err(e) => assert!(e.to_string().contains("is outside of the directory scripts can access"), "{}", e),
+        Ok(v) => panic!("creating {:?} gave {}", new, v),
+    }
+    assert_eq!(fs::read_to_string(dir.join("secret")).unwrap(), "secret");
+    assert!(!dir.join("new.txt").exists());
+}
+
+#[test]
+fn paths_inside_the_root_are_allowed() {
+    let (dir, root) = dirs("inside");
+    let mut interpreter = confined(&root);
+    assert_eq!(interpreter.eval("(slurp \"inside.txt\")").value.unwrap().to_string(), "\"inside\"");
+    assert!(interpreter.eval("(spit \"./made.txt\" \"made\")").value.is_ok());
+    assert_eq!(fs::read_to_string(root.join("made.txt")).unwrap(), "made");
+
+    let absolute = root.canonicalize().unwrap().join("inside.txt");
+    let read = interpreter.eval(&format!("(slurp {:?})", absolute.display().to_string())).value;
+    assert_eq!(read.unwrap().to_string(), "\"inside\"");
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn dot_dot_escapes_are_refused() {
+    let (dir, root) = dirs("dotdot");
+    let mut interpreter = confined(&root);
+    assert_refused(&mut interpreter, &dir, "../secret");
+    assert_refused(&mut interpreter, &dir, "../..");
+    assert_refused(&mut interpreter, &dir, "./../secret");
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn absolute_paths_outside_the_root_are_refused() {
+    let (dir, root) = dirs("absolute");
+    let mut interpreter = confined(&root);
+    let secret = dir.canonicalize().unwrap().join("secret");
+    assert_refused(&mut interpreter, &dir, &secret.display().to_string());
+    assert_refused(&mut interpreter, &dir, &dir.display().to_string());
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks_pointing_outside_are_refused() {
+    let (dir, root) = dirs("symlink");
+    std::os::unix::fs::symlink(dir.join("secret"), root.join("link")).unwrap();
+    std::os::unix::fs::symlink(&dir, root.join("up")).unwrap();
+    let mut interpreter = confined(&root);
+    assert_refused(&mut interpreter, &dir, "link");
+    assert_refused(&mut interpreter, &dir, "up/secret");
+    assert_refused(&mut interpreter, &dir, "up");
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[cfg(unix)]
+#[test]
+fn new_files_under_missing_directories_outside_are_refused() {
+    let (dir, root) = dirs("missing");
+    std::os::unix::fs::symlink(&dir, root.join("up")).unwrap();
+    let mut interpreter = confined(&root);
+    assert_refused(&mut interpreter, &dir, "../missing/deeper");
+    assert_refused(&mut interpreter, &dir, "missing/../../deeper");
+    assert_refused(&mut interpreter, &dir, "up/missing");
+
+    // one inside is fine, if the builtin makes the directories
+    assert!(interpreter.eval("(emit-file \"missing/deeper/new.txt\" \"made\")").value.is_ok());
+    assert!(root.join("missing/deeper/new.txt").exists());
+    assert!(interpreter.eval("(emit-file \"up/missing/new.txt\" \"pwned\")").value.is_err());
+    assert!(!dir.join("missing").exists());
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,27 @@
+// `split_forms` finds where each top-level form ends with the reader, so the
+// text of a form comes out whole whatever's inside it.
+
+use risp::split_forms;
+
+fn split(src: &str) -> Vec<String> {
+    split_forms(src).expect("source that reads")
+}
+
+#[test]
+fn atoms_and_lists() {
+    assert_eq!(split("1 foo\n(+ 1 2) :k"), vec!["1", "foo", "(+ 1 2)", ":k"]);
+    assert_eq!(split("[1 [2 3]] {:a 1}\n#{1 2}"), vec!["[1 [2 3]]", "{:a 1}", "#{1 2}"]);
+}
+
+#[test]
+fn delimiters_inside_forms() {
+    assert_eq!(split(r#"(str "a \" ) b") 2"#), vec![r#"(str "a \" ) b")"#, "2"]);
+    assert_eq!(split(r"(list #\( #\)) 3"), vec![r"(list #\( #\))", "3"]);
+    assert_eq!(split("(+ 1 ; a ) in a comment\n 2) 4"), vec!["(+ 1 ; a ) in a comment\n 2)", "4"]);
+}
+
+#[test]
+fn comments_and_quotes() {
+    assert_eq!(split("; a comment (\n'(1 2)\n`(a ,b) ; after"), vec!["'(1 2)", "`(a ,b)"]);
+    assert!(split_forms("(+ 1 2").is_err());
+}